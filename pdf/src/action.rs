@@ -0,0 +1,226 @@
+use crate::destination::Destination;
+use crate::error::{PdfError, Result};
+use crate::geom::coordinate::Point;
+use crate::geom::rect::Rect;
+use crate::object::dictionary::PdfDict;
+use crate::object::{ObjectId, PdfObject};
+use crate::page::annotation::{Annotation, AnnotationKind};
+use crate::xref::Xref;
+
+// action of a link or an outline item
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    // with is_map the position of the mouse is added to the uri
+    Uri {
+        uri: String,
+        is_map: bool,
+    },
+    // destination is None if the named destination is not defined
+    GoTo {
+        destination: Option<Destination>,
+        name: Option<String>,
+    },
+    // destination in another file, its page is the page index in that file,
+    // named destinations of the file are not resolved
+    GoToR {
+        file: Option<String>,
+        destination: Option<Destination>,
+        name: Option<String>,
+        new_window: Option<bool>,
+    },
+    Launch {
+        file: Option<String>,
+        parameters: Option<String>,
+        new_window: Option<bool>,
+    },
+    // NextPage, PrevPage, FirstPage and LastPage, or a viewer specific name
+    Named {
+        name: String,
+    },
+    Other {
+        subtype: String,
+    },
+}
+
+impl Action {
+    // resolve map a destination of the document to its page and view
+    pub(crate) fn try_new<F>(dict: &PdfDict, xref: &Xref, resolve: &F) -> Result<Self>
+    where
+        F: Fn(&PdfObject) -> Result<Option<Destination>>,
+    {
+        let subtype = match dict.get("S") {
+            Some(s) => xref.read_object(s)?.as_name()?.name().to_string(),
+            None => {
+                return Err(PdfError::DocumentStructure(
+                    "action dictionary has no S".to_string(),
+                ))
+            }
+        };
+        let new_window = match dict.get("NewWindow") {
+            Some(n) => Some(xref.read_object(n)?.as_bool()?.0),
+            None => None,
+        };
+        match subtype.as_str() {
+            "URI" => {
+                let uri = match dict.get("URI") {
+                    Some(uri) => xref.read_object(uri)?.string_bytes()?,
+                    None => {
+                        return Err(PdfError::DocumentStructure(
+                            "URI action has no URI".to_string(),
+                        ))
+                    }
+                };
+                let is_map = match dict.get("IsMap") {
+                    Some(m) => xref.read_object(m)?.as_bool()?.0,
+                    None => false,
+                };
+                Ok(Action::Uri {
+                    uri: String::from_utf8_lossy(&uri).into_owned(),
+                    is_map,
+                })
+            }
+            "GoTo" => {
+                let dest = dict.get("D").ok_or(PdfError::DocumentStructure(
+                    "GoTo action has no D".to_string(),
+                ))?;
+                Ok(Action::GoTo {
+                    destination: resolve(dest)?,
+                    name: destination_name(dest, xref)?,
+                })
+            }
+            "GoToR" => {
+                let mut destination = None;
+                let mut name = None;
+                if let Some(dest) = dict.get("D") {
+                    match xref.read_object(dest)? {
+                        PdfObject::Array(arr) => {
+                            destination = Some(Destination::try_new(&arr, |_| None)?)
+                        }
+                        _ => name = destination_name(dest, xref)?,
+                    }
+                }
+                Ok(Action::GoToR {
+                    file: file_specification(dict.get("F"), xref)?,
+                    destination,
+                    name,
+                    new_window,
+                })
+            }
+            "Launch" => {
+                // the file may be only in the platform dictionary
+                let mut file = file_specification(dict.get("F"), xref)?;
+                let mut parameters = None;
+                if let Some(win) = dict.get("Win") {
+                    let win = xref.read_object(win)?.to_dict()?;
+                    if file.is_none() {
+                        file = file_specification(win.get("F"), xref)?;
+                    }
+                    if let Some(p) = win.get("P") {
+                        parameters = Some(xref.read_object(p)?.as_text_string()?);
+                    }
+                }
+                Ok(Action::Launch {
+                    file,
+                    parameters,
+                    new_window,
+                })
+            }
+            "Named" => {
+                let name = dict.get("N").ok_or(PdfError::DocumentStructure(
+                    "Named action has no N".to_string(),
+                ))?;
+                Ok(Action::Named {
+                    name: xref.read_object(name)?.as_name()?.name().to_string(),
+                })
+            }
+            _ => Ok(Action::Other { subtype }),
+        }
+    }
+}
+
+// name of a named destination, None for explicit destinations
+fn destination_name(dest: &PdfObject, xref: &Xref) -> Result<Option<String>> {
+    match xref.read_object(dest)? {
+        PdfObject::Name(n) => Ok(Some(n.name().to_string())),
+        d @ (PdfObject::LiteralString(_) | PdfObject::HexString(_)) => {
+            Ok(Some(d.as_text_string()?))
+        }
+        _ => Ok(None),
+    }
+}
+
+// file name of a string or a file specification dictionary, /UF is preferred
+fn file_specification(spec: Option<&PdfObject>, xref: &Xref) -> Result<Option<String>> {
+    let spec = match spec {
+        Some(spec) => xref.read_object(spec)?,
+        None => return Ok(None),
+    };
+    match spec {
+        PdfObject::Dict(d) => {
+            for key in ["UF", "F", "Unix", "DOS", "Mac"] {
+                if let Some(f) = d.get(key) {
+                    return Ok(Some(xref.read_object(f)?.as_text_string()?));
+                }
+            }
+            Ok(None)
+        }
+        s => Ok(Some(s.as_text_string()?)),
+    }
+}
+
+// link annotation of a page with its action, /Dest of the link is a GoTo action
+#[derive(Debug, Clone)]
+pub struct Link {
+    id: Option<ObjectId>,
+    rect: Rect,
+    quad_points: Vec<[Point; 4]>,
+    action: Option<Action>,
+}
+
+impl Link {
+    // None if the annotation is not a link
+    pub(crate) fn try_new<F>(annot: &Annotation, xref: &Xref, resolve: &F) -> Result<Option<Self>>
+    where
+        F: Fn(&PdfObject) -> Result<Option<Destination>>,
+    {
+        let (destination, action) = match annot.kind() {
+            AnnotationKind::Link {
+                destination,
+                action,
+                ..
+            } => (destination, action),
+            _ => return Ok(None),
+        };
+        let action = match (action, destination) {
+            (Some(action), _) => Some(Action::try_new(action, xref, resolve)?),
+            (None, Some(dest)) => Some(Action::GoTo {
+                destination: resolve(dest)?,
+                name: destination_name(dest, xref)?,
+            }),
+            (None, None) => None,
+        };
+        Ok(Some(Link {
+            id: annot.id(),
+            rect: annot.rect().to_owned(),
+            quad_points: annot.quad_points().to_vec(),
+            action,
+        }))
+    }
+
+    pub fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    pub fn rect(&self) -> &Rect {
+        &self.rect
+    }
+
+    // regions of the link inside rect, empty if the whole rect is the link
+    pub fn quad_points(&self) -> &[[Point; 4]] {
+        self.quad_points.as_slice()
+    }
+
+    pub fn action(&self) -> Option<&Action> {
+        self.action.as_ref()
+    }
+}
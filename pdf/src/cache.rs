@@ -0,0 +1,83 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+// bounded cache, evict the least recently used entry when full.
+// recency is kept in an ordered map, get and insert are O(log n)
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    // tick of last use to key, the first one is the least recently used
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let (v, t) = self.entries.get_mut(key)?;
+        // already the most recently used, e.g. repeated reads of one chunk
+        if *t != self.tick {
+            self.order.remove(t);
+            self.tick += 1;
+            *t = self.tick;
+            self.order.insert(self.tick, key.to_owned());
+        }
+        Some(v)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some((_, t)) = self.entries.remove(&key) {
+            self.order.remove(&t);
+        } else if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key.to_owned());
+        self.entries.insert(key, (value, self.tick));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruCache;
+
+    #[test]
+    fn test_lru_cache() {
+        let mut cache = LruCache::new(2);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        assert_eq!(cache.get(&1), Some(&"a"));
+        cache.insert(3, "c");
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&1), Some(&"a"));
+        assert_eq!(cache.get(&3), Some(&"c"));
+
+        // insert an existing key makes it the most recently used
+        cache.insert(1, "d");
+        cache.insert(4, "e");
+        assert_eq!(cache.get(&3), None);
+        assert_eq!(cache.get(&1), Some(&"d"));
+        assert_eq!(cache.get(&4), Some(&"e"));
+        cache.clear();
+        assert_eq!(cache.get(&1), None);
+    }
+}
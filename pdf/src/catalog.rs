@@ -1,5 +1,6 @@
 use crate::error::Result;
 use crate::object::dictionary::PdfDict;
+use crate::object::{ObjectId, PdfObject};
 use crate::pagetree::{PageNodeRef, PageTree};
 use crate::xref::Xref;
 
@@ -51,6 +52,21 @@ impl Catalog {
         Ok(catalog)
     }
 
+    // catalog of a linearized file with only the first page loaded
+    pub fn try_new_first_page(
+        root: PdfDict,
+        page: &PdfObject,
+        index: u32,
+        xref: &Xref,
+    ) -> Result<Self> {
+        let mut catalog = Catalog::default();
+        if let Some(pl) = root.get("PageLayout") {
+            catalog.page_layout = PageLayout::new(pl.as_name()?.name());
+        }
+        catalog.page_tree = PageTree::try_new_single_page(page, index, xref)?;
+        Ok(catalog)
+    }
+
     pub fn get_page(&self, pageindex: &u32) -> Option<&PageNodeRef> {
         self.page_tree.get_page(pageindex)
     }
@@ -58,4 +74,12 @@ impl Catalog {
     pub fn total_page(&self) -> Result<u32> {
         self.page_tree.count()
     }
+
+    pub fn page_index(&self, id: &ObjectId) -> Option<u32> {
+        self.page_tree.page_index(id)
+    }
+
+    pub fn outlines(&self) -> Option<&PdfDict> {
+        self.outlines.as_ref()
+    }
 }
@@ -34,7 +34,6 @@ impl Pattern {
             .as_number()
             .map_err(|_| PdfError::Color("Color error".to_string()))?
             .integer();
-        println!("{:?}", pt);
         match pt {
             1 => {
                 let p = TilingPattern::try_new(obj, xref)?;
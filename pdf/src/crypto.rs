@@ -49,34 +49,39 @@ use aes::cipher::block_padding::{NoPadding, ZeroPadding};
 use aes::cipher::generic_array::GenericArray;
 use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
 
-pub fn aes128_decrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
-    let data = data.to_vec();
-    let iv = data[..16].to_vec();
-    let mut data = data[16..].to_vec();
-    let key = GenericArray::clone_from_slice(key.into());
-    let giv = GenericArray::from_slice(iv.as_slice());
-    let _pt = Aes128CbcDec::new(&key, &giv)
+use crate::error::{PdfError, Result};
+
+pub fn aes128_decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let (iv, data) = split_iv(data)?;
+    let mut data = data.to_vec();
+    let key = GenericArray::clone_from_slice(key);
+    let giv = GenericArray::from_slice(iv);
+    let pt = Aes128CbcDec::new(&key, giv)
         .decrypt_padded_mut::<Pkcs7>(&mut data)
-        .unwrap();
-    let last = data.last().unwrap().to_owned();
-    let decrypt_length = data.len() - last as usize;
-    let decrypt_data = data[..decrypt_length].to_vec();
-    return decrypt_data;
+        .map_err(PdfError::Aes)?;
+    Ok(pt.to_vec())
 }
 
-pub fn aes256_decrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
-    let data = data.to_vec();
-    let iv = data[..16].to_vec();
-    let mut data = data[16..].to_vec();
-    let key = GenericArray::clone_from_slice(key.into());
-    let giv = GenericArray::from_slice(iv.as_slice());
-    let _pt = Aes256CbcDec::new(&key, &giv)
+pub fn aes256_decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let (iv, data) = split_iv(data)?;
+    let mut data = data.to_vec();
+    let key = GenericArray::clone_from_slice(key);
+    let giv = GenericArray::from_slice(iv);
+    let pt = Aes256CbcDec::new(&key, giv)
         .decrypt_padded_mut::<Pkcs7>(&mut data)
-        .unwrap();
-    let last = data.last().unwrap().to_owned();
-    let decrypt_length = data.len() - last as usize;
-    let decrypt_data = data[..decrypt_length].to_vec();
-    return decrypt_data;
+        .map_err(PdfError::Aes)?;
+    Ok(pt.to_vec())
+}
+
+// the first 16 bytes of aes encrypted data is the initialization vector
+fn split_iv(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    if data.len() < 16 {
+        return Err(PdfError::File(format!(
+            "aes encrypted data length {} is shorter than iv",
+            data.len()
+        )));
+    }
+    Ok(data.split_at(16))
 }
 
 pub fn aes_cbc_encrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
@@ -90,13 +95,13 @@ pub fn aes_cbc_encrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
         .unwrap();
     return data;
 }
-pub fn aes_cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8> {
+pub fn aes_cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>> {
     assert_eq!(key.len(), 32);
     let mut data = data.to_vec();
-    let key = GenericArray::clone_from_slice(key.into());
-    let giv = GenericArray::from_slice(iv.into());
-    let _pt = Aes256CbcDec::new(&key, &giv)
-        .decrypt_padded_mut::<ZeroPadding>(&mut data)
-        .unwrap();
-    return data;
+    let key = GenericArray::clone_from_slice(key);
+    let giv = GenericArray::from_slice(iv);
+    let pt = Aes256CbcDec::new(&key, giv)
+        .decrypt_padded_mut::<NoPadding>(&mut data)
+        .map_err(PdfError::Aes)?;
+    Ok(pt.to_vec())
 }
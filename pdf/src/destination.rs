@@ -0,0 +1,146 @@
+use crate::error::{PdfError, Result};
+use crate::object::array::PdfArray;
+use crate::object::{ObjectId, PdfObject};
+
+// how the page is shown, None means keep the current value of the viewer
+#[derive(Debug, Clone, PartialEq)]
+pub enum DestinationView {
+    Xyz {
+        left: Option<f32>,
+        top: Option<f32>,
+        zoom: Option<f32>,
+    },
+    Fit,
+    FitH {
+        top: Option<f32>,
+    },
+    FitV {
+        left: Option<f32>,
+    },
+    FitR {
+        left: f32,
+        bottom: f32,
+        right: f32,
+        top: f32,
+    },
+    FitB,
+    FitBH {
+        top: Option<f32>,
+    },
+    FitBV {
+        left: Option<f32>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Destination {
+    page: Option<u32>,
+    view: DestinationView,
+}
+
+impl Destination {
+    // explicit destination [page /XYZ left top zoom], page_index map page object to page index,
+    // page is an integer in destination to other documents
+    pub fn try_new<F>(arr: &PdfArray, page_index: F) -> Result<Self>
+    where
+        F: Fn(&ObjectId) -> Option<u32>,
+    {
+        let page = match arr.get(0) {
+            Some(PdfObject::Indirect(id)) => page_index(id),
+            Some(PdfObject::Number(n)) if n.integer() >= 0 => Some(n.integer() as u32),
+            _ => None,
+        };
+        let fit = match arr.get(1) {
+            Some(PdfObject::Name(name)) => name.name().to_string(),
+            // some writers omit the fit type
+            None => "Fit".to_string(),
+            Some(obj) => {
+                return Err(PdfError::DocumentStructure(format!(
+                    "destination fit type need name got:{:?}",
+                    obj
+                )))
+            }
+        };
+        let param = |i: usize| -> Option<f32> {
+            match arr.get(i) {
+                Some(PdfObject::Number(n)) => Some(n.real()),
+                _ => None,
+            }
+        };
+        let view = match fit.as_str() {
+            "XYZ" => DestinationView::Xyz {
+                left: param(2),
+                top: param(3),
+                // zoom 0 has the same meaning as null
+                zoom: param(4).filter(|z| *z != 0.0),
+            },
+            "Fit" => DestinationView::Fit,
+            "FitH" => DestinationView::FitH { top: param(2) },
+            "FitV" => DestinationView::FitV { left: param(2) },
+            "FitR" => DestinationView::FitR {
+                left: param(2).unwrap_or(0.0),
+                bottom: param(3).unwrap_or(0.0),
+                right: param(4).unwrap_or(0.0),
+                top: param(5).unwrap_or(0.0),
+            },
+            "FitB" => DestinationView::FitB,
+            "FitBH" => DestinationView::FitBH { top: param(2) },
+            "FitBV" => DestinationView::FitBV { left: param(2) },
+            _ => {
+                return Err(PdfError::DocumentStructure(format!(
+                    "unknown destination fit type:{}",
+                    fit
+                )))
+            }
+        };
+        Ok(Destination { page, view })
+    }
+
+    // page index, None if the page is not in the document
+    pub fn page(&self) -> Option<u32> {
+        self.page
+    }
+
+    pub fn view(&self) -> &DestinationView {
+        &self.view
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Destination, DestinationView};
+    use crate::object::array::PdfArray;
+    use crate::object::name::PdfName;
+    use crate::object::number::PdfNumber;
+    use crate::object::PdfObject;
+
+    #[test]
+    fn test_destination() {
+        let arr = PdfArray::new(vec![
+            PdfObject::Indirect((7, 0)),
+            PdfObject::Name(PdfName::new("XYZ".to_string())),
+            PdfObject::Number(PdfNumber::Integer(72)),
+            PdfObject::Null,
+            PdfObject::Number(PdfNumber::Real(0.0)),
+        ]);
+        let dest = Destination::try_new(&arr, |id| if id.0 == 7 { Some(2) } else { None }).unwrap();
+        assert_eq!(dest.page(), Some(2));
+        assert_eq!(
+            dest.view(),
+            &DestinationView::Xyz {
+                left: Some(72.0),
+                top: None,
+                zoom: None
+            }
+        );
+
+        let arr = PdfArray::new(vec![
+            PdfObject::Number(PdfNumber::Integer(3)),
+            PdfObject::Name(PdfName::new("FitH".to_string())),
+            PdfObject::Number(PdfNumber::Integer(500)),
+        ]);
+        let dest = Destination::try_new(&arr, |_| None).unwrap();
+        assert_eq!(dest.page(), Some(3));
+        assert_eq!(dest.view(), &DestinationView::FitH { top: Some(500.0) });
+    }
+}
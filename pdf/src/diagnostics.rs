@@ -0,0 +1,219 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use log::warn;
+
+use crate::error::{PdfError, Result};
+use crate::object::ObjectId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticCategory {
+    Xref,
+    Object,
+    Stream,
+    Filter,
+    Content,
+    Structure,
+}
+
+// a recoverable problem found when reading the document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    category: DiagnosticCategory,
+    object: Option<ObjectId>,
+    offset: Option<usize>,
+    page: Option<u32>,
+    operator: Option<String>,
+    // kind of the error the diagnostic is made from
+    error: Option<&'static str>,
+    message: String,
+}
+
+impl Diagnostic {
+    pub fn new(category: DiagnosticCategory, message: String) -> Self {
+        Diagnostic {
+            category,
+            object: None,
+            offset: None,
+            page: None,
+            operator: None,
+            error: None,
+            message,
+        }
+    }
+
+    // keep the context and the kind of a recovered error
+    pub fn from_error(category: DiagnosticCategory, error: &PdfError) -> Self {
+        let mut diagnostic = Diagnostic::new(category, error.root().to_string());
+        if let Some(context) = error.context() {
+            diagnostic.object = context.object();
+            diagnostic.offset = context.offset();
+            diagnostic.page = context.page();
+            diagnostic.operator = context.operator().map(|o| o.to_string());
+        }
+        diagnostic.error = Some(error.kind());
+        diagnostic
+    }
+
+    pub fn with_object(mut self, object: ObjectId) -> Self {
+        self.object = Some(object);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn category(&self) -> DiagnosticCategory {
+        self.category
+    }
+
+    pub fn object(&self) -> Option<ObjectId> {
+        self.object
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    pub fn page(&self) -> Option<u32> {
+        self.page
+    }
+
+    pub fn operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+
+    pub fn error_kind(&self) -> Option<&'static str> {
+        self.error
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.category)?;
+        if let Some(page) = self.page {
+            write!(f, " page {}", page)?;
+        }
+        if let Some(operator) = self.operator.as_ref() {
+            write!(f, " operator {}", operator)?;
+        }
+        if let Some((num, gen)) = self.object {
+            write!(f, " object {} {}", num, gen)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " offset {}", offset)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// collect diagnostics of a document, in strict mode the first one is an error
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    warnings: Mutex<Vec<Diagnostic>>,
+    strict: AtomicBool,
+}
+
+impl Diagnostics {
+    pub fn new(strict: bool) -> Self {
+        Diagnostics {
+            warnings: Mutex::new(Vec::new()),
+            strict: AtomicBool::new(strict),
+        }
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict.load(Ordering::Relaxed)
+    }
+
+    pub fn set_strict(&self, strict: bool) {
+        self.strict.store(strict, Ordering::Relaxed)
+    }
+
+    // record the diagnostic, return error in strict mode
+    pub fn warn(&self, diagnostic: Diagnostic) -> Result<()> {
+        warn!("{}", diagnostic);
+        if let Ok(mut warnings) = self.warnings.lock() {
+            warnings.push(diagnostic.clone());
+        }
+        if self.is_strict() {
+            return Err(PdfError::Diagnostic(diagnostic));
+        }
+        Ok(())
+    }
+
+    pub fn warnings(&self) -> Vec<Diagnostic> {
+        match self.warnings.lock() {
+            Ok(warnings) => warnings.to_owned(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut warnings) = self.warnings.lock() {
+            warnings.clear();
+        }
+    }
+}
+
+// report to diagnostics if there is one, otherwise only log it
+pub(crate) fn report(diagnostics: Option<&Diagnostics>, diagnostic: Diagnostic) -> Result<()> {
+    match diagnostics {
+        Some(d) => d.warn(diagnostic),
+        None => {
+            warn!("{}", diagnostic);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, DiagnosticCategory, Diagnostics};
+    use crate::error::{PdfError, Result, ResultExt};
+
+    #[test]
+    fn test_diagnostics() {
+        let diagnostics = Diagnostics::default();
+        let d = Diagnostic::new(DiagnosticCategory::Stream, "wrong Length".to_string())
+            .with_object((3, 0))
+            .with_offset(100);
+        assert_eq!(d.to_string(), "Stream object 3 0 offset 100: wrong Length");
+        diagnostics.warn(d.clone()).unwrap();
+        assert_eq!(diagnostics.warnings(), vec![d.clone()]);
+
+        diagnostics.set_strict(true);
+        match diagnostics.warn(d.clone()) {
+            Err(PdfError::Diagnostic(e)) => assert_eq!(e, d),
+            r => panic!("strict diagnostics should be error, got:{:?}", r),
+        }
+        diagnostics.clear();
+        assert!(diagnostics.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_diagnostic_from_error() {
+        let r: Result<()> = Err(PdfError::Color("unknown color space".to_string()));
+        let e = r.with_operator("cs").with_page(2).unwrap_err();
+        let d = Diagnostic::from_error(DiagnosticCategory::Content, &e);
+        assert_eq!(d.page(), Some(2));
+        assert_eq!(d.operator(), Some("cs"));
+        assert_eq!(d.error_kind(), Some("Color"));
+        assert_eq!(
+            d.to_string(),
+            "Content page 2 operator cs: Color:unknown color space"
+        );
+    }
+}
@@ -1,32 +1,214 @@
+use std::collections::HashMap;
+use std::io::Write;
 use std::path::Path;
+use std::sync::Arc;
 
+use crate::action::Link;
 use crate::catalog::Catalog;
+use crate::destination::Destination;
+use crate::diagnostics::{Diagnostic, DiagnosticCategory, Diagnostics};
 use crate::error::{PdfError, Result};
-use crate::object::{ObjectId, PdfObject};
+use crate::fdf::{FdfAnnotation, FdfField, FieldValue, FormData};
+use crate::form::{AcroForm, FieldType};
+use crate::metadata::{DocumentInfo, XmpMetadata};
+use crate::nametree::{NameTree, NumberTree};
+use crate::object::array::PdfArray;
+use crate::object::bool::PdfBool;
+use crate::object::name::PdfName;
+use crate::object::number::PdfNumber;
+use crate::object::{dictionary::PdfDict, ObjectId, PdfObject};
+use crate::outline::{read_outlines, OutlineItem};
 use crate::page::Page;
+use crate::pagelabel::PageLabels;
 use crate::reader::PdfReader;
+use crate::source::{ByteSource, MemorySource};
 use crate::xref::Xref;
 
+pub use crate::linearization::{Linearization, PageOffsetHint, PageOffsetHints};
+pub use crate::xref::Revision;
+
 pub struct Document {
     xref: Xref,
     catalog: Catalog,
+    repaired: bool,
+    linearization: Option<Linearization>,
+    first_page_only: bool,
 }
 
 impl Document {
     pub fn new_from_file<P: AsRef<Path>>(p: P, password: Option<&[u8]>) -> Result<Self> {
         let reader = PdfReader::new_from_file(p)?;
-        let xref = Xref::try_new(reader, password)?;
+        Self::open(reader, password, Arc::new(Diagnostics::default()))
+    }
+
+    // open document in memory without copy, data can be Vec<u8>, Arc<[u8]>, a mmap ...
+    pub fn from_bytes<T: AsRef<[u8]> + Send + Sync + 'static>(
+        data: T,
+        password: Option<&[u8]>,
+    ) -> Result<Self> {
+        Self::from_source(MemorySource::new(data), password)
+    }
+
+    pub fn from_source<S: ByteSource + 'static>(
+        source: S,
+        password: Option<&[u8]>,
+    ) -> Result<Self> {
+        let reader = PdfReader::new_from_source(source);
+        Self::open(reader, password, Arc::new(Diagnostics::default()))
+    }
+
+    // any recoverable problem, include the ones found when open, is an error
+    pub fn from_source_strict<S: ByteSource + 'static>(
+        source: S,
+        password: Option<&[u8]>,
+    ) -> Result<Self> {
+        let reader = PdfReader::new_from_source(source);
+        Self::open(reader, password, Arc::new(Diagnostics::new(true)))
+    }
+
+    // show the first page of a linearized file before the rest is available,
+    // a source without the needed bytes return PdfError::MissingRange,
+    // fetch the range and open again. File not linearized is opened as a whole
+    pub fn open_first_page<S: ByteSource + 'static>(
+        source: S,
+        password: Option<&[u8]>,
+    ) -> Result<Self> {
+        let diagnostics = Arc::new(Diagnostics::default());
+        let mut reader = PdfReader::new_from_source(source);
+        reader.set_diagnostics(diagnostics.clone());
+        let linearization = match Linearization::try_new(&reader)? {
+            Some(lin) => lin,
+            None => return Self::open(reader, password, diagnostics),
+        };
+        let mut xref = Xref::new(reader);
+        xref.set_diagnostics(diagnostics);
+        xref.load_first_page(linearization.first_page_xref_offset(), password)?;
+        let root = xref
+            .trailer()
+            .get("Root")
+            .ok_or(PdfError::DocumentStructure(
+                "Root not in first page trailer".to_string(),
+            ))?;
+        let root = xref.read_object(root)?.to_dict()?;
+        let page = PdfObject::Indirect((linearization.first_page_object(), 0));
+        let catalog = Catalog::try_new_first_page(root, &page, linearization.first_page(), &xref)?;
+        Ok(Document {
+            xref,
+            catalog,
+            repaired: false,
+            linearization: Some(linearization),
+            first_page_only: true,
+        })
+    }
+
+    // load the whole document after open_first_page, when all data is available
+    pub fn load_all(&mut self, password: Option<&[u8]>) -> Result<()> {
+        if !self.first_page_only {
+            return Ok(());
+        }
+        self.xref.load(password)?;
+        self.load_catalog()?;
+        self.first_page_only = false;
+        Ok(())
+    }
+
+    fn open(
+        mut reader: PdfReader,
+        password: Option<&[u8]>,
+        diagnostics: Arc<Diagnostics>,
+    ) -> Result<Self> {
+        reader.set_diagnostics(diagnostics.clone());
+        let linearization = Linearization::try_new(&reader)?;
+        let mut xref = Xref::new(reader);
+        xref.set_diagnostics(diagnostics);
+        let mut repaired = false;
+        match xref.load(password) {
+            Ok(()) => {}
+            Err(
+                e @ (PdfError::WrongPassword
+                | PdfError::MissingRange(_, _)
+                | PdfError::Diagnostic(_)),
+            ) => return Err(e),
+            Err(e) => {
+                xref.diagnostics().warn(Diagnostic::new(
+                    DiagnosticCategory::Xref,
+                    format!("load xref failed:{:?}, try to rebuild it", e),
+                ))?;
+                xref.rebuild(password)?;
+                repaired = true;
+            }
+        }
 
         let mut doc = Document {
             xref,
             catalog: Catalog::default(),
+            repaired,
+            linearization,
+            first_page_only: false,
         };
-        doc.load_catalog()?;
+        if let Err(e) = doc.load_catalog() {
+            if doc.repaired || matches!(e, PdfError::MissingRange(_, _) | PdfError::Diagnostic(_)) {
+                return Err(e);
+            }
+            doc.diagnostics().warn(Diagnostic::new(
+                DiagnosticCategory::Xref,
+                format!("load catalog failed:{:?}, try to rebuild xref", e),
+            ))?;
+            doc.xref.rebuild(password)?;
+            doc.repaired = true;
+            doc.load_catalog()?;
+        }
 
         Ok(doc)
     }
 
+    // cross-reference table was broken and reconstructed by scanning the file
+    pub fn is_repaired(&self) -> bool {
+        self.repaired
+    }
+
+    // recoverable problems found when reading the document
+    pub fn diagnostics(&self) -> &Diagnostics {
+        self.xref.diagnostics()
+    }
+
+    // in strict mode the next recoverable problem is returned as PdfError::Diagnostic
+    pub fn set_strict(&self, strict: bool) {
+        self.xref.diagnostics().set_strict(strict)
+    }
+
+    pub fn is_linearized(&self) -> bool {
+        self.linearization.is_some()
+    }
+
+    // only the first page is loaded by open_first_page
+    pub fn is_first_page_only(&self) -> bool {
+        self.first_page_only
+    }
+
+    // linearization parameters, including the first-page cross-reference offset
+    pub fn linearization(&self) -> Option<&Linearization> {
+        self.linearization.as_ref()
+    }
+
+    // offset and length of every page in a linearized file, read from the primary hint stream
+    pub fn page_offset_hints(&self) -> Result<PageOffsetHints> {
+        match self.linearization.as_ref() {
+            Some(lin) => lin.page_offset_hints(&self.xref),
+            None => Err(PdfError::DocumentStructure(
+                "document is not linearized".to_string(),
+            )),
+        }
+    }
+
     fn load_catalog(&mut self) -> Result<()> {
+        let catalog = self.root()?;
+        self.catalog = Catalog::try_new(catalog, &self.xref)?;
+        Ok(())
+    }
+
+    // the catalog dictionary
+    fn root(&self) -> Result<PdfDict> {
         let root = self
             .xref
             .trailer()
@@ -34,13 +216,398 @@ impl Document {
             .ok_or(PdfError::DocumentStructure(
                 "Root not in trailer".to_string(),
             ))?;
-        let catalog = self.xref.read_object(root)?.to_dict()?;
-        self.catalog = Catalog::try_new(catalog, &self.xref)?;
-        Ok(())
+        self.xref.read_object(root)?.to_dict()
+    }
+
+    // document information dictionary, None if trailer has no Info
+    pub fn info(&self) -> Result<Option<DocumentInfo>> {
+        let info = match self.xref.trailer().get("Info") {
+            Some(info) => self.xref.read_object(info)?,
+            None => return Ok(None),
+        };
+        match info {
+            PdfObject::Dict(d) => Ok(Some(DocumentInfo::try_new(&d, &self.xref)?)),
+            _ => Ok(None),
+        }
+    }
+
+    // XMP metadata stream of the catalog, None if catalog has no Metadata
+    pub fn metadata(&self) -> Result<Option<XmpMetadata>> {
+        let metadata = match self.root()?.get("Metadata") {
+            Some(m) => self.xref.read_object(m)?,
+            None => return Ok(None),
+        };
+        match metadata {
+            PdfObject::Stream(s) => Ok(Some(XmpMetadata::try_new(&s, &self.xref)?)),
+            _ => Ok(None),
+        }
+    }
+
+    // top level items of the document outline
+    pub fn outlines(&self) -> Result<Vec<OutlineItem>> {
+        match self.catalog.outlines() {
+            Some(outlines) => read_outlines(outlines, &self.xref, &|dest| self.destination(dest)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // explicit destination array, destination dictionary with /D or a named destination,
+    // None if the name is not defined
+    pub fn destination(&self, dest: &PdfObject) -> Result<Option<Destination>> {
+        self.resolve_destination(dest, true)
+    }
+
+    fn resolve_destination(&self, dest: &PdfObject, named: bool) -> Result<Option<Destination>> {
+        match self.xref.read_object(dest)? {
+            PdfObject::Array(arr) => Ok(Some(Destination::try_new(&arr, |id| {
+                self.catalog.page_index(id)
+            })?)),
+            PdfObject::Dict(d) => match d.get("D") {
+                Some(d) => self.resolve_destination(d, false),
+                None => Ok(None),
+            },
+            PdfObject::Name(name) if named => {
+                if let Some(dests) = self.root()?.get("Dests") {
+                    let dests = self.xref.read_object(dests)?;
+                    if let Some(d) = dests.get_from_dict(name.name()) {
+                        return self.resolve_destination(d, false);
+                    }
+                }
+                // some writers use names for destinations in the name tree
+                self.named_destination(name.name().as_bytes())
+            }
+            s @ (PdfObject::LiteralString(_) | PdfObject::HexString(_)) if named => {
+                self.named_destination(s.string_bytes()?.as_slice())
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn named_destination(&self, name: &[u8]) -> Result<Option<Destination>> {
+        let dests = match self.name_tree("Dests")? {
+            Some(dests) => dests,
+            None => return Ok(None),
+        };
+        match dests.get(&name.to_vec())? {
+            Some(d) => self.resolve_destination(&d, false),
+            None => Ok(None),
+        }
+    }
+
+    // name tree in the catalog /Names dictionary, e.g. Dests, EmbeddedFiles, JavaScript
+    pub fn name_tree(&self, name: &str) -> Result<Option<NameTree<'_>>> {
+        let names = match self.root()?.get("Names") {
+            Some(names) => self.xref.read_object(names)?,
+            None => return Ok(None),
+        };
+        match names.get_from_dict(name) {
+            Some(tree) => Ok(Some(NameTree::try_new(tree, &self.xref)?)),
+            None => Ok(None),
+        }
+    }
+
+    // the catalog /PageLabels number tree, None if the document has no page labels
+    pub fn page_labels(&self) -> Result<Option<PageLabels>> {
+        match self.root()?.get("PageLabels") {
+            Some(labels) => {
+                let tree = NumberTree::try_new(labels, &self.xref)?;
+                Ok(Some(PageLabels::try_new(&tree, &self.xref)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // label of the page index, the page number start from 1 if document has no page labels
+    pub fn page_label(&self, i: u32) -> Result<String> {
+        let label = match self.page_labels()? {
+            Some(labels) => labels.label(i),
+            None => None,
+        };
+        Ok(label.unwrap_or_else(|| (i + 1).to_string()))
+    }
+
+    // index of the first page with the label
+    pub fn page_index_of_label(&self, label: &str) -> Result<Option<u32>> {
+        let total = self.total_page()?;
+        match self.page_labels()? {
+            Some(labels) => Ok(labels.page_index(label, total)),
+            None => Ok(label
+                .parse::<u32>()
+                .ok()
+                .filter(|n| *n >= 1 && *n <= total)
+                .map(|n| n - 1)),
+        }
+    }
+
+    // link annotations of the page with actions resolved, links that can't be read are
+    // skipped and reported as diagnostics
+    pub fn links(&self, page: u32) -> Result<Vec<Link>> {
+        let page = self
+            .get_page(&page)
+            .ok_or(PdfError::Page(format!("page {} not found", page)))?;
+        let resolve = |dest: &PdfObject| self.destination(dest);
+        let mut links = Vec::new();
+        for annot in page.annotations()? {
+            match Link::try_new(&annot, &self.xref, &resolve) {
+                Ok(Some(link)) => links.push(link),
+                Ok(None) => {}
+                Err(e) => {
+                    let mut diagnostic = Diagnostic::new(
+                        DiagnosticCategory::Structure,
+                        format!("read link of page {} failed:{:?}", page.index(), e),
+                    );
+                    if let Some(id) = annot.id() {
+                        diagnostic = diagnostic.with_object(id);
+                    }
+                    self.diagnostics().warn(diagnostic)?;
+                }
+            }
+        }
+        Ok(links)
+    }
+
+    // interactive form of the catalog /AcroForm, None if the document has no form
+    pub fn acro_form(&self) -> Result<Option<AcroForm>> {
+        let dict = match self.root()?.get("AcroForm") {
+            Some(form) => match self.xref.read_object(form)? {
+                PdfObject::Dict(d) => d,
+                _ => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        let annotation_pages = self.annotation_pages()?;
+        let page_index = |widget: &PdfDict, id: Option<ObjectId>| -> Option<u32> {
+            if let Some(PdfObject::Indirect(page)) = widget.get("P") {
+                if let Some(index) = self.catalog.page_index(page) {
+                    return Some(index);
+                }
+            }
+            id.and_then(|id| annotation_pages.get(&id).copied())
+        };
+        Ok(Some(AcroForm::try_new(dict, &self.xref, &page_index)?))
+    }
+
+    // set the value of the terminal field with the fully qualified name. Buttons take the name
+    // of an on state or Off, choice fields the export value of an option. Appearances of text and
+    // choice widgets are drawn again, NeedAppearances is set when the /DA font can't draw the value
+    pub fn set_field_value(&mut self, name: &str, value: &str) -> Result<()> {
+        let form = self
+            .acro_form()?
+            .ok_or(PdfError::Form("document has no AcroForm".to_string()))?;
+        let need_appearances = self.write_field_value(&form, name, &[value])?;
+        if need_appearances && !form.need_appearances() {
+            self.set_need_appearances()?;
+        }
+        self.load_catalog()
+    }
+
+    // write /V and the widget appearances of one field, return true if some widget
+    // appearance is left to the viewer. The catalog is not reloaded
+    fn write_field_value(&mut self, form: &AcroForm, name: &str, values: &[&str]) -> Result<bool> {
+        let field = form
+            .field(name)
+            .ok_or(PdfError::Form(format!("field {} not found", name)))?;
+        let field_id = field.id().ok_or(PdfError::Form(format!(
+            "field {} is not an indirect object",
+            name
+        )))?;
+        let v = field.new_value(values)?;
+
+        // merged field and widget share the dictionary, so changes are collected by object
+        let mut changes: HashMap<ObjectId, PdfDict> = HashMap::new();
+        // read again, an earlier field of the same import may have changed it
+        let mut dict = self.xref.read_indirect_object(&field_id)?.to_dict()?;
+        dict.insert("V", v);
+        if field.field_type() == Some(FieldType::Choice) {
+            let mut indices = values
+                .iter()
+                .map(|value| field.option_index(value))
+                .collect::<Option<Vec<usize>>>();
+            match indices.as_mut() {
+                Some(indices) => {
+                    indices.sort();
+                    let indices = indices
+                        .iter()
+                        .map(|i| PdfObject::Number(PdfNumber::Integer(*i as i32)))
+                        .collect();
+                    dict.insert("I", PdfObject::Array(PdfArray::new(indices)))
+                }
+                None => dict.remove("I"),
+            };
+        }
+        changes.insert(field_id, dict);
+
+        // the appearance of several selected options is drawn by viewers
+        let mut need_appearances = values.len() > 1;
+        let value = values[0];
+        let text = field.display_text(value);
+        for widget in field.widgets() {
+            let id = match widget.id() {
+                Some(id) => id,
+                None => {
+                    need_appearances = true;
+                    continue;
+                }
+            };
+            let mut dict = match changes.remove(&id) {
+                Some(dict) => dict,
+                None => self.xref.read_indirect_object(&id)?.to_dict()?,
+            };
+            if field.field_type() == Some(FieldType::Button) {
+                let state = match widget.on_state() {
+                    Some(on) if on == value => value,
+                    _ => "Off",
+                };
+                dict.insert("AS", PdfObject::Name(PdfName::new(state.to_string())));
+            } else if values.len() == 1 {
+                match form.text_appearance(field, &dict, text.as_str(), &self.xref)? {
+                    Some(stream) => {
+                        let ap_id = self.add_object(PdfObject::Stream(stream));
+                        let mut ap = match dict.get("AP") {
+                            Some(ap) => match self.xref.read_object(ap)? {
+                                PdfObject::Dict(ap) => ap,
+                                _ => PdfDict::default(),
+                            },
+                            None => PdfDict::default(),
+                        };
+                        // the down appearance shows the old value
+                        ap.remove("D");
+                        ap.insert("N", PdfObject::Indirect(ap_id));
+                        dict.insert("AP", PdfObject::Dict(ap));
+                    }
+                    None => need_appearances = true,
+                }
+            }
+            changes.insert(id, dict);
+        }
+        for (id, dict) in changes {
+            self.set_object(id, PdfObject::Dict(dict))?;
+        }
+        Ok(need_appearances)
+    }
+
+    // values of terminal fields which have a value, with markup annotations of pages if annotations
+    pub fn export_form_data(&self, annotations: bool) -> Result<FormData> {
+        let mut data = FormData::new(None);
+        if let Some(form) = self.acro_form()? {
+            for field in form.terminal_fields() {
+                if let Some(value) = field.value() {
+                    let value = FieldValue::try_new(value)?;
+                    data.add_field(FdfField::new(field.name().to_string(), value));
+                }
+            }
+        }
+        if annotations {
+            for i in 0..self.total_page()? {
+                let page = match self.get_page(&i) {
+                    Some(page) => page,
+                    None => continue,
+                };
+                for annot in page.annotations()? {
+                    if annot.subtype().is_markup() {
+                        data.add_annotation(FdfAnnotation::try_new(i, annot.dict(), &self.xref)?);
+                    }
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    // set values of the fields as set_field_value does, fields missing in the document
+    // or with values they can't take are reported as diagnostics. A list of several
+    // values is only taken by list boxes with MultiSelect
+    pub fn import_form_data(&mut self, data: &FormData) -> Result<()> {
+        if data.fields().is_empty() {
+            return Ok(());
+        }
+        let form = self
+            .acro_form()?
+            .ok_or(PdfError::Form("document has no AcroForm".to_string()))?;
+        let mut need_appearances = false;
+        for field in data.fields() {
+            match self.write_field_value(&form, field.name(), &field.value().texts()) {
+                Ok(need) => need_appearances |= need,
+                Err(e) => {
+                    self.diagnostics().warn(Diagnostic::new(
+                        DiagnosticCategory::Structure,
+                        format!("import value of field {} failed:{:?}", field.name(), e),
+                    ))?;
+                }
+            }
+        }
+        if need_appearances && !form.need_appearances() {
+            self.set_need_appearances()?;
+        }
+        self.load_catalog()
+    }
+
+    // ask viewers to build appearances of fields, AcroForm may be direct in the catalog
+    fn set_need_appearances(&mut self) -> Result<()> {
+        let mut root = self.root()?;
+        let need = PdfObject::Bool(PdfBool(true));
+        match root.get("AcroForm") {
+            Some(PdfObject::Indirect(id)) => {
+                let id = *id;
+                let mut form = self.xref.read_indirect_object(&id)?.to_dict()?;
+                form.insert("NeedAppearances", need);
+                self.set_object(id, PdfObject::Dict(form))
+            }
+            Some(PdfObject::Dict(form)) => {
+                let mut form = form.to_owned();
+                form.insert("NeedAppearances", need);
+                root.insert("AcroForm", PdfObject::Dict(form));
+                match self.xref.trailer().get("Root") {
+                    Some(PdfObject::Indirect(id)) => {
+                        let id = *id;
+                        self.set_object(id, PdfObject::Dict(root))
+                    }
+                    _ => Err(PdfError::DocumentStructure(
+                        "Root in trailer is not an indirect object".to_string(),
+                    )),
+                }
+            }
+            _ => Err(PdfError::Form("document has no AcroForm".to_string())),
+        }
+    }
+
+    // page index of every annotation in page /Annots
+    fn annotation_pages(&self) -> Result<HashMap<ObjectId, u32>> {
+        let mut pages = HashMap::new();
+        for i in 0..self.total_page()? {
+            let node = match self.catalog.get_page(&i) {
+                Some(node) => node,
+                None => continue,
+            };
+            if let Some(annots) = node.dict().get("Annots") {
+                if let PdfObject::Array(annots) = self.xref.read_object(annots)? {
+                    for annot in annots.iter() {
+                        if let PdfObject::Indirect(id) = annot {
+                            pages.insert(*id, i);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(pages)
+    }
+
+    // revisions in file order, the last one is the current state of document
+    pub fn revisions(&self) -> &[Revision] {
+        self.xref.revisions()
+    }
+
+    pub fn revision(&self) -> usize {
+        self.xref.revision()
+    }
+
+    // open the document as of an earlier revision
+    pub fn set_revision(&mut self, revision: usize) -> Result<()> {
+        self.xref.set_revision(revision)?;
+        self.load_catalog()
     }
 
     pub fn get_page(&self, i: &u32) -> Option<Page> {
-        let node = self.catalog.get_page(i).unwrap();
+        let node = self.catalog.get_page(i)?;
         Some(Page::try_new(node.clone(), &self.xref).unwrap())
     }
 
@@ -49,9 +616,52 @@ impl Document {
     }
 
     pub fn total_page(&self) -> Result<u32> {
+        if self.first_page_only {
+            if let Some(lin) = self.linearization.as_ref() {
+                return Ok(lin.page_count());
+            }
+        }
         self.catalog.total_page()
     }
     pub fn read_object(&self, num: u32, gen: u16) -> Result<PdfObject> {
         self.xref.read_indirect_object(&(num, gen))
     }
+
+    // replace an object, or create it with the number. Changes are read back at once,
+    // pages already loaded keep the objects they have read
+    pub fn set_object(&mut self, id: ObjectId, obj: PdfObject) -> Result<()> {
+        self.xref.set_object(id, obj)
+    }
+
+    pub fn add_object(&mut self, obj: PdfObject) -> ObjectId {
+        self.xref.add_object(obj)
+    }
+
+    // the object is null after deleted
+    pub fn delete_object(&mut self, id: ObjectId) -> Result<()> {
+        self.xref.delete_object(id)
+    }
+
+    // write the original file unchanged followed by changed objects and a new cross-reference
+    // section, so signatures of the original file are still valid
+    pub fn save_incremental<W: Write>(&self, out: W) -> Result<()> {
+        if self.first_page_only {
+            return Err(PdfError::DocumentStructure(
+                "save document need load_all after open_first_page".to_string(),
+            ));
+        }
+        self.xref.write_incremental(out)
+    }
+
+    // rewrite the whole document: objects not reachable from trailer Root and Info are dropped
+    // and the rest are renumbered. With object_streams, objects other than streams are packed
+    // into object streams and the cross-reference is a stream. Encryption is removed
+    pub fn save_full<W: Write>(&self, out: W, object_streams: bool) -> Result<()> {
+        if self.first_page_only {
+            return Err(PdfError::DocumentStructure(
+                "save document need load_all after open_first_page".to_string(),
+            ));
+        }
+        self.xref.write_full(out, object_streams)
+    }
 }
@@ -1,5 +1,11 @@
+use std::fmt::Display;
+
+use aes::cipher::block_padding::UnpadError;
 use thiserror::Error;
 
+use crate::diagnostics::Diagnostic;
+use crate::object::ObjectId;
+
 #[derive(Debug, Error)]
 pub enum PdfError {
     #[error("Password is wrong")]
@@ -8,6 +14,10 @@ pub enum PdfError {
     #[error("Reader Error: '{0}'")]
     Reader(String),
 
+    // bytes are not available yet, caller can fetch the range and retry
+    #[error("Missing range offset:{0} length:{1}")]
+    MissingRange(usize, usize),
+
     #[error("File Error:'{0}'")]
     File(String),
 
@@ -55,6 +65,207 @@ pub enum PdfError {
 
     #[error("Pattern:{0}")]
     Pattern(String),
+
+    #[error("Metadata:{0}")]
+    Metadata(String),
+
+    #[error("Form:{0}")]
+    Form(String),
+
+    // warning in strict mode
+    #[error("Diagnostic:{0}")]
+    Diagnostic(Diagnostic),
+
+    #[error("IO error {0}:{1}")]
+    Io(String, #[source] std::io::Error),
+
+    #[error("Flate decode error:{0}")]
+    Flate(#[source] std::io::Error),
+
+    #[error("AES decrypt error:{0}")]
+    Aes(#[source] UnpadError),
+
+    // where the error happened, source is never a Context
+    #[error("{0}: {1}")]
+    Context(ErrorContext, #[source] Box<PdfError>),
 }
 
 pub type Result<T> = std::result::Result<T, PdfError>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorContext {
+    object: Option<ObjectId>,
+    offset: Option<usize>,
+    page: Option<u32>,
+    operator: Option<String>,
+}
+
+impl ErrorContext {
+    pub fn object(&self) -> Option<ObjectId> {
+        self.object
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    pub fn page(&self) -> Option<u32> {
+        self.page
+    }
+
+    pub fn operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(page) = self.page {
+            parts.push(format!("page {}", page));
+        }
+        if let Some(operator) = self.operator.as_ref() {
+            parts.push(format!("operator {}", operator));
+        }
+        if let Some((num, gen)) = self.object {
+            parts.push(format!("object {} {}", num, gen));
+        }
+        if let Some(offset) = self.offset {
+            parts.push(format!("offset {}", offset));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl PdfError {
+    // add context, the innermost value is kept if it is already set.
+    // errors handled by caller like MissingRange are not wrapped
+    fn with_context(self, f: impl FnOnce(&mut ErrorContext)) -> Self {
+        match self {
+            PdfError::Context(mut context, source) => {
+                f(&mut context);
+                PdfError::Context(context, source)
+            }
+            PdfError::WrongPassword | PdfError::MissingRange(_, _) | PdfError::Diagnostic(_) => {
+                self
+            }
+            _ => {
+                let mut context = ErrorContext::default();
+                f(&mut context);
+                PdfError::Context(context, Box::new(self))
+            }
+        }
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            PdfError::Context(context, _) => Some(context),
+            _ => None,
+        }
+    }
+
+    // the error without context
+    pub fn root(&self) -> &PdfError {
+        match self {
+            PdfError::Context(_, source) => source.root(),
+            _ => self,
+        }
+    }
+
+    // variant name of the root error, for grouping errors
+    pub fn kind(&self) -> &'static str {
+        match self.root() {
+            PdfError::WrongPassword => "WrongPassword",
+            PdfError::Reader(_) => "Reader",
+            PdfError::MissingRange(_, _) => "MissingRange",
+            PdfError::File(_) => "File",
+            PdfError::ParseObject(_) => "ParseObject",
+            PdfError::Filter(_) => "Filter",
+            PdfError::Object(_) => "Object",
+            PdfError::Xref(_) => "Xref",
+            PdfError::DocumentStructure(_) => "DocumentStructure",
+            PdfError::Page(_) => "Page",
+            PdfError::Interpreter(_) => "Interpreter",
+            PdfError::ContentParser(_) => "ContentParser",
+            PdfError::Path(_) => "Path",
+            PdfError::Font(_) => "Font",
+            PdfError::Character(_) => "Character",
+            PdfError::Color(_) => "Color",
+            PdfError::Function(_) => "Function",
+            PdfError::Image(_) => "Image",
+            PdfError::Pattern(_) => "Pattern",
+            PdfError::Metadata(_) => "Metadata",
+            PdfError::Form(_) => "Form",
+            PdfError::Diagnostic(_) => "Diagnostic",
+            PdfError::Io(_, _) => "Io",
+            PdfError::Flate(_) => "Flate",
+            PdfError::Aes(_) => "Aes",
+            PdfError::Context(_, _) => "Context",
+        }
+    }
+}
+
+pub trait ResultExt<T> {
+    fn with_object(self, object: ObjectId) -> Result<T>;
+    fn with_offset(self, offset: usize) -> Result<T>;
+    fn with_page(self, page: u32) -> Result<T>;
+    fn with_operator(self, operator: &str) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn with_object(self, object: ObjectId) -> Result<T> {
+        self.map_err(|e| e.with_context(|c| c.object = c.object.or(Some(object))))
+    }
+
+    fn with_offset(self, offset: usize) -> Result<T> {
+        self.map_err(|e| e.with_context(|c| c.offset = c.offset.or(Some(offset))))
+    }
+
+    fn with_page(self, page: u32) -> Result<T> {
+        self.map_err(|e| e.with_context(|c| c.page = c.page.or(Some(page))))
+    }
+
+    fn with_operator(self, operator: &str) -> Result<T> {
+        self.map_err(|e| {
+            e.with_context(|c| {
+                if c.operator.is_none() {
+                    c.operator = Some(operator.to_string())
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::{PdfError, Result, ResultExt};
+
+    #[test]
+    fn test_error_context() {
+        let r: Result<()> = Err(PdfError::Filter("bad data".to_string()));
+        let e = r
+            .with_object((5, 0))
+            .with_offset(120)
+            .with_object((1, 0))
+            .with_page(2)
+            .unwrap_err();
+        let context = e.context().unwrap();
+        assert_eq!(context.object(), Some((5, 0)));
+        assert_eq!(context.offset(), Some(120));
+        assert_eq!(context.page(), Some(2));
+        assert_eq!(e.kind(), "Filter");
+        assert_eq!(
+            e.to_string(),
+            "page 2 object 5 0 offset 120: Filter error:bad data"
+        );
+        assert_eq!(e.source().unwrap().to_string(), "Filter error:bad data");
+
+        let r: Result<()> = Err(PdfError::MissingRange(0, 10));
+        assert!(matches!(
+            r.with_object((1, 0)),
+            Err(PdfError::MissingRange(0, 10))
+        ));
+    }
+}
@@ -0,0 +1,615 @@
+use roxmltree::{Document as XmlDocument, Node};
+
+use crate::error::{PdfError, Result};
+use crate::object::array::PdfArray;
+use crate::object::dictionary::PdfDict;
+use crate::object::name::PdfName;
+use crate::object::number::PdfNumber;
+use crate::object::string::{encode_text_string, PdfLiteral};
+use crate::object::PdfObject;
+use crate::page::annotation::{
+    HIDDEN, INVISIBLE, LOCKED, LOCKED_CONTENTS, NO_ROTATE, NO_VIEW, NO_ZOOM, PRINT, READ_ONLY,
+    TOGGLE_NO_VIEW,
+};
+use crate::reader::PdfReader;
+use crate::writer::PdfWriter;
+use crate::xref::Xref;
+
+const NS_XFDF: &str = "http://ns.adobe.com/xfdf/";
+
+// annotation keys refer to objects of the document, they are not exported
+const DOCUMENT_KEYS: [&str; 6] = ["P", "Parent", "Popup", "IRT", "AP", "StructParent"];
+
+const FLAG_NAMES: [(u32, &str); 10] = [
+    (INVISIBLE, "invisible"),
+    (HIDDEN, "hidden"),
+    (PRINT, "print"),
+    (NO_ZOOM, "nozoom"),
+    (NO_ROTATE, "norotate"),
+    (NO_VIEW, "noview"),
+    (READ_ONLY, "readonly"),
+    (LOCKED, "locked"),
+    (TOGGLE_NO_VIEW, "togglenoview"),
+    (LOCKED_CONTENTS, "lockedcontents"),
+];
+
+// value of a field, name for buttons, list for choice fields with several selected values
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Name(String),
+    List(Vec<String>),
+}
+
+impl FieldValue {
+    pub(crate) fn try_new(value: &PdfObject) -> Result<Self> {
+        match value {
+            PdfObject::Name(n) => Ok(FieldValue::Name(n.name().to_string())),
+            PdfObject::Array(arr) => Ok(FieldValue::List(
+                arr.iter()
+                    .map(|v| v.as_text_string())
+                    .collect::<Result<Vec<String>>>()?,
+            )),
+            v => Ok(FieldValue::Text(v.as_text_string()?)),
+        }
+    }
+
+    fn to_object(&self) -> PdfObject {
+        let text = |s: &str| PdfObject::LiteralString(PdfLiteral::new(encode_text_string(s)));
+        match self {
+            FieldValue::Text(s) => text(s),
+            FieldValue::Name(n) => PdfObject::Name(PdfName::new(n.to_owned())),
+            FieldValue::List(l) => {
+                PdfObject::Array(PdfArray::new(l.iter().map(|s| text(s)).collect()))
+            }
+        }
+    }
+
+    pub(crate) fn texts(&self) -> Vec<&str> {
+        match self {
+            FieldValue::Text(s) | FieldValue::Name(s) => vec![s.as_str()],
+            FieldValue::List(l) => l.iter().map(|s| s.as_str()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdfField {
+    name: String,
+    value: FieldValue,
+}
+
+impl FdfField {
+    pub fn new(name: String, value: FieldValue) -> Self {
+        FdfField { name, value }
+    }
+
+    // fully qualified name
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn value(&self) -> &FieldValue {
+        &self.value
+    }
+}
+
+// annotation dictionary with the index of its page, references to objects of
+// the document are removed and other indirect objects are resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdfAnnotation {
+    page: u32,
+    dict: PdfDict,
+}
+
+impl FdfAnnotation {
+    pub(crate) fn try_new(page: u32, dict: &PdfDict, xref: &Xref) -> Result<Self> {
+        let mut resolved = PdfDict::default();
+        for (key, value) in dict.entries() {
+            if DOCUMENT_KEYS.contains(&key.as_str()) || key == "Page" {
+                continue;
+            }
+            if let Some(value) = resolve(value, xref)? {
+                resolved.insert(key, value);
+            }
+        }
+        Ok(FdfAnnotation {
+            page,
+            dict: resolved,
+        })
+    }
+
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    pub fn dict(&self) -> &PdfDict {
+        &self.dict
+    }
+
+    fn subtype(&self) -> Option<&str> {
+        match self.dict.get("Subtype") {
+            Some(PdfObject::Name(n)) => Some(n.name()),
+            _ => None,
+        }
+    }
+
+    fn text(&self, key: &str) -> Option<String> {
+        self.dict.get(key).and_then(|t| t.as_text_string().ok())
+    }
+
+    fn numbers(&self, key: &str) -> Option<Vec<f32>> {
+        numbers(self.dict.get(key)?)
+    }
+}
+
+// indirect objects are read, None for streams which can't be direct objects
+fn resolve(obj: &PdfObject, xref: &Xref) -> Result<Option<PdfObject>> {
+    match xref.read_object(obj)? {
+        PdfObject::Stream(_) => Ok(None),
+        PdfObject::Array(arr) => {
+            let mut elements = Vec::new();
+            for o in arr.iter() {
+                elements.push(resolve(o, xref)?.unwrap_or(PdfObject::Null));
+            }
+            Ok(Some(PdfObject::Array(PdfArray::new(elements))))
+        }
+        PdfObject::Dict(dict) => {
+            let mut resolved = PdfDict::default();
+            for (key, value) in dict.entries() {
+                if let Some(value) = resolve(value, xref)? {
+                    resolved.insert(key, value);
+                }
+            }
+            Ok(Some(PdfObject::Dict(resolved)))
+        }
+        o => Ok(Some(o)),
+    }
+}
+
+fn numbers(obj: &PdfObject) -> Option<Vec<f32>> {
+    obj.as_array()
+        .ok()?
+        .iter()
+        .map(|n| n.as_number().ok().map(|n| n.real()))
+        .collect()
+}
+
+// form data of FDF and XFDF files, field values and optionally annotations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormData {
+    file: Option<String>,
+    fields: Vec<FdfField>,
+    annotations: Vec<FdfAnnotation>,
+}
+
+impl FormData {
+    // file is the PDF file the data belongs to
+    pub fn new(file: Option<String>) -> Self {
+        FormData {
+            file,
+            ..Default::default()
+        }
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn fields(&self) -> &[FdfField] {
+        self.fields.as_slice()
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| &f.value)
+    }
+
+    pub fn add_field(&mut self, field: FdfField) {
+        self.fields.push(field);
+    }
+
+    pub fn annotations(&self) -> &[FdfAnnotation] {
+        self.annotations.as_slice()
+    }
+
+    pub fn add_annotation(&mut self, annotation: FdfAnnotation) {
+        self.annotations.push(annotation);
+    }
+
+    // read an FDF file, the cross-reference is rebuilt if the file has none
+    pub fn from_fdf(bytes: Vec<u8>) -> Result<Self> {
+        let xref = match Xref::try_new(PdfReader::new(bytes.clone()), None) {
+            Ok(xref) => xref,
+            Err(_) => Xref::try_rebuild(PdfReader::new(bytes), None)?,
+        };
+        let root = xref
+            .trailer()
+            .get("Root")
+            .ok_or(PdfError::Form("FDF trailer has no Root".to_string()))?;
+        let fdf = xref
+            .read_object(root)?
+            .get_from_dict("FDF")
+            .ok_or(PdfError::Form(
+                "FDF catalog has no FDF dictionary".to_string(),
+            ))?
+            .to_owned();
+        let fdf = xref.read_object(&fdf)?.to_dict()?;
+
+        let mut data = FormData::default();
+        if let Some(f) = fdf.get("F") {
+            data.file = match xref.read_object(f)? {
+                PdfObject::Dict(spec) => match spec.get("F") {
+                    Some(f) => Some(xref.read_object(f)?.as_text_string()?),
+                    None => None,
+                },
+                f => Some(f.as_text_string()?),
+            };
+        }
+        if let Some(fields) = fdf.get("Fields") {
+            for field in xref.read_object(fields)?.as_array()?.iter() {
+                data.read_fdf_field(field, "", &xref)?;
+            }
+        }
+        if let Some(annots) = fdf.get("Annots") {
+            for annot in xref.read_object(annots)?.as_array()?.iter() {
+                let dict = xref.read_object(annot)?.to_dict()?;
+                let page = match dict.get("Page") {
+                    Some(p) => xref.read_object(p)?.integer()?.max(0) as u32,
+                    None => 0,
+                };
+                data.annotations
+                    .push(FdfAnnotation::try_new(page, &dict, &xref)?);
+            }
+        }
+        Ok(data)
+    }
+
+    fn read_fdf_field(&mut self, field: &PdfObject, parent: &str, xref: &Xref) -> Result<()> {
+        let dict = xref.read_object(field)?.to_dict()?;
+        let name = match dict.get("T") {
+            Some(t) => {
+                let t = xref.read_object(t)?.as_text_string()?;
+                if parent.is_empty() {
+                    t
+                } else {
+                    format!("{}.{}", parent, t)
+                }
+            }
+            None => parent.to_string(),
+        };
+        if let Some(v) = dict.get("V") {
+            let value = FieldValue::try_new(&xref.read_object(v)?)?;
+            self.fields.push(FdfField::new(name.clone(), value));
+        }
+        if let Some(kids) = dict.get("Kids") {
+            for kid in xref.read_object(kids)?.as_array()?.iter() {
+                self.read_fdf_field(kid, &name, xref)?;
+            }
+        }
+        Ok(())
+    }
+
+    // FDF file with fields nested by their partial names
+    pub fn to_fdf(&self) -> Result<Vec<u8>> {
+        let mut fdf = PdfDict::default();
+        if let Some(file) = self.file.as_ref() {
+            fdf.insert(
+                "F",
+                PdfObject::LiteralString(PdfLiteral::new(encode_text_string(file))),
+            );
+        }
+        let fields = field_tree(&self.fields)
+            .iter()
+            .map(|node| node.to_fdf())
+            .collect();
+        fdf.insert("Fields", PdfObject::Array(PdfArray::new(fields)));
+        if !self.annotations.is_empty() {
+            let annots = self
+                .annotations
+                .iter()
+                .map(|a| {
+                    let mut dict = a.dict.to_owned();
+                    dict.insert("Page", PdfObject::Number(PdfNumber::Integer(a.page as i32)));
+                    PdfObject::Dict(dict)
+                })
+                .collect();
+            fdf.insert("Annots", PdfObject::Array(PdfArray::new(annots)));
+        }
+        let mut root = PdfDict::default();
+        root.insert("FDF", PdfObject::Dict(fdf));
+        let mut trailer = PdfDict::default();
+        trailer.insert("Root", PdfObject::Indirect((1, 0)));
+
+        let mut writer = PdfWriter::new(Vec::new());
+        writer.write_bytes(b"%FDF-1.2\n%\xE2\xE3\xCF\xD3\n")?;
+        writer.write_indirect_object((1, 0), &PdfObject::Dict(root))?;
+        writer.write_bytes(b"trailer\n")?;
+        writer.write_dict(&trailer)?;
+        writer.write_bytes(b"\n%%EOF\n")?;
+        Ok(writer.into_inner())
+    }
+
+    // read an XFDF file, annotations in it are not read
+    pub fn from_xfdf(xml: &str) -> Result<Self> {
+        let doc = XmlDocument::parse(xml)
+            .map_err(|e| PdfError::Form(format!("parse XFDF failed:{:?}", e)))?;
+        let root = doc.root_element();
+        if root.tag_name().name() != "xfdf" {
+            return Err(PdfError::Form(format!(
+                "XFDF root element need xfdf got:{}",
+                root.tag_name().name()
+            )));
+        }
+        let mut data = FormData::default();
+        for node in root.children().filter(|n| n.is_element()) {
+            match node.tag_name().name() {
+                "f" => data.file = node.attribute("href").map(String::from),
+                "fields" => {
+                    for field in node.children().filter(|n| n.has_tag_name("field")) {
+                        data.read_xfdf_field(field, "");
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(data)
+    }
+
+    fn read_xfdf_field(&mut self, field: Node, parent: &str) {
+        let name = match field.attribute("name") {
+            Some(t) if parent.is_empty() => t.to_string(),
+            Some(t) => format!("{}.{}", parent, t),
+            None => parent.to_string(),
+        };
+        let mut values: Vec<String> = field
+            .children()
+            .filter(|n| n.has_tag_name("value"))
+            .map(|n| n.text().unwrap_or_default().to_string())
+            .collect();
+        match values.len() {
+            0 => {}
+            1 => self.fields.push(FdfField::new(
+                name.clone(),
+                FieldValue::Text(values.remove(0)),
+            )),
+            _ => self
+                .fields
+                .push(FdfField::new(name.clone(), FieldValue::List(values))),
+        }
+        for kid in field.children().filter(|n| n.has_tag_name("field")) {
+            self.read_xfdf_field(kid, &name);
+        }
+    }
+
+    // XFDF file, annotations are written with their common attributes and contents
+    pub fn to_xfdf(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<xfdf xmlns=\"{}\" xml:space=\"preserve\">\n",
+            NS_XFDF
+        ));
+        if let Some(file) = self.file.as_ref() {
+            xml.push_str(&format!("  <f href=\"{}\"/>\n", escape_xml(file)));
+        }
+        xml.push_str("  <fields>\n");
+        for node in field_tree(&self.fields) {
+            node.write_xfdf(&mut xml, 2);
+        }
+        xml.push_str("  </fields>\n");
+        if !self.annotations.is_empty() {
+            xml.push_str("  <annots>\n");
+            for annot in self.annotations.iter() {
+                write_xfdf_annotation(annot, &mut xml);
+            }
+            xml.push_str("  </annots>\n");
+        }
+        xml.push_str("</xfdf>\n");
+        xml
+    }
+}
+
+// fields grouped by the parts of their names
+struct FieldNode<'a> {
+    name: &'a str,
+    value: Option<&'a FieldValue>,
+    kids: Vec<FieldNode<'a>>,
+}
+
+fn field_tree(fields: &[FdfField]) -> Vec<FieldNode<'_>> {
+    let mut nodes: Vec<FieldNode> = Vec::new();
+    for field in fields {
+        let mut level = &mut nodes;
+        let mut parts = field.name.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let index = match level.iter().position(|n| n.name == part) {
+                Some(i) => i,
+                None => {
+                    level.push(FieldNode {
+                        name: part,
+                        value: None,
+                        kids: Vec::new(),
+                    });
+                    level.len() - 1
+                }
+            };
+            if parts.peek().is_none() {
+                level[index].value = Some(&field.value);
+            }
+            level = &mut level[index].kids;
+        }
+    }
+    nodes
+}
+
+impl FieldNode<'_> {
+    fn to_fdf(&self) -> PdfObject {
+        let mut dict = PdfDict::default();
+        dict.insert(
+            "T",
+            PdfObject::LiteralString(PdfLiteral::new(encode_text_string(self.name))),
+        );
+        if let Some(value) = self.value {
+            dict.insert("V", value.to_object());
+        }
+        if !self.kids.is_empty() {
+            let kids = self.kids.iter().map(|k| k.to_fdf()).collect();
+            dict.insert("Kids", PdfObject::Array(PdfArray::new(kids)));
+        }
+        PdfObject::Dict(dict)
+    }
+
+    fn write_xfdf(&self, xml: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        xml.push_str(&format!(
+            "{}<field name=\"{}\">\n",
+            indent,
+            escape_xml(self.name)
+        ));
+        if let Some(value) = self.value {
+            for text in value.texts() {
+                xml.push_str(&format!(
+                    "{}  <value>{}</value>\n",
+                    indent,
+                    escape_xml(text)
+                ));
+            }
+        }
+        for kid in self.kids.iter() {
+            kid.write_xfdf(xml, depth + 1);
+        }
+        xml.push_str(&format!("{}</field>\n", indent));
+    }
+}
+
+fn write_xfdf_annotation(annot: &FdfAnnotation, xml: &mut String) {
+    // element name is the subtype in lower case, like highlight or freetext
+    let element = match annot.subtype() {
+        Some(subtype) => subtype.to_lowercase(),
+        None => return,
+    };
+    let mut attrs = vec![("page".to_string(), annot.page.to_string())];
+    if let Some(rect) = annot.numbers("Rect") {
+        attrs.push(("rect".to_string(), join_numbers(&rect)));
+    }
+    if let Some(c) = annot.numbers("C") {
+        if c.len() == 3 {
+            let hex: String = c
+                .iter()
+                .map(|v| format!("{:02X}", (v.clamp(0.0, 1.0) * 255.0).round() as u8))
+                .collect();
+            attrs.push(("color".to_string(), format!("#{}", hex)));
+        }
+    }
+    for (key, attr) in [
+        ("NM", "name"),
+        ("M", "date"),
+        ("T", "title"),
+        ("Subj", "subject"),
+    ] {
+        if let Some(text) = annot.text(key) {
+            attrs.push((attr.to_string(), text));
+        }
+    }
+    if let Some(f) = annot.dict.get("F").and_then(|f| f.integer().ok()) {
+        let flags: Vec<&str> = FLAG_NAMES
+            .iter()
+            .filter(|(bit, _)| f as u32 & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        if !flags.is_empty() {
+            attrs.push(("flags".to_string(), flags.join(",")));
+        }
+    }
+    if let Some(quads) = annot.numbers("QuadPoints") {
+        attrs.push(("coords".to_string(), join_numbers(&quads)));
+    }
+
+    xml.push_str(&format!("    <{}", element));
+    for (name, value) in attrs {
+        xml.push_str(&format!(" {}=\"{}\"", name, escape_xml(&value)));
+    }
+    xml.push_str(">\n");
+    if let Some(contents) = annot.text("Contents") {
+        xml.push_str(&format!(
+            "      <contents>{}</contents>\n",
+            escape_xml(&contents)
+        ));
+    }
+    if let Some(PdfObject::Array(ink)) = annot.dict.get("InkList") {
+        xml.push_str("      <inklist>\n");
+        for path in ink.iter().filter_map(numbers) {
+            let points: Vec<String> = path
+                .chunks_exact(2)
+                .map(|p| format!("{},{}", p[0], p[1]))
+                .collect();
+            xml.push_str(&format!(
+                "        <gesture>{}</gesture>\n",
+                points.join(";")
+            ));
+        }
+        xml.push_str("      </inklist>\n");
+    }
+    xml.push_str(&format!("    </{}>\n", element));
+}
+
+fn join_numbers(numbers: &[f32]) -> String {
+    numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FdfField, FieldValue, FormData};
+
+    #[test]
+    fn test_form_data_round_trip() {
+        let mut data = FormData::new(Some("form.pdf".to_string()));
+        let fields = [
+            ("person.name", FieldValue::Text("A & B <C>".to_string())),
+            ("person.email", FieldValue::Text("a@b.c".to_string())),
+            ("agree", FieldValue::Name("Yes".to_string())),
+            (
+                "colors",
+                FieldValue::List(vec!["red".to_string(), "blue".to_string()]),
+            ),
+        ];
+        for (name, value) in fields {
+            data.add_field(FdfField::new(name.to_string(), value));
+        }
+
+        let fdf = FormData::from_fdf(data.to_fdf().unwrap()).unwrap();
+        assert_eq!(fdf, data);
+
+        // names are text in XFDF
+        let xfdf = FormData::from_xfdf(&data.to_xfdf()).unwrap();
+        assert_eq!(xfdf.file(), Some("form.pdf"));
+        assert_eq!(xfdf.fields().len(), 4);
+        assert_eq!(xfdf.fields()[0], data.fields()[0]);
+        assert_eq!(
+            xfdf.field("agree"),
+            Some(&FieldValue::Text("Yes".to_string()))
+        );
+        assert_eq!(xfdf.field("colors"), data.field("colors"));
+    }
+}
@@ -107,7 +107,6 @@ impl<'a> Group4Decoder<'a> {
             let mode = decode_mode(&mut self.reader).ok_or(PdfError::Filter(
                 "Ccittfax Mode is not detected".to_string(),
             ))?;
-            println!("{:?},{:?}", mode, a0);
             match mode {
                 Mode::Pass => {
                     let _b1 = refstate.next_change(!color, a0).ok_or(PdfError::Filter(
@@ -141,7 +140,6 @@ impl<'a> Group4Decoder<'a> {
                 Mode::V => {
                     let b1 = refstate.next_change(!color, a0).unwrap_or(self.width);
                     let a1 = b1 + 0;
-                    println!("{:?},{:?}", a0, b1);
                     if a1 >= self.width {
                         break;
                     }
@@ -207,15 +205,11 @@ pub fn decode_g4(buffer: &[u8], param: &Param) -> Result<Vec<u8>> {
     let mut decoder = Group4Decoder::new(reader, param.columns);
     let mut res = Vec::new();
     let rows = if param.rows > 0 { param.rows } else { u16::MAX };
-    for i in 0..rows {
+    for _ in 0..rows {
         let line = decoder.decode_line()?;
         if line.is_empty() {
             break;
         }
-        if i > 406 {
-            panic!("finish");
-        }
-        println!("row: {:?},{:?}", i, decoder.reference);
         res.extend(line);
     }
 
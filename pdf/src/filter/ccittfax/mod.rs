@@ -64,11 +64,7 @@ impl Param {
     }
 }
 
-use std::io::Write;
 pub fn ccittfax_decode(buf: &[u8], params: Option<&PdfDict>) -> Result<Vec<u8>> {
-    let mut f = std::fs::File::create("ccittfax_decode").unwrap();
-    f.write_all(buf).unwrap();
-
     let p = match params {
         Some(d) => Param::try_new(d)?,
         None => Param::default(),
@@ -1,9 +1,16 @@
+use crate::diagnostics::{self, Diagnostic, DiagnosticCategory, Diagnostics};
 use crate::error::{PdfError, Result};
 use crate::object::dictionary::PdfDict;
 use flate2::bufread::ZlibDecoder;
-use std::io::Read;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
 
-pub fn flate_decode(input: &[u8], params: Option<&PdfDict>) -> Result<Vec<u8>> {
+pub fn flate_decode(
+    input: &[u8],
+    params: Option<&PdfDict>,
+    diagnostics: Option<&Diagnostics>,
+) -> Result<Vec<u8>> {
     let mut decoder = ZlibDecoder::new(input);
     let mut buf: [u8; 1] = [0; 1];
     let mut decompressed: Vec<u8> = Vec::new();
@@ -15,9 +22,22 @@ pub fn flate_decode(input: &[u8], params: Option<&PdfDict>) -> Result<Vec<u8>> {
                 }
                 decompressed.push(buf[0]);
             }
+            Err(e) if decompressed.is_empty() => {
+                return Err(PdfError::Flate(e));
+            }
             Err(e) => {
-                //return Err(PdfError::Filter(format!("FlateDecode error :{:?}", e)));
-                println!("Flated Decode Error:{:?}", e);
+                // damaged stream, keep the data decoded before error
+                diagnostics::report(
+                    diagnostics,
+                    Diagnostic::new(
+                        DiagnosticCategory::Filter,
+                        format!(
+                            "FlateDecode error after {} bytes:{:?}",
+                            decompressed.len(),
+                            e
+                        ),
+                    ),
+                )?;
                 break;
             }
         }
@@ -80,6 +100,13 @@ pub fn flate_decode(input: &[u8], params: Option<&PdfDict>) -> Result<Vec<u8>> {
     Ok(decompressed)
 }
 
+// compress data for FlateDecode, without predictor
+pub fn flate_encode(input: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(input).map_err(PdfError::Flate)?;
+    encoder.finish().map_err(PdfError::Flate)
+}
+
 fn png_sub(row: &[u8], bytes_per_pixel: usize) -> Vec<u8> {
     let mut result = row.to_vec();
     for i in bytes_per_pixel..row.len() {
@@ -145,14 +172,22 @@ fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
 
 #[cfg(test)]
 mod tests {
-    use super::flate_decode;
+    use super::{flate_decode, flate_encode};
 
     #[test]
     fn test_flated_decode() {
         let encoded = [
             0x78, 0x9c, 0x4b, 0xcb, 0xcf, 0x07, 0x00, 0x02, 0x82, 0x01, 0x45,
         ];
-        let res = flate_decode(&encoded, None).unwrap();
+        let res = flate_decode(&encoded, None, None).unwrap();
         assert_eq!(res, b"foo");
     }
+
+    #[test]
+    fn test_flate_encode() {
+        let data = b"BT /F1 12 Tf (Hello, world!) Tj ET".repeat(10);
+        let encoded = flate_encode(&data).unwrap();
+        assert!(encoded.len() < data.len());
+        assert_eq!(flate_decode(&encoded, None, None).unwrap(), data);
+    }
 }
@@ -7,6 +7,7 @@ pub mod jbig2;
 pub mod lzw;
 pub mod run_length;
 
+use crate::diagnostics::Diagnostics;
 use crate::error::{PdfError, Result};
 use crate::filter::run_length::run_length_decode;
 use crate::object::dictionary::PdfDict;
@@ -18,12 +19,17 @@ use flate::flate_decode;
 use jbig2::jbig2_decode;
 use lzw::lzw_decode;
 
-pub fn apply_filter(name: &str, input: &[u8], params: Option<&PdfDict>) -> Result<Vec<u8>> {
+pub fn apply_filter(
+    name: &str,
+    input: &[u8],
+    params: Option<&PdfDict>,
+    diagnostics: Option<&Diagnostics>,
+) -> Result<Vec<u8>> {
     match name {
         "AHx" | "ASCIIHexDecode" => ascii_hex_decode(input),
         "A85" | "ASCII85Decode" => ascii_85_decode(input),
         "LZW" | "LZWDecode" => lzw_decode(input, params),
-        "Fl" | "FlateDecode" => flate_decode(input, params),
+        "Fl" | "FlateDecode" => flate_decode(input, params, diagnostics),
         "RL" | "RunLengthDecode" => run_length_decode(input),
         "DCT" | "DCTDecode" => dct_decode(input, params),
         "CCF" | "CCITTFaxDecode" => ccittfax_decode(input, params),
@@ -1,5 +1,7 @@
 use std::char;
 
+use log::warn;
+
 use crate::error::{PdfError, Result};
 use crate::font::cmap::{Cmap, CodeSpaceRange};
 use crate::object::{number::PdfNumber, PdfObject};
@@ -146,7 +148,7 @@ impl CmapParser {
                 break;
             }
             match token {
-                Token::Other(buf) => match buf {
+                Token::Other(buf) => match buf.as_ref() {
                     b"usecmap" => {
                         let name = args
                             .pop()
@@ -209,7 +211,7 @@ impl CmapParser {
                     args.push(PdfObject::LiteralString(s));
                 }
                 Token::Number(buf, is_real) => {
-                    let num = PdfNumber::from_buffer(buf, is_real);
+                    let num = PdfNumber::from_buffer(&buf, is_real);
                     args.push(PdfObject::Number(num));
                 }
                 Token::StartName => {
@@ -221,7 +223,7 @@ impl CmapParser {
                     args.push(PdfObject::Array(a));
                 }
                 _ => {
-                    println!("impossiable token:{:?}", token)
+                    warn!("unexpected token in cmap:{:?}", token)
                 }
             }
         }
@@ -274,10 +276,14 @@ endbfrange
 endcmap CMapName currentdict /CMap defineresource pop end end";
         let cmap_parser = CmapParser::new(content.to_vec());
         let cmap = cmap_parser.parse().unwrap();
-        for (key, v) in cmap.cid_to_unicode.iter() {
-            println!("{:?},{:?}", key, v);
-        }
-        //println!("{:?}", cmap);
+        assert_eq!(
+            cmap.cid_to_unicode.get(&0x84).map(|v| v.as_str()),
+            Some("\u{2014}")
+        );
+        assert_eq!(
+            cmap.cid_to_unicode.get(&0xe0).map(|v| v.as_str()),
+            Some("\u{e0}")
+        );
     }
 
     #[test]
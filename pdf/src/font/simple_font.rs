@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use log::debug;
+
 use crate::error::{PdfError, Result};
 use crate::font::afm::Afm;
 use crate::font::cmap::Cmap;
@@ -142,8 +144,6 @@ impl SimpleFont {
                     }
                 }
             }
-        } else {
-            println!("fontfile is none");
         }
         Ok(())
     }
@@ -345,11 +345,9 @@ impl SimpleFont {
         if let Some(gid) = self.code_to_gid.get(&c) {
             return Some(GlyphDesc::Gid(gid.to_owned()));
         }
-        println!(
-            "result is None:{:?},{:?},{:?},{:?}",
+        debug!(
+            "glyph of {:?} is not found in font {}",
             code,
-            self.code_to_gid,
-            self.subtype,
             self.base_font()
         );
         None
@@ -40,7 +40,6 @@ impl Sfnt {
         let search_range = reader.read_be_u16()?;
         let _entry_selector = reader.read_be_u16()?;
         let range_shift = reader.read_be_u16()?;
-        println!("{:?}", num_tables);
         if num_tables == 0 {
             return Ok(Sfnt {
                 tables: HashMap::new(),
@@ -135,7 +135,6 @@ mod tests {
     #[test]
     fn test_truetype_program() {
         let font_data = load_builtin_font_data("TimesNewRoman").unwrap();
-        let program = TrueTypeProgram::try_new(font_data).unwrap();
-        println!("{:?}", program);
+        assert!(TrueTypeProgram::try_new(font_data).is_ok());
     }
 }
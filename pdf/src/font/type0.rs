@@ -1,5 +1,7 @@
 use std::char;
 
+use log::debug;
+
 use crate::error::{PdfError, Result};
 use crate::font::cid_font::CidFont;
 use crate::font::cmap::Cmap;
@@ -91,7 +93,7 @@ impl Type0 {
                 }
             }
             None => {
-                println!("Type0 font to_unicode is None");
+                debug!("Type0 font {} has no ToUnicode", self.base_font());
                 let mut s = String::new();
                 s.push(char::REPLACEMENT_CHARACTER);
                 return Ok(s);
@@ -129,7 +131,7 @@ impl Type0 {
                                 char.set_origin_x(w.1);
                                 char.set_origin_y(w.2);
                             } else {
-                                println!("width is None {:?},{:?}", self.writting_mode(), char);
+                                debug!("vertical metrics of {:?} is not found", char.code());
                             }
                         }
                     }
@@ -0,0 +1,918 @@
+use std::collections::HashSet;
+
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
+use crate::error::{PdfError, Result};
+use crate::font::encoding::{get_predefined_encoding, FontEncoding};
+use crate::font::simple_font::SimpleFont;
+use crate::geom::rect::Rect;
+use crate::object::array::PdfArray;
+use crate::object::dictionary::PdfDict;
+use crate::object::name::PdfName;
+use crate::object::number::PdfNumber;
+use crate::object::stream::PdfStream;
+use crate::object::string::{encode_text_string, PdfLiteral};
+use crate::object::{ObjectId, PdfObject};
+use crate::writer::to_bytes;
+use crate::xref::Xref;
+
+// bits of field flags /Ff
+pub const READ_ONLY: u32 = 1;
+pub const REQUIRED: u32 = 1 << 1;
+pub const NO_EXPORT: u32 = 1 << 2;
+pub const MULTILINE: u32 = 1 << 12;
+pub const PASSWORD: u32 = 1 << 13;
+pub const NO_TOGGLE_TO_OFF: u32 = 1 << 14;
+pub const RADIO: u32 = 1 << 15;
+pub const PUSHBUTTON: u32 = 1 << 16;
+pub const COMBO: u32 = 1 << 17;
+pub const EDIT: u32 = 1 << 18;
+pub const MULTI_SELECT: u32 = 1 << 21;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldType {
+    Text,
+    Button,
+    Choice,
+    Signature,
+}
+
+impl FieldType {
+    fn new(ft: &str) -> Result<Self> {
+        match ft {
+            "Tx" => Ok(FieldType::Text),
+            "Btn" => Ok(FieldType::Button),
+            "Ch" => Ok(FieldType::Choice),
+            "Sig" => Ok(FieldType::Signature),
+            _ => Err(PdfError::DocumentStructure(format!(
+                "unknown field type:{}",
+                ft
+            ))),
+        }
+    }
+}
+
+// widget annotation of a terminal field
+#[derive(Debug, Clone)]
+pub struct FieldWidget {
+    id: Option<ObjectId>,
+    page: Option<u32>,
+    rect: Option<Rect>,
+    on_state: Option<String>,
+    appearance_state: Option<String>,
+}
+
+impl FieldWidget {
+    fn try_new(
+        dict: &PdfDict,
+        id: Option<ObjectId>,
+        page: Option<u32>,
+        xref: &Xref,
+    ) -> Result<Self> {
+        let rect = match dict.get("Rect") {
+            Some(r) => Some(Rect::new_from_pdf_bbox(xref.read_object(r)?.as_array()?)?),
+            None => None,
+        };
+        // the state other than Off in the normal appearance
+        let mut on_state = None;
+        if let Some(ap) = dict.get("AP") {
+            if let Some(n) = xref.read_object(ap)?.get_from_dict("N") {
+                if let PdfObject::Dict(states) = xref.read_object(n)? {
+                    let mut names: Vec<&String> =
+                        states.entries().keys().filter(|k| *k != "Off").collect();
+                    names.sort();
+                    on_state = names.first().map(|s| s.to_string());
+                }
+            }
+        }
+        let appearance_state = match dict.get("AS") {
+            Some(s) => Some(xref.read_object(s)?.as_name()?.name().to_string()),
+            None => None,
+        };
+        Ok(FieldWidget {
+            id,
+            page,
+            rect,
+            on_state,
+            appearance_state,
+        })
+    }
+
+    pub fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    // index of the page the widget is on
+    pub fn page(&self) -> Option<u32> {
+        self.page
+    }
+
+    pub fn rect(&self) -> Option<&Rect> {
+        self.rect.as_ref()
+    }
+
+    // appearance state name of checkbox or radio button when it's on
+    pub fn on_state(&self) -> Option<&str> {
+        self.on_state.as_deref()
+    }
+
+    pub fn appearance_state(&self) -> Option<&str> {
+        self.appearance_state.as_deref()
+    }
+}
+
+// option of choice field, export value and the text shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoiceOption {
+    export: String,
+    display: String,
+}
+
+impl ChoiceOption {
+    pub fn export(&self) -> &str {
+        self.export.as_str()
+    }
+
+    pub fn display(&self) -> &str {
+        self.display.as_str()
+    }
+}
+
+// attributes inherited from parent fields and the AcroForm dictionary
+#[derive(Debug, Clone, Default)]
+struct Inherited {
+    field_type: Option<FieldType>,
+    flags: u32,
+    value: Option<PdfObject>,
+    default_value: Option<PdfObject>,
+    default_appearance: Option<String>,
+    quadding: i32,
+    max_len: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FormField {
+    id: Option<ObjectId>,
+    dict: PdfDict,
+    name: String,
+    partial_name: Option<String>,
+    alternate_name: Option<String>,
+    field_type: Option<FieldType>,
+    flags: u32,
+    value: Option<PdfObject>,
+    default_value: Option<PdfObject>,
+    default_appearance: Option<String>,
+    quadding: i32,
+    max_len: Option<i32>,
+    options: Vec<ChoiceOption>,
+    widgets: Vec<FieldWidget>,
+    kids: Vec<FormField>,
+}
+
+impl FormField {
+    pub fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    pub fn dict(&self) -> &PdfDict {
+        &self.dict
+    }
+
+    // fully qualified name, partial names of ancestors joined by '.'
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn partial_name(&self) -> Option<&str> {
+        self.partial_name.as_deref()
+    }
+
+    // /TU, the name shown in user interface
+    pub fn alternate_name(&self) -> Option<&str> {
+        self.alternate_name.as_deref()
+    }
+
+    pub fn field_type(&self) -> Option<FieldType> {
+        self.field_type
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.flags & READ_ONLY != 0
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.flags & REQUIRED != 0
+    }
+
+    pub fn is_checkbox(&self) -> bool {
+        self.field_type == Some(FieldType::Button) && self.flags & (RADIO | PUSHBUTTON) == 0
+    }
+
+    pub fn is_radio(&self) -> bool {
+        self.field_type == Some(FieldType::Button) && self.flags & RADIO != 0
+    }
+
+    pub fn is_pushbutton(&self) -> bool {
+        self.field_type == Some(FieldType::Button) && self.flags & PUSHBUTTON != 0
+    }
+
+    pub fn is_combo(&self) -> bool {
+        self.field_type == Some(FieldType::Choice) && self.flags & COMBO != 0
+    }
+
+    pub fn is_multi_select(&self) -> bool {
+        self.field_type == Some(FieldType::Choice) && self.flags & MULTI_SELECT != 0
+    }
+
+    // value /V as stored, string for text and choice fields, name for buttons
+    pub fn value(&self) -> Option<&PdfObject> {
+        self.value.as_ref()
+    }
+
+    pub fn default_value(&self) -> Option<&PdfObject> {
+        self.default_value.as_ref()
+    }
+
+    // value as text, choice field with multiple selected values is joined by ','
+    pub fn value_text(&self) -> Option<String> {
+        self.value.as_ref().and_then(value_text)
+    }
+
+    pub fn default_appearance(&self) -> Option<&str> {
+        self.default_appearance.as_deref()
+    }
+
+    // 0 left, 1 centered, 2 right
+    pub fn quadding(&self) -> i32 {
+        self.quadding
+    }
+
+    pub fn max_len(&self) -> Option<i32> {
+        self.max_len
+    }
+
+    pub fn options(&self) -> &[ChoiceOption] {
+        self.options.as_slice()
+    }
+
+    pub fn widgets(&self) -> &[FieldWidget] {
+        self.widgets.as_slice()
+    }
+
+    // on state names of the check box or radio buttons
+    pub fn on_states(&self) -> Vec<&str> {
+        let mut states: Vec<&str> = Vec::new();
+        for state in self.widgets.iter().filter_map(|w| w.on_state()) {
+            if !states.contains(&state) {
+                states.push(state);
+            }
+        }
+        states
+    }
+
+    pub fn kids(&self) -> &[FormField] {
+        self.kids.as_slice()
+    }
+
+    // field with no kid fields, has a value and widgets
+    pub fn is_terminal(&self) -> bool {
+        self.kids.is_empty()
+    }
+
+    // /V of the values, name of the on state for buttons and string for others.
+    // Only list boxes with MultiSelect take several values, /V is an array of them
+    pub(crate) fn new_value(&self, values: &[&str]) -> Result<PdfObject> {
+        if !self.is_terminal() {
+            return Err(PdfError::Form(format!(
+                "{} is not a terminal field",
+                self.name
+            )));
+        }
+        if self.is_read_only() {
+            return Err(PdfError::Form(format!("{} is read only", self.name)));
+        }
+        let value = match values {
+            [value] => *value,
+            [] => return Err(PdfError::Form(format!("{} value is empty", self.name))),
+            _ if self.is_multi_select() => {
+                let mut selected = Vec::new();
+                for value in values {
+                    if self.option_index(value).is_none() {
+                        return Err(PdfError::Form(format!(
+                            "{} has no option {}",
+                            self.name, value
+                        )));
+                    }
+                    selected.push(text_object(value));
+                }
+                return Ok(PdfObject::Array(PdfArray::new(selected)));
+            }
+            _ => {
+                return Err(PdfError::Form(format!(
+                    "{} takes only one value",
+                    self.name
+                )))
+            }
+        };
+        match self.field_type {
+            Some(FieldType::Text) => {
+                if let Some(max_len) = self.max_len {
+                    if value.chars().count() > max_len.max(0) as usize {
+                        return Err(PdfError::Form(format!(
+                            "{} value is longer than MaxLen {}",
+                            self.name, max_len
+                        )));
+                    }
+                }
+                Ok(text_object(value))
+            }
+            Some(FieldType::Button) if !self.is_pushbutton() => {
+                if value != "Off" && !self.on_states().contains(&value) {
+                    return Err(PdfError::Form(format!(
+                        "{} has no state {}, states are {:?}",
+                        self.name,
+                        value,
+                        self.on_states()
+                    )));
+                }
+                Ok(PdfObject::Name(PdfName::new(value.to_string())))
+            }
+            Some(FieldType::Choice) => {
+                if self.flags & EDIT == 0 && self.option_index(value).is_none() {
+                    return Err(PdfError::Form(format!(
+                        "{} has no option {}",
+                        self.name, value
+                    )));
+                }
+                Ok(text_object(value))
+            }
+            _ => Err(PdfError::Form(format!(
+                "value of {} can't be set",
+                self.name
+            ))),
+        }
+    }
+
+    // index in /Opt of the option with the export value
+    pub(crate) fn option_index(&self, value: &str) -> Option<usize> {
+        self.options.iter().position(|o| o.export == value)
+    }
+
+    // text shown in the widget for the value
+    pub(crate) fn display_text(&self, value: &str) -> String {
+        if self.field_type == Some(FieldType::Choice) {
+            if let Some(i) = self.option_index(value) {
+                return self.options[i].display.to_owned();
+            }
+        }
+        if self.field_type == Some(FieldType::Text) && self.flags & PASSWORD != 0 {
+            return "*".repeat(value.chars().count());
+        }
+        value.to_string()
+    }
+}
+
+fn text_object(text: &str) -> PdfObject {
+    PdfObject::LiteralString(PdfLiteral::new(encode_text_string(text)))
+}
+
+fn value_text(value: &PdfObject) -> Option<String> {
+    match value {
+        PdfObject::Name(n) => Some(n.name().to_string()),
+        PdfObject::LiteralString(_) | PdfObject::HexString(_) => value.as_text_string().ok(),
+        PdfObject::Array(arr) => Some(
+            arr.iter()
+                .filter_map(value_text)
+                .collect::<Vec<String>>()
+                .join(","),
+        ),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AcroForm {
+    dict: PdfDict,
+    fields: Vec<FormField>,
+    need_appearances: bool,
+    default_resources: Option<PdfDict>,
+    default_appearance: Option<String>,
+}
+
+impl AcroForm {
+    // page_index map a widget annotation to the index of its page
+    pub(crate) fn try_new<F>(dict: PdfDict, xref: &Xref, page_index: &F) -> Result<Self>
+    where
+        F: Fn(&PdfDict, Option<ObjectId>) -> Option<u32>,
+    {
+        let mut form = AcroForm::default();
+        if let Some(na) = dict.get("NeedAppearances") {
+            form.need_appearances = xref.read_object(na)?.as_bool()?.0;
+        }
+        if let Some(dr) = dict.get("DR") {
+            form.default_resources = Some(xref.read_object(dr)?.to_dict()?);
+        }
+        let mut inherited = Inherited::default();
+        if let Some(da) = dict.get("DA") {
+            let da = xref.read_object(da)?.as_text_string()?;
+            form.default_appearance = Some(da.clone());
+            inherited.default_appearance = Some(da);
+        }
+        if let Some(q) = dict.get("Q") {
+            inherited.quadding = xref.read_object(q)?.integer()?;
+        }
+        let mut visited = HashSet::new();
+        if let Some(fields) = dict.get("Fields") {
+            for field in xref.read_object(fields)?.as_array()?.iter() {
+                let reader = FieldReader { xref, page_index };
+                if let Some(field) = reader.read(field, "", &inherited, &mut visited)? {
+                    form.fields.push(field);
+                }
+            }
+        }
+        form.dict = dict;
+        Ok(form)
+    }
+
+    pub fn dict(&self) -> &PdfDict {
+        &self.dict
+    }
+
+    // root fields in /Fields
+    pub fn fields(&self) -> &[FormField] {
+        self.fields.as_slice()
+    }
+
+    // all terminal fields in tree order
+    pub fn terminal_fields(&self) -> Vec<&FormField> {
+        let mut fields = Vec::new();
+        let mut stack: Vec<&FormField> = self.fields.iter().rev().collect();
+        while let Some(field) = stack.pop() {
+            if field.is_terminal() {
+                fields.push(field);
+            } else {
+                stack.extend(field.kids.iter().rev());
+            }
+        }
+        fields
+    }
+
+    // field with the fully qualified name
+    pub fn field(&self, name: &str) -> Option<&FormField> {
+        let mut stack: Vec<&FormField> = self.fields.iter().collect();
+        while let Some(field) = stack.pop() {
+            if field.name == name {
+                return Some(field);
+            }
+            stack.extend(field.kids.iter());
+        }
+        None
+    }
+
+    pub fn need_appearances(&self) -> bool {
+        self.need_appearances
+    }
+
+    // /DR, fonts used by the default appearance strings
+    pub fn default_resources(&self) -> Option<&PdfDict> {
+        self.default_resources.as_ref()
+    }
+
+    pub fn default_appearance(&self) -> Option<&str> {
+        self.default_appearance.as_deref()
+    }
+
+    // normal appearance of a text or choice field widget showing the text, drawn with
+    // the font of /DA in /DR. None if the font is missing or can't encode the text
+    pub(crate) fn text_appearance(
+        &self,
+        field: &FormField,
+        widget: &PdfDict,
+        text: &str,
+        xref: &Xref,
+    ) -> Result<Option<PdfStream>> {
+        let da = match field.default_appearance() {
+            Some(da) => da,
+            None => return Ok(None),
+        };
+        let mut tokens: Vec<String> = da.split_ascii_whitespace().map(String::from).collect();
+        let tf = match tokens.iter().position(|t| t == "Tf") {
+            Some(i) if i >= 2 => i,
+            _ => return Ok(None),
+        };
+        let font_name = match tokens[tf - 2].strip_prefix('/') {
+            Some(name) => name.to_string(),
+            None => return Ok(None),
+        };
+        let font_ref = match self.font_resource(&font_name, xref)? {
+            Some(font) => font,
+            None => return Ok(None),
+        };
+        let font = match AppearanceFont::try_new(&font_ref, xref)? {
+            Some(font) => font,
+            None => return Ok(None),
+        };
+        let lines = match text
+            .split('\n')
+            .map(|line| font.encode(line))
+            .collect::<Option<Vec<Vec<u8>>>>()
+        {
+            Some(lines) => lines,
+            None => return Ok(None),
+        };
+        let rect = match widget.get("Rect") {
+            Some(r) => Rect::new_from_pdf_bbox(xref.read_object(r)?.as_array()?)?,
+            None => return Ok(None),
+        };
+        let (width, height) = (rect.width(), rect.height());
+
+        let mut content = String::from("/Tx BMC\nq\n");
+        let mut border_width = 0.0;
+        if let Some(mk) = widget.get("MK") {
+            let mk = xref.read_object(mk)?.to_dict()?;
+            if let Some(bg) = color_operator(&mk, "BG", ["g", "rg", "k"], xref)? {
+                content.push_str(&format!("{} 0 0 {} {} re f\n", bg, num(width), num(height)));
+            }
+            if let Some(bc) = color_operator(&mk, "BC", ["G", "RG", "K"], xref)? {
+                border_width = 1.0;
+                if let Some(bs) = widget.get("BS") {
+                    if let Some(w) = xref.read_object(bs)?.get_from_dict("W") {
+                        border_width = xref.read_object(w)?.as_number()?.real();
+                    }
+                }
+                if border_width > 0.0 {
+                    content.push_str(&format!(
+                        "{} {} w {} {} {} {} re S\n",
+                        bc,
+                        num(border_width),
+                        num(border_width / 2.0),
+                        num(border_width / 2.0),
+                        num(width - border_width),
+                        num(height - border_width)
+                    ));
+                }
+            }
+        }
+        let padding = border_width + 2.0;
+        let (inner_width, inner_height) = (width - 2.0 * padding, height - 2.0 * padding);
+        content.push_str(&format!(
+            "{} {} {} {} re W n\n",
+            num(padding),
+            num(padding),
+            num(inner_width),
+            num(inner_height)
+        ));
+
+        // font size 0 is auto sized to fit the field
+        let multiline = field.flags() & MULTILINE != 0;
+        let mut size: f32 = tokens[tf - 1].parse().unwrap_or(0.0);
+        if size <= 0.0 {
+            size = if multiline { 12.0 } else { inner_height * 0.7 };
+            let widest = lines.iter().map(|l| font.width(l)).fold(0.0, f32::max);
+            if !multiline && widest > 0.0 {
+                size = size.min(inner_width * 1000.0 / widest);
+            }
+            size = size.max(4.0);
+        }
+        tokens[tf - 1] = num(size);
+
+        content.push_str("BT\n");
+        content.push_str(&tokens.join(" "));
+        content.push('\n');
+        let leading = size * 1.15;
+        for (i, line) in lines.iter().enumerate() {
+            let line_width = font.width(line) * size / 1000.0;
+            let x = match field.quadding() {
+                1 => (width - line_width) / 2.0,
+                2 => width - padding - line_width,
+                _ => padding,
+            };
+            // single line is centered vertically, lines of multiline field start at the top
+            let y = if multiline {
+                height - padding - size - leading * i as f32
+            } else {
+                (height - size * 0.7) / 2.0
+            };
+            content.push_str(&format!("1 0 0 1 {} {} Tm ", num(x), num(y)));
+            let literal = PdfObject::LiteralString(PdfLiteral::new(line.to_owned()));
+            content.push_str(&String::from_utf8_lossy(&to_bytes(&literal)?));
+            content.push_str(" Tj\n");
+            if !multiline {
+                break;
+            }
+        }
+        content.push_str("ET\nQ\nEMC\n");
+
+        let mut fonts = PdfDict::default();
+        fonts.insert(&font_name, font_ref);
+        let mut resources = PdfDict::default();
+        resources.insert("Font", PdfObject::Dict(fonts));
+        let mut dict = PdfDict::default();
+        dict.insert("Type", PdfObject::Name(PdfName::new("XObject".to_string())));
+        dict.insert("Subtype", PdfObject::Name(PdfName::new("Form".to_string())));
+        dict.insert(
+            "BBox",
+            PdfObject::Array(PdfArray::new(vec![
+                PdfObject::Number(PdfNumber::Integer(0)),
+                PdfObject::Number(PdfNumber::Integer(0)),
+                PdfObject::Number(PdfNumber::Real(width)),
+                PdfObject::Number(PdfNumber::Real(height)),
+            ])),
+        );
+        dict.insert("Resources", PdfObject::Dict(resources));
+        Ok(Some(PdfStream::new(dict, content.into_bytes())))
+    }
+
+    // font in /DR /Font, as it is stored
+    fn font_resource(&self, name: &str, xref: &Xref) -> Result<Option<PdfObject>> {
+        let fonts = match self
+            .default_resources
+            .as_ref()
+            .and_then(|dr| dr.get("Font"))
+        {
+            Some(fonts) => xref.read_object(fonts)?,
+            None => return Ok(None),
+        };
+        Ok(fonts.get_from_dict(name).cloned())
+    }
+}
+
+// simple font of a default appearance string
+struct AppearanceFont {
+    font: SimpleFont,
+    encoding: FontEncoding,
+}
+
+impl AppearanceFont {
+    fn try_new(font: &PdfObject, xref: &Xref) -> Result<Option<Self>> {
+        let dict = xref.read_object(font)?.to_dict()?;
+        match dict.get("Subtype") {
+            Some(PdfObject::Name(n)) if n.name() == "Type1" || n.name() == "TrueType" => {}
+            _ => return Ok(None),
+        }
+        let encoding = match dict
+            .get("Encoding")
+            .map(|e| xref.read_object(e))
+            .transpose()?
+        {
+            Some(PdfObject::Name(n)) => get_predefined_encoding(n.name()),
+            Some(PdfObject::Dict(d)) => match d.get("BaseEncoding") {
+                Some(PdfObject::Name(n)) => get_predefined_encoding(n.name()),
+                _ => Some(FontEncoding::Standard),
+            },
+            _ => Some(FontEncoding::Standard),
+        };
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => return Ok(None),
+        };
+        match SimpleFont::try_new(dict, xref) {
+            Ok(font) => Ok(Some(AppearanceFont { font, encoding })),
+            Err(_) => Ok(None),
+        }
+    }
+
+    // None if a character is not in the encoding
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        text.chars()
+            .map(|c| {
+                let unit = u16::try_from(c as u32).ok()?;
+                self.encoding
+                    .charcode_from_unicode(&unit)
+                    .filter(|code| *code != 0)
+                    .map(|code| code as u8)
+            })
+            .collect()
+    }
+
+    // width in thousandths of font size
+    fn width(&self, codes: &[u8]) -> f32 {
+        codes
+            .iter()
+            .map(|code| self.font.char_width(*code).unwrap_or(500.0))
+            .sum()
+    }
+}
+
+// color operator of a /MK color array, None if the entry is missing or empty
+fn color_operator(
+    mk: &PdfDict,
+    key: &str,
+    operators: [&str; 3],
+    xref: &Xref,
+) -> Result<Option<String>> {
+    let color = match mk.get(key) {
+        Some(c) => xref.read_object(c)?,
+        None => return Ok(None),
+    };
+    let components = color
+        .as_array()?
+        .iter()
+        .map(|c| Ok(num(xref.read_object(c)?.as_number()?.real())))
+        .collect::<Result<Vec<String>>>()?;
+    let operator = match components.len() {
+        1 => operators[0],
+        3 => operators[1],
+        4 => operators[2],
+        _ => return Ok(None),
+    };
+    Ok(Some(format!("{} {}", components.join(" "), operator)))
+}
+
+// number in content stream, at most two decimals
+fn num(v: f32) -> String {
+    let s = format!("{:.2}", v);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+struct FieldReader<'a, F> {
+    xref: &'a Xref,
+    page_index: &'a F,
+}
+
+impl<F> FieldReader<'_, F>
+where
+    F: Fn(&PdfDict, Option<ObjectId>) -> Option<u32>,
+{
+    // None if the field is visited before or can't be read
+    fn read(
+        &self,
+        obj: &PdfObject,
+        parent_name: &str,
+        inherited: &Inherited,
+        visited: &mut HashSet<ObjectId>,
+    ) -> Result<Option<FormField>> {
+        let id = match obj {
+            PdfObject::Indirect(id) => Some(*id),
+            _ => None,
+        };
+        if let Some(id) = id {
+            if !visited.insert(id) {
+                self.xref.diagnostics().warn(
+                    Diagnostic::new(
+                        DiagnosticCategory::Structure,
+                        "form field is visited twice".to_string(),
+                    )
+                    .with_object(id),
+                )?;
+                return Ok(None);
+            }
+        }
+        let field = self
+            .xref
+            .read_object(obj)
+            .and_then(|o| o.to_dict())
+            .and_then(|dict| self.read_field(dict, id, parent_name, inherited, visited));
+        match field {
+            Ok(field) => Ok(Some(field)),
+            Err(
+                e @ (PdfError::WrongPassword
+                | PdfError::MissingRange(_, _)
+                | PdfError::Diagnostic(_)),
+            ) => Err(e),
+            // a malformed field is skipped, the rest of the form is still read
+            Err(e) => {
+                let mut diagnostic = Diagnostic::new(
+                    DiagnosticCategory::Structure,
+                    format!("read form field failed:{}, it is skipped", e),
+                );
+                if let Some(id) = id {
+                    diagnostic = diagnostic.with_object(id);
+                }
+                self.xref.diagnostics().warn(diagnostic)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn read_field(
+        &self,
+        dict: PdfDict,
+        id: Option<ObjectId>,
+        parent_name: &str,
+        inherited: &Inherited,
+        visited: &mut HashSet<ObjectId>,
+    ) -> Result<FormField> {
+        let xref = self.xref;
+        let text = |key: &str| -> Result<Option<String>> {
+            match dict.get(key) {
+                Some(t) => Ok(Some(xref.read_object(t)?.as_text_string()?)),
+                None => Ok(None),
+            }
+        };
+        let mut attrs = inherited.clone();
+        if let Some(ft) = dict.get("FT") {
+            attrs.field_type = Some(FieldType::new(xref.read_object(ft)?.as_name()?.name())?);
+        }
+        if let Some(ff) = dict.get("Ff") {
+            attrs.flags = xref.read_object(ff)?.integer()? as u32;
+        }
+        if let Some(v) = dict.get("V") {
+            attrs.value = Some(xref.read_object(v)?);
+        }
+        if let Some(dv) = dict.get("DV") {
+            attrs.default_value = Some(xref.read_object(dv)?);
+        }
+        if let Some(da) = text("DA")? {
+            attrs.default_appearance = Some(da);
+        }
+        if let Some(q) = dict.get("Q") {
+            attrs.quadding = xref.read_object(q)?.integer()?;
+        }
+        let partial_name = text("T")?;
+        let name = match (&partial_name, parent_name.is_empty()) {
+            (Some(t), true) => t.to_owned(),
+            (Some(t), false) => format!("{}.{}", parent_name, t),
+            (None, _) => parent_name.to_string(),
+        };
+        let mut options = Vec::new();
+        if let Some(opt) = dict.get("Opt") {
+            for o in xref.read_object(opt)?.as_array()?.iter() {
+                let option = match xref.read_object(o)? {
+                    PdfObject::Array(pair) => {
+                        let export = pair.get(0).map(|e| xref.read_object(e)).transpose()?;
+                        let display = pair.get(1).map(|d| xref.read_object(d)).transpose()?;
+                        match (export, display) {
+                            (Some(e), Some(d)) => ChoiceOption {
+                                export: e.as_text_string()?,
+                                display: d.as_text_string()?,
+                            },
+                            _ => continue,
+                        }
+                    }
+                    o => {
+                        let text = o.as_text_string()?;
+                        ChoiceOption {
+                            export: text.clone(),
+                            display: text,
+                        }
+                    }
+                };
+                options.push(option);
+            }
+        }
+        if let Some(m) = dict.get("MaxLen") {
+            attrs.max_len = Some(xref.read_object(m)?.integer()?);
+        }
+
+        let mut field = FormField {
+            id,
+            name,
+            partial_name,
+            alternate_name: text("TU")?,
+            field_type: attrs.field_type,
+            flags: attrs.flags,
+            value: attrs.value.clone(),
+            default_value: attrs.default_value.clone(),
+            default_appearance: attrs.default_appearance.clone(),
+            quadding: attrs.quadding,
+            max_len: attrs.max_len,
+            options,
+            ..Default::default()
+        };
+        // kids without /T are widgets of this field, a field without kids may be merged with its widget
+        match dict.get("Kids") {
+            Some(kids) => {
+                for kid in xref.read_object(kids)?.as_array()?.iter() {
+                    let kid_id = match kid {
+                        PdfObject::Indirect(id) => Some(*id),
+                        _ => None,
+                    };
+                    let kid_dict = xref.read_object(kid)?.to_dict()?;
+                    if kid_dict.get("T").is_none() && is_widget(&kid_dict) {
+                        if let Some(kid_id) = kid_id {
+                            visited.insert(kid_id);
+                        }
+                        let page = (self.page_index)(&kid_dict, kid_id);
+                        field
+                            .widgets
+                            .push(FieldWidget::try_new(&kid_dict, kid_id, page, xref)?);
+                    } else if let Some(kid) = self.read(kid, &field.name, &attrs, visited)? {
+                        field.kids.push(kid);
+                    }
+                }
+            }
+            None => {
+                if is_widget(&dict) {
+                    let page = (self.page_index)(&dict, id);
+                    field
+                        .widgets
+                        .push(FieldWidget::try_new(&dict, id, page, xref)?);
+                }
+            }
+        }
+        field.dict = dict;
+        Ok(field)
+    }
+}
+
+fn is_widget(dict: &PdfDict) -> bool {
+    match dict.get("Subtype") {
+        Some(PdfObject::Name(n)) => n.name() == "Widget",
+        // widgets without Subtype but with Rect are seen in the wild
+        _ => dict.get("Rect").is_some(),
+    }
+}
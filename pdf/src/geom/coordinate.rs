@@ -41,7 +41,7 @@ impl Default for Matrix {
     }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct Point {
     x: f32,
     y: f32,
@@ -54,7 +54,7 @@ impl Point {
 
     pub fn transform(&self, matrix: &Matrix) -> Point {
         let x = matrix.a * self.x + matrix.c * self.y + matrix.e;
-        let y = matrix.b * self.x + matrix.c * self.y + matrix.e;
+        let y = matrix.b * self.x + matrix.d * self.y + matrix.f;
         Point { x, y }
     }
     pub fn x(&self) -> f32 {
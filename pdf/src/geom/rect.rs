@@ -41,10 +41,24 @@ impl Rect {
             .as_number()
             .map_err(|_| PdfError::Pattern("Rect array element is not number".to_string()))?
             .real();
-        let rect = Rect::new(Point::new(llx, lly), urx - llx, ury - lly);
+        // any two diagonally opposite corners
+        let rect = Rect::new(
+            Point::new(llx.min(urx), lly.min(ury)),
+            (urx - llx).abs(),
+            (ury - lly).abs(),
+        );
         Ok(rect)
     }
 
+    // common part of two rectangles, empty rectangle has zero width or height
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let lx = self.lx().max(other.lx());
+        let ly = self.ly().max(other.ly());
+        let ux = self.ux().min(other.ux());
+        let uy = self.uy().min(other.uy());
+        Rect::new(Point::new(lx, ly), (ux - lx).max(0.0), (uy - ly).max(0.0))
+    }
+
     pub fn lower_left(&self) -> &Point {
         &self.lf
     }
@@ -1,19 +1,32 @@
+mod cache;
 mod catalog;
 mod character;
 mod crypto;
 mod filter;
 mod function;
-mod object;
+mod linearization;
 mod pagetree;
 mod patterns;
 mod reader;
 mod security;
 mod xref;
 
+pub mod action;
 pub mod color;
+pub mod destination;
 pub mod device;
+pub mod diagnostics;
 pub mod document;
 pub mod error;
+pub mod fdf;
+pub mod form;
 pub mod font;
 pub mod geom;
+pub mod metadata;
+pub mod nametree;
+pub mod object;
+pub mod outline;
 pub mod page;
+pub mod pagelabel;
+pub mod source;
+pub mod writer;
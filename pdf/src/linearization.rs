@@ -0,0 +1,336 @@
+use crate::diagnostics::{self, Diagnostic, DiagnosticCategory};
+use crate::error::{PdfError, Result};
+use crate::object::dictionary::PdfDict;
+use crate::object::PdfObject;
+use crate::reader::PdfReader;
+use crate::xref::Xref;
+
+// linearization parameter dictionary must be in the first 1024 bytes of file
+const LINEARIZATION_SEARCH_LIMIT: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct Linearization {
+    file_length: usize,
+    hint_offset: usize,
+    hint_length: usize,
+    overflow_hint: Option<(usize, usize)>,
+    first_page_object: u32,
+    first_page_end: usize,
+    page_count: u32,
+    main_xref_offset: usize,
+    first_page: u32,
+    first_page_xref_offset: usize,
+}
+
+impl Linearization {
+    // read the linearization dictionary at beginning of the file,
+    // None if the file is not linearized or was updated after linearized
+    pub fn try_new(reader: &PdfReader) -> Result<Option<Self>> {
+        match Self::read(reader) {
+            Ok(lin) => Ok(lin),
+            Err(PdfError::MissingRange(offset, len)) => Err(PdfError::MissingRange(offset, len)),
+            Err(e) => {
+                diagnostics::report(
+                    reader.diagnostics(),
+                    Diagnostic::new(
+                        DiagnosticCategory::Structure,
+                        format!("read linearization dictionary failed:{:?}", e),
+                    ),
+                )?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn read(reader: &PdfReader) -> Result<Option<Self>> {
+        reader.reset_offset(0);
+        reader.skip_white_space()?;
+        // header and the binary comment line
+        while reader.peek_byte()? == b'%' {
+            reader.read_line()?;
+            reader.skip_white_space()?;
+        }
+        let offset = reader.current_pos();
+        if offset > LINEARIZATION_SEARCH_LIMIT {
+            return Ok(None);
+        }
+        let dict = match reader.read_indirect_object(offset)? {
+            PdfObject::Dict(d) => d,
+            _ => return Ok(None),
+        };
+        if dict.get("Linearized").is_none() {
+            return Ok(None);
+        }
+        if !reader.read_token()?.is_other_key(b"endobj") {
+            return Err(PdfError::DocumentStructure(
+                "linearization dictionary need endobj".to_string(),
+            ));
+        }
+        reader.skip_white_space()?;
+        let first_page_xref_offset = reader.current_pos();
+
+        let file_length = get_usize(&dict, "L")?;
+        // an incremental update after linearized is legal, the file is just read as usual
+        if file_length != reader.size() {
+            log::info!(
+                "linearization file length {} is not {}, file is updated after linearized",
+                file_length,
+                reader.size()
+            );
+            return Ok(None);
+        }
+        let hint = dict
+            .get("H")
+            .ok_or(PdfError::DocumentStructure(
+                "linearization dictionary has no H".to_string(),
+            ))?
+            .as_array()?;
+        let mut hint_values = Vec::new();
+        for v in hint.iter() {
+            hint_values.push(v.integer()? as usize);
+        }
+        if hint_values.len() != 2 && hint_values.len() != 4 {
+            return Err(PdfError::DocumentStructure(format!(
+                "linearization hint array length {} is not 2 or 4",
+                hint_values.len()
+            )));
+        }
+        let overflow_hint = if hint_values.len() == 4 {
+            Some((hint_values[2], hint_values[3]))
+        } else {
+            None
+        };
+        let first_page = match dict.get("P") {
+            Some(p) => p.integer()? as u32,
+            None => 0,
+        };
+        Ok(Some(Linearization {
+            file_length,
+            hint_offset: hint_values[0],
+            hint_length: hint_values[1],
+            overflow_hint,
+            first_page_object: get_usize(&dict, "O")? as u32,
+            first_page_end: get_usize(&dict, "E")?,
+            page_count: get_usize(&dict, "N")? as u32,
+            main_xref_offset: get_usize(&dict, "T")?,
+            first_page,
+            first_page_xref_offset,
+        }))
+    }
+
+    pub fn file_length(&self) -> usize {
+        self.file_length
+    }
+
+    // offset and length of the primary hint stream
+    pub fn hint_stream(&self) -> (usize, usize) {
+        (self.hint_offset, self.hint_length)
+    }
+
+    pub fn overflow_hint_stream(&self) -> Option<(usize, usize)> {
+        self.overflow_hint
+    }
+
+    pub fn first_page_object(&self) -> u32 {
+        self.first_page_object
+    }
+
+    // end of the first page section, all objects of first page are before it
+    pub fn first_page_end(&self) -> usize {
+        self.first_page_end
+    }
+
+    pub fn page_count(&self) -> u32 {
+        self.page_count
+    }
+
+    pub fn main_xref_offset(&self) -> usize {
+        self.main_xref_offset
+    }
+
+    pub fn first_page(&self) -> u32 {
+        self.first_page
+    }
+
+    pub fn first_page_xref_offset(&self) -> usize {
+        self.first_page_xref_offset
+    }
+
+    pub fn page_offset_hints(&self, xref: &Xref) -> Result<PageOffsetHints> {
+        let stream = xref.read_object_at(self.hint_offset)?.to_stream()?;
+        let data = stream.decode_data(Some(xref))?;
+        PageOffsetHints::try_new(data.as_slice(), self)
+    }
+
+    // offsets in hint tables are computed as if the primary hint stream is not in file
+    fn adjust_offset(&self, offset: usize) -> usize {
+        if offset >= self.hint_offset {
+            offset + self.hint_length
+        } else {
+            offset
+        }
+    }
+}
+
+fn get_usize(dict: &PdfDict, key: &str) -> Result<usize> {
+    match dict.get(key) {
+        Some(v) => Ok(v.integer()? as usize),
+        None => Err(PdfError::DocumentStructure(format!(
+            "linearization dictionary has no {}",
+            key
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PageOffsetHint {
+    objects_num: u32,
+    offset: usize,
+    length: usize,
+    shared_objects: Vec<u32>,
+    content_offset: usize,
+    content_length: usize,
+}
+
+impl PageOffsetHint {
+    pub fn objects_num(&self) -> u32 {
+        self.objects_num
+    }
+
+    // offset of the page object in file
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // length of the page section, page object and objects used only by this page
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    // shared object identifiers in the shared object hint table
+    pub fn shared_objects(&self) -> &[u32] {
+        self.shared_objects.as_slice()
+    }
+
+    // offset of the content stream in file
+    pub fn content_offset(&self) -> usize {
+        self.content_offset
+    }
+
+    pub fn content_length(&self) -> usize {
+        self.content_length
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PageOffsetHints {
+    pages: Vec<PageOffsetHint>,
+}
+
+impl PageOffsetHints {
+    fn try_new(data: &[u8], lin: &Linearization) -> Result<Self> {
+        let mut bits = BitReader::new(data);
+        let min_objects = bits.read(32)?;
+        let first_page_offset = bits.read(32)? as usize;
+        let objects_bits = bits.read(16)?;
+        let min_length = bits.read(32)? as usize;
+        let length_bits = bits.read(16)?;
+        let min_content_offset = bits.read(32)? as usize;
+        let content_offset_bits = bits.read(16)?;
+        let min_content_length = bits.read(32)? as usize;
+        let content_length_bits = bits.read(16)?;
+        let shared_num_bits = bits.read(16)?;
+        let shared_id_bits = bits.read(16)?;
+        let numerator_bits = bits.read(16)?;
+        let _denominator = bits.read(16)?;
+
+        let n = lin.page_count as usize;
+        let mut pages = vec![PageOffsetHint::default(); n];
+        // every item of all pages are together, and each item starts at a byte boundary
+        for page in pages.iter_mut() {
+            page.objects_num = min_objects + bits.read(objects_bits)?;
+        }
+        bits.align();
+        for page in pages.iter_mut() {
+            page.length = min_length + bits.read(length_bits)? as usize;
+        }
+        bits.align();
+        let mut shared_nums = Vec::with_capacity(n);
+        for _ in 0..n {
+            shared_nums.push(bits.read(shared_num_bits)?);
+        }
+        bits.align();
+        for (page, num) in pages.iter_mut().zip(shared_nums.iter()) {
+            for _ in 0..*num {
+                page.shared_objects.push(bits.read(shared_id_bits)?);
+            }
+        }
+        bits.align();
+        for num in shared_nums.iter() {
+            for _ in 0..*num {
+                bits.read(numerator_bits)?;
+            }
+        }
+        bits.align();
+        for page in pages.iter_mut() {
+            page.content_offset = min_content_offset + bits.read(content_offset_bits)? as usize;
+        }
+        bits.align();
+        for page in pages.iter_mut() {
+            page.content_length = min_content_length + bits.read(content_length_bits)? as usize;
+        }
+
+        let mut offset = first_page_offset;
+        for page in pages.iter_mut() {
+            page.offset = lin.adjust_offset(offset);
+            page.content_offset = lin.adjust_offset(offset + page.content_offset);
+            offset += page.length;
+        }
+        Ok(PageOffsetHints { pages })
+    }
+
+    pub fn pages(&self) -> &[PageOffsetHint] {
+        self.pages.as_slice()
+    }
+
+    pub fn page(&self, index: u32) -> Option<&PageOffsetHint> {
+        self.pages.get(index as usize)
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, bit_pos: 0 }
+    }
+
+    fn read(&mut self, count: u32) -> Result<u32> {
+        if count > 32 {
+            return Err(PdfError::DocumentStructure(format!(
+                "hint table item has {} bits",
+                count
+            )));
+        }
+        let mut value: u64 = 0;
+        for _ in 0..count {
+            let byte = self
+                .data
+                .get(self.bit_pos / 8)
+                .ok_or(PdfError::DocumentStructure(
+                    "hint table is truncated".to_string(),
+                ))?;
+            let bit = (byte >> (7 - (self.bit_pos % 8))) & 1;
+            value = (value << 1) | bit as u64;
+            self.bit_pos += 1;
+        }
+        Ok(value as u32)
+    }
+
+    fn align(&mut self) {
+        self.bit_pos = self.bit_pos.div_ceil(8) * 8;
+    }
+}
@@ -0,0 +1,489 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use roxmltree::{Document as XmlDocument, Node};
+
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
+use crate::error::{PdfError, Result};
+use crate::object::dictionary::PdfDict;
+use crate::object::stream::PdfStream;
+use crate::xref::Xref;
+
+const NS_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const NS_DC: &str = "http://purl.org/dc/elements/1.1/";
+const NS_XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const NS_PDF: &str = "http://ns.adobe.com/pdf/1.3/";
+
+// date in Info dictionary `D:YYYYMMDDHHmmSSOHH'mm'` or XMP `YYYY-MM-DDThh:mm:ssTZD`,
+// fields not in the string have their default value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfDate {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    // minutes from UTC, None if the time zone is unknown
+    utc_offset: Option<i32>,
+}
+
+impl PdfDate {
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix("D:").unwrap_or(s);
+        if s.as_bytes().get(4) == Some(&b'-') {
+            return Self::parse_iso(s);
+        }
+        let digits: Vec<u8> = s.bytes().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.len() < 4 {
+            return None;
+        }
+        let field = |start: usize, len: usize, default: u32| -> u32 {
+            match digits.get(start..start + len) {
+                Some(d) => d.iter().fold(0, |v, c| v * 10 + (c - b'0') as u32),
+                None => default,
+            }
+        };
+        let date = PdfDate {
+            year: field(0, 4, 0) as u16,
+            month: field(4, 2, 1) as u8,
+            day: field(6, 2, 1) as u8,
+            hour: field(8, 2, 0) as u8,
+            minute: field(10, 2, 0) as u8,
+            second: field(12, 2, 0) as u8,
+            utc_offset: parse_utc_offset(&s[digits.len()..]),
+        };
+        date.validate()
+    }
+
+    fn parse_iso(s: &str) -> Option<Self> {
+        let (date, time) = match s.split_once('T') {
+            Some((d, t)) => (d, Some(t)),
+            None => (s, None),
+        };
+        let mut parts = date.split('-');
+        let year = parts.next()?.parse::<u16>().ok()?;
+        let month = parts
+            .next()
+            .map(|v| v.parse::<u8>().ok())
+            .unwrap_or(Some(1))?;
+        let day = parts
+            .next()
+            .map(|v| v.parse::<u8>().ok())
+            .unwrap_or(Some(1))?;
+        let mut date = PdfDate {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            utc_offset: None,
+        };
+        if let Some(time) = time {
+            let end = time.find(['Z', '+', '-']).unwrap_or(time.len());
+            let mut fields = time[..end].split(':');
+            date.hour = fields.next()?.parse().ok()?;
+            date.minute = fields.next().map(|v| v.parse().ok()).unwrap_or(Some(0))?;
+            // fraction of second is ignored
+            date.second = match fields.next() {
+                Some(v) => v.split('.').next()?.parse().ok()?,
+                None => 0,
+            };
+            date.utc_offset = parse_utc_offset(&time[end..]);
+        }
+        date.validate()
+    }
+
+    fn validate(self) -> Option<Self> {
+        if (1..=12).contains(&self.month)
+            && (1..=31).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+        {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn utc_offset(&self) -> Option<i32> {
+        self.utc_offset
+    }
+}
+
+// ISO 8601, same as XMP dates
+impl Display for PdfDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        match self.utc_offset {
+            Some(0) => write!(f, "Z"),
+            Some(m) => {
+                let sign = if m < 0 { '-' } else { '+' };
+                write!(f, "{}{:02}:{:02}", sign, m.abs() / 60, m.abs() % 60)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+// `Z`, `+HH'mm'` or `+HH:mm`
+fn parse_utc_offset(s: &str) -> Option<i32> {
+    let sign = match s.chars().next()? {
+        'Z' => return Some(0),
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: Vec<i32> = s[1..]
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| d as i32)
+        .collect();
+    let hour = digits.get(..2).map(|d| d[0] * 10 + d[1])?;
+    let minute = digits.get(2..4).map(|d| d[0] * 10 + d[1]).unwrap_or(0);
+    Some(sign * (hour * 60 + minute))
+}
+
+// document information dictionary, trailer `/Info`
+#[derive(Debug, Clone, Default)]
+pub struct DocumentInfo {
+    title: Option<String>,
+    author: Option<String>,
+    subject: Option<String>,
+    keywords: Option<String>,
+    creator: Option<String>,
+    producer: Option<String>,
+    creation_date: Option<PdfDate>,
+    mod_date: Option<PdfDate>,
+    custom: BTreeMap<String, String>,
+}
+
+const INFO_KEYS: [&str; 9] = [
+    "Title",
+    "Author",
+    "Subject",
+    "Keywords",
+    "Creator",
+    "Producer",
+    "CreationDate",
+    "ModDate",
+    "Trapped",
+];
+
+impl DocumentInfo {
+    // an entry which is not a text string is reported and skipped
+    pub fn try_new(info: &PdfDict, xref: &Xref) -> Result<Self> {
+        let text = |key: &str| -> Result<Option<String>> {
+            let value = match info.get(key) {
+                Some(v) => xref.read_object(v)?,
+                None => return Ok(None),
+            };
+            match value {
+                v if v.is_null() => Ok(None),
+                v => match v.as_text_string() {
+                    Ok(s) => Ok(Some(s)),
+                    Err(e) => {
+                        xref.diagnostics().warn(Diagnostic::new(
+                            DiagnosticCategory::Structure,
+                            format!("document information {} is skipped:{}", key, e),
+                        ))?;
+                        Ok(None)
+                    }
+                },
+            }
+        };
+        let mut custom = BTreeMap::new();
+        for key in info.entries().keys() {
+            if INFO_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            if let Some(value) = text(key)? {
+                custom.insert(key.to_owned(), value);
+            }
+        }
+        Ok(DocumentInfo {
+            title: text("Title")?,
+            author: text("Author")?,
+            subject: text("Subject")?,
+            keywords: text("Keywords")?,
+            creator: text("Creator")?,
+            producer: text("Producer")?,
+            creation_date: text("CreationDate")?.and_then(|d| PdfDate::parse(&d)),
+            mod_date: text("ModDate")?.and_then(|d| PdfDate::parse(&d)),
+            custom,
+        })
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    pub fn keywords(&self) -> Option<&str> {
+        self.keywords.as_deref()
+    }
+
+    // application created the original document
+    pub fn creator(&self) -> Option<&str> {
+        self.creator.as_deref()
+    }
+
+    // application converted the document to pdf
+    pub fn producer(&self) -> Option<&str> {
+        self.producer.as_deref()
+    }
+
+    pub fn creation_date(&self) -> Option<&PdfDate> {
+        self.creation_date.as_ref()
+    }
+
+    pub fn mod_date(&self) -> Option<&PdfDate> {
+        self.mod_date.as_ref()
+    }
+
+    // entries other than the standard ones, only text strings
+    pub fn custom(&self) -> &BTreeMap<String, String> {
+        &self.custom
+    }
+}
+
+// XMP metadata stream of the catalog, with the common Dublin Core, xmp and pdf properties
+#[derive(Debug, Clone, Default)]
+pub struct XmpMetadata {
+    xml: String,
+    title: Option<String>,
+    description: Option<String>,
+    creators: Vec<String>,
+    subjects: Vec<String>,
+    creator_tool: Option<String>,
+    create_date: Option<PdfDate>,
+    modify_date: Option<PdfDate>,
+    metadata_date: Option<PdfDate>,
+    producer: Option<String>,
+    keywords: Option<String>,
+    pdf_version: Option<String>,
+}
+
+impl XmpMetadata {
+    pub fn try_new(stream: &PdfStream, xref: &Xref) -> Result<Self> {
+        let data = stream.decode_data(Some(xref))?;
+        Self::parse(String::from_utf8_lossy(data.as_slice()).as_ref())
+    }
+
+    pub fn parse(xml: &str) -> Result<Self> {
+        let xml = xml.trim_start_matches('\u{feff}').trim();
+        let doc = XmlDocument::parse(xml)
+            .map_err(|e| PdfError::Metadata(format!("parse XMP failed:{}", e)))?;
+        let mut metadata = XmpMetadata {
+            xml: xml.to_string(),
+            ..Default::default()
+        };
+        let descriptions = doc
+            .descendants()
+            .filter(|n| n.has_tag_name((NS_RDF, "Description")));
+        for description in descriptions {
+            // simple properties can be attributes of rdf:Description
+            for attr in description.attributes() {
+                if let Some(ns) = attr.namespace() {
+                    metadata.set_property(ns, attr.name(), vec![attr.value().to_string()]);
+                }
+            }
+            for property in description.children().filter(|n| n.is_element()) {
+                if let Some(ns) = property.tag_name().namespace() {
+                    metadata.set_property(
+                        ns,
+                        property.tag_name().name(),
+                        property_values(property),
+                    );
+                }
+            }
+        }
+        Ok(metadata)
+    }
+
+    fn set_property(&mut self, ns: &str, name: &str, values: Vec<String>) {
+        let first = values.first().cloned();
+        match (ns, name) {
+            (NS_DC, "title") => self.title = first,
+            (NS_DC, "description") => self.description = first,
+            (NS_DC, "creator") => self.creators = values,
+            (NS_DC, "subject") => self.subjects = values,
+            (NS_XMP, "CreatorTool") => self.creator_tool = first,
+            (NS_XMP, "CreateDate") => self.create_date = first.and_then(|d| PdfDate::parse(&d)),
+            (NS_XMP, "ModifyDate") => self.modify_date = first.and_then(|d| PdfDate::parse(&d)),
+            (NS_XMP, "MetadataDate") => self.metadata_date = first.and_then(|d| PdfDate::parse(&d)),
+            (NS_PDF, "Producer") => self.producer = first,
+            (NS_PDF, "Keywords") => self.keywords = first,
+            (NS_PDF, "PDFVersion") => self.pdf_version = first,
+            _ => {}
+        }
+    }
+
+    // the whole XMP packet, for properties not extracted
+    pub fn xml(&self) -> &str {
+        self.xml.as_str()
+    }
+
+    // dc:title, the x-default language
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    // dc:creator, authors in order
+    pub fn creators(&self) -> &[String] {
+        self.creators.as_slice()
+    }
+
+    // dc:subject, the keywords
+    pub fn subjects(&self) -> &[String] {
+        self.subjects.as_slice()
+    }
+
+    pub fn creator_tool(&self) -> Option<&str> {
+        self.creator_tool.as_deref()
+    }
+
+    pub fn create_date(&self) -> Option<&PdfDate> {
+        self.create_date.as_ref()
+    }
+
+    pub fn modify_date(&self) -> Option<&PdfDate> {
+        self.modify_date.as_ref()
+    }
+
+    pub fn metadata_date(&self) -> Option<&PdfDate> {
+        self.metadata_date.as_ref()
+    }
+
+    pub fn producer(&self) -> Option<&str> {
+        self.producer.as_deref()
+    }
+
+    pub fn keywords(&self) -> Option<&str> {
+        self.keywords.as_deref()
+    }
+
+    pub fn pdf_version(&self) -> Option<&str> {
+        self.pdf_version.as_deref()
+    }
+}
+
+// text of a simple property, or items of rdf:Alt, rdf:Seq and rdf:Bag.
+// In rdf:Alt the x-default item is the first
+fn property_values(property: Node) -> Vec<String> {
+    let container = property.children().find(|n| {
+        n.has_tag_name((NS_RDF, "Alt"))
+            || n.has_tag_name((NS_RDF, "Seq"))
+            || n.has_tag_name((NS_RDF, "Bag"))
+    });
+    let container = match container {
+        Some(c) => c,
+        None => return vec![property.text().unwrap_or_default().trim().to_string()],
+    };
+    let mut values = Vec::new();
+    for li in container
+        .children()
+        .filter(|n| n.has_tag_name((NS_RDF, "li")))
+    {
+        let text = li.text().unwrap_or_default().trim().to_string();
+        let lang = li.attribute(("http://www.w3.org/XML/1998/namespace", "lang"));
+        if lang == Some("x-default") {
+            values.insert(0, text);
+        } else {
+            values.push(text);
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PdfDate, XmpMetadata};
+
+    #[test]
+    fn test_parse_date() {
+        let d = PdfDate::parse("D:20230512082127+03'00'").unwrap();
+        assert_eq!((d.year(), d.month(), d.day()), (2023, 5, 12));
+        assert_eq!((d.hour(), d.minute(), d.second()), (8, 21, 27));
+        assert_eq!(d.utc_offset(), Some(180));
+        assert_eq!(d.to_string(), "2023-05-12T08:21:27+03:00");
+
+        let d = PdfDate::parse("D:1999").unwrap();
+        assert_eq!((d.year(), d.month(), d.day()), (1999, 1, 1));
+        assert_eq!(d.utc_offset(), None);
+        assert_eq!(PdfDate::parse("D:19991301").map(|d| d.month()), None);
+
+        let d = PdfDate::parse("2023-12-06T07:01:18.25Z").unwrap();
+        assert_eq!(d.to_string(), "2023-12-06T07:01:18Z");
+        let d = PdfDate::parse("2001-02-03T04:05-08:30").unwrap();
+        assert_eq!(d.utc_offset(), Some(-510));
+    }
+
+    #[test]
+    fn test_parse_xmp() {
+        let xml = r#"<?xpacket begin='' id='W5M0MpCehiHzreSzNTczkc9d'?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+    <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/">
+      <dc:title><rdf:Alt>
+        <rdf:li xml:lang="fr">Titre</rdf:li>
+        <rdf:li xml:lang="x-default">Title</rdf:li>
+      </rdf:Alt></dc:title>
+      <dc:subject><rdf:Bag><rdf:li>a</rdf:li><rdf:li>b</rdf:li></rdf:Bag></dc:subject>
+    </rdf:Description>
+    <rdf:Description rdf:about="" xmlns:pdf="http://ns.adobe.com/pdf/1.3/" pdf:Producer="rspdf">
+      <pdf:Keywords>a, b</pdf:Keywords>
+    </rdf:Description>
+  </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end='w'?>"#;
+        let xmp = XmpMetadata::parse(xml).unwrap();
+        assert_eq!(xmp.title(), Some("Title"));
+        assert_eq!(xmp.subjects(), &["a".to_string(), "b".to_string()]);
+        assert_eq!(xmp.producer(), Some("rspdf"));
+        assert_eq!(xmp.keywords(), Some("a, b"));
+        assert!(XmpMetadata::parse("<x:xmpmeta>").is_err());
+    }
+}
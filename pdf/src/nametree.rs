@@ -0,0 +1,214 @@
+use std::collections::{HashSet, VecDeque};
+use std::marker::PhantomData;
+
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
+use crate::error::{PdfError, Result};
+use crate::object::dictionary::PdfDict;
+use crate::object::{ObjectId, PdfObject};
+use crate::xref::Xref;
+
+// key of name tree or number tree, and the key of the array holding entries
+pub trait TreeKey: Sized + PartialOrd {
+    const ENTRIES: &'static str;
+
+    fn from_object(obj: &PdfObject) -> Result<Self>;
+}
+
+// name tree key is a string, compared as bytes
+impl TreeKey for Vec<u8> {
+    const ENTRIES: &'static str = "Names";
+
+    fn from_object(obj: &PdfObject) -> Result<Self> {
+        obj.string_bytes()
+    }
+}
+
+impl TreeKey for i32 {
+    const ENTRIES: &'static str = "Nums";
+
+    fn from_object(obj: &PdfObject) -> Result<Self> {
+        obj.integer()
+    }
+}
+
+// tree of /Kids, leaves have /Names or /Nums arrays of key value pairs,
+// values are returned as they are stored, may be indirect objects
+#[derive(Debug)]
+pub struct Tree<'a, K> {
+    root: PdfDict,
+    root_id: Option<ObjectId>,
+    xref: &'a Xref,
+    key: PhantomData<K>,
+}
+
+pub type NameTree<'a> = Tree<'a, Vec<u8>>;
+pub type NumberTree<'a> = Tree<'a, i32>;
+
+impl<'a, K: TreeKey> Tree<'a, K> {
+    // root can be an indirect object, it is read with xref
+    pub(crate) fn try_new(root: &PdfObject, xref: &'a Xref) -> Result<Self> {
+        let root_id = match root {
+            PdfObject::Indirect(id) => Some(*id),
+            _ => None,
+        };
+        let root = xref.read_object(root)?.to_dict()?;
+        Ok(Tree {
+            root,
+            root_id,
+            xref,
+            key: PhantomData,
+        })
+    }
+
+    fn visited(&self) -> HashSet<ObjectId> {
+        self.root_id.into_iter().collect()
+    }
+
+    pub fn root(&self) -> &PdfDict {
+        &self.root
+    }
+
+    // value of the key, kids out of /Limits are skipped
+    pub fn get(&self, key: &K) -> Result<Option<PdfObject>> {
+        let mut visited = self.visited();
+        self.find(&self.root, key, &mut visited)
+    }
+
+    fn find(
+        &self,
+        node: &PdfDict,
+        key: &K,
+        visited: &mut HashSet<ObjectId>,
+    ) -> Result<Option<PdfObject>> {
+        if let Some(entries) = node.get(K::ENTRIES) {
+            let entries = self.xref.read_object(entries)?;
+            let entries = entries.as_array()?;
+            for i in (0..entries.len().saturating_sub(1)).step_by(2) {
+                if &K::from_object(&self.xref.read_object(entries.get(i).unwrap())?)? == key {
+                    return Ok(entries.get(i + 1).cloned());
+                }
+            }
+        }
+        if let Some(kids) = node.get("Kids") {
+            let kids = self.xref.read_object(kids)?;
+            for kid in kids.as_array()?.iter() {
+                let kid = match self.read_node(kid, visited)? {
+                    Some(kid) => kid,
+                    None => continue,
+                };
+                if let Some(limits) = kid.get("Limits") {
+                    let limits = self.xref.read_object(limits)?;
+                    let limits = limits.as_array()?;
+                    if let (Some(low), Some(high)) = (limits.get(0), limits.get(1)) {
+                        let low = K::from_object(&self.xref.read_object(low)?)?;
+                        let high = K::from_object(&self.xref.read_object(high)?)?;
+                        if key < &low || key > &high {
+                            continue;
+                        }
+                    }
+                }
+                if let Some(value) = self.find(&kid, key, visited)? {
+                    return Ok(Some(value));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // None if the node is visited before
+    fn read_node(
+        &self,
+        node: &PdfObject,
+        visited: &mut HashSet<ObjectId>,
+    ) -> Result<Option<PdfDict>> {
+        if let PdfObject::Indirect(id) = node {
+            if !visited.insert(*id) {
+                self.xref.diagnostics().warn(
+                    Diagnostic::new(
+                        DiagnosticCategory::Structure,
+                        format!("{} tree node is visited twice", K::ENTRIES),
+                    )
+                    .with_object(*id),
+                )?;
+                return Ok(None);
+            }
+        }
+        match self.xref.read_object(node)? {
+            PdfObject::Dict(d) => Ok(Some(d)),
+            o => Err(PdfError::DocumentStructure(format!(
+                "{} tree node need dict got:{:?}",
+                K::ENTRIES,
+                o
+            ))),
+        }
+    }
+
+    // all entries in tree order, stop at the first error
+    pub fn iter(&self) -> TreeIter<'_, 'a, K> {
+        TreeIter {
+            tree: self,
+            nodes: vec![PdfObject::Dict(self.root.to_owned())],
+            entries: VecDeque::new(),
+            visited: self.visited(),
+        }
+    }
+}
+
+pub struct TreeIter<'t, 'a, K> {
+    tree: &'t Tree<'a, K>,
+    nodes: Vec<PdfObject>,
+    entries: VecDeque<(K, PdfObject)>,
+    visited: HashSet<ObjectId>,
+}
+
+impl<K: TreeKey> TreeIter<'_, '_, K> {
+    // read the next node, push its kids and entries
+    fn read_next_node(&mut self) -> Result<()> {
+        let node = match self.nodes.pop() {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+        let node = match self.tree.read_node(&node, &mut self.visited)? {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+        let xref = self.tree.xref;
+        if let Some(entries) = node.get(K::ENTRIES) {
+            let entries = xref.read_object(entries)?;
+            let entries = entries.as_array()?;
+            for i in (0..entries.len().saturating_sub(1)).step_by(2) {
+                let key = K::from_object(&xref.read_object(entries.get(i).unwrap())?)?;
+                self.entries
+                    .push_back((key, entries.get(i + 1).unwrap().to_owned()));
+            }
+        }
+        if let Some(kids) = node.get("Kids") {
+            let kids = xref.read_object(kids)?;
+            let kids = kids.as_array()?;
+            // stack of nodes, the first kid is on the top
+            for i in (0..kids.len()).rev() {
+                self.nodes.push(kids.get(i).unwrap().to_owned());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K: TreeKey> Iterator for TreeIter<'_, '_, K> {
+    type Item = Result<(K, PdfObject)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.entries.pop_front() {
+                return Some(Ok(entry));
+            }
+            if self.nodes.is_empty() {
+                return None;
+            }
+            if let Err(e) = self.read_next_node() {
+                self.nodes.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
@@ -13,6 +13,10 @@ impl PdfArray {
         self.elements.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
     pub fn get(&self, index: usize) -> Option<&PdfObject> {
         self.elements.get(index)
     }
@@ -19,4 +19,12 @@ impl PdfDict {
     pub fn entries(&self) -> &HashMap<String, PdfObject> {
         &self.entries
     }
+
+    pub fn insert(&mut self, key: &str, obj: PdfObject) -> Option<PdfObject> {
+        self.entries.insert(key.to_string(), obj)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<PdfObject> {
+        self.entries.remove(key)
+    }
 }
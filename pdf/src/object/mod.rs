@@ -5,7 +5,7 @@ use crate::object::dictionary::PdfDict;
 use crate::object::name::PdfName;
 use crate::object::number::PdfNumber;
 use crate::object::stream::PdfStream;
-use crate::object::string::{PdfHexString, PdfLiteral};
+use crate::object::string::{decode_text_string, PdfHexString, PdfLiteral};
 
 pub mod array;
 pub mod bool;
@@ -107,6 +107,30 @@ impl PdfObject {
         }
     }
 
+    // decoded text string, from literal or hex string
+    pub fn as_text_string(&self) -> Result<String> {
+        match self {
+            PdfObject::LiteralString(s) => Ok(decode_text_string(s.bytes())),
+            PdfObject::HexString(s) => Ok(decode_text_string(s.raw_bytes()?.as_slice())),
+            _ => Err(PdfError::Object(format!(
+                "PdfObject as text string need LiteralString or HexString got:{:?}",
+                self
+            ))),
+        }
+    }
+
+    // bytes of literal or hex string, not decoded as text
+    pub fn string_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            PdfObject::LiteralString(s) => Ok(s.bytes().to_vec()),
+            PdfObject::HexString(s) => s.raw_bytes(),
+            _ => Err(PdfError::Object(format!(
+                "PdfObject string bytes need LiteralString or HexString got:{:?}",
+                self
+            ))),
+        }
+    }
+
     pub fn as_name(&self) -> Result<&PdfName> {
         match self {
             PdfObject::Name(name) => Ok(name),
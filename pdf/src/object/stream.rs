@@ -1,3 +1,4 @@
+use crate::diagnostics::Diagnostics;
 use crate::error::{PdfError, Result};
 
 use crate::filter::apply_filter;
@@ -38,13 +39,26 @@ impl PdfStream {
             },
             None => None,
         };
+        self.apply_filters(params, xref.map(|x| x.diagnostics()))
+    }
+
+    // for streams read before the xref is loaded, their DecodeParms are direct objects
+    pub(crate) fn decode_data_with(&self, diagnostics: Option<&Diagnostics>) -> Result<Vec<u8>> {
+        self.apply_filters(self.dict.get("DecodeParms").cloned(), diagnostics)
+    }
+
+    fn apply_filters(
+        &self,
+        params: Option<PdfObject>,
+        diagnostics: Option<&Diagnostics>,
+    ) -> Result<Vec<u8>> {
         if let Some(filter) = self.dict.get("Filter") {
             match filter {
                 PdfObject::Name(n) => match params {
                     Some(PdfObject::Dict(d)) => {
-                        apply_filter(n.name(), self.data.as_slice(), Some(&d))
+                        apply_filter(n.name(), self.data.as_slice(), Some(&d), diagnostics)
                     }
-                    None => apply_filter(n.name(), self.data.as_slice(), None),
+                    None => apply_filter(n.name(), self.data.as_slice(), None, diagnostics),
                     _ => Err(PdfError::Filter("Filter param must be Dict ".to_string())),
                 },
                 PdfObject::Array(filters) => match params {
@@ -56,6 +70,7 @@ impl PdfStream {
                                 name.as_name()?.name(),
                                 data.as_slice(),
                                 Some(param.as_dict()?),
+                                diagnostics,
                             )?;
                         }
                         Ok(data)
@@ -63,7 +78,12 @@ impl PdfStream {
                     None => {
                         let mut data = self.data.clone();
                         for name in filters.iter() {
-                            data = apply_filter(name.as_name()?.name(), data.as_slice(), None)?;
+                            data = apply_filter(
+                                name.as_name()?.name(),
+                                data.as_slice(),
+                                None,
+                                diagnostics,
+                            )?;
                         }
                         Ok(data)
                     }
@@ -1,4 +1,5 @@
 use crate::error::{PdfError, Result};
+use crate::font::encoding::FontEncoding;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct PdfLiteral {
@@ -36,6 +37,60 @@ impl PdfHexString {
     }
 }
 
+// text string is UTF-16BE or UTF-8 with byte order mark, otherwise PDFDocEncoding
+pub fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(data) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        let mut units = Vec::with_capacity(data.len() / 2);
+        let mut in_language = false;
+        for pair in data.chunks_exact(2) {
+            let unit = u16::from_be_bytes([pair[0], pair[1]]);
+            // language and country code is between two ESC
+            if unit == 0x1b {
+                in_language = !in_language;
+                continue;
+            }
+            if !in_language {
+                units.push(unit);
+            }
+        }
+        return String::from_utf16_lossy(units.as_slice());
+    }
+    if let Some(data) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return String::from_utf8_lossy(data).into_owned();
+    }
+    bytes
+        .iter()
+        .map(|b| {
+            FontEncoding::PdfDoc
+                .unicode_from_charcode(*b)
+                .and_then(char::from_u32)
+                .filter(|c| *c != '\0' || *b == 0)
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+        .collect()
+}
+
+// PDFDocEncoding if every character is in it, otherwise UTF-16BE with byte order mark
+pub fn encode_text_string(text: &str) -> Vec<u8> {
+    let pdfdoc: Option<Vec<u8>> = text
+        .chars()
+        .map(|c| {
+            let unit = u16::try_from(c as u32).ok()?;
+            FontEncoding::PdfDoc
+                .charcode_from_unicode(&unit)
+                .map(|code| code as u8)
+        })
+        .collect();
+    if let Some(bytes) = pdfdoc {
+        return bytes;
+    }
+    let mut bytes = vec![0xfe, 0xff];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    bytes
+}
+
 fn hex_to_u8(c: u8) -> Result<u8> {
     match c {
         b'A'..=b'F' => Ok(c - b'A' + 10),
@@ -47,3 +102,43 @@ fn hex_to_u8(c: u8) -> Result<u8> {
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_text_string, encode_text_string};
+
+    #[test]
+    fn test_decode_text_string() {
+        assert_eq!(decode_text_string(b"Hello"), "Hello");
+        // PDFDocEncoding bullet and em dash
+        assert_eq!(decode_text_string(&[0x80, 0x20, 0x84]), "\u{2022} \u{2014}");
+        assert_eq!(
+            decode_text_string(&[0xfe, 0xff, 0x00, 0x48, 0x4e, 0x2d]),
+            "H\u{4e2d}"
+        );
+        assert_eq!(
+            decode_text_string(&[
+                0xfe, 0xff, 0x00, 0x1b, 0x00, 0x65, 0x00, 0x6e, 0x00, 0x1b, 0x00, 0x41
+            ]),
+            "A"
+        );
+        assert_eq!(
+            decode_text_string(&[0xef, 0xbb, 0xbf, 0xc3, 0xa9]),
+            "\u{e9}"
+        );
+    }
+
+    #[test]
+    fn test_encode_text_string() {
+        assert_eq!(encode_text_string("Hello"), b"Hello".to_vec());
+        assert_eq!(encode_text_string("\u{2022}"), vec![0x80]);
+        assert_eq!(
+            encode_text_string("H\u{4e2d}"),
+            vec![0xfe, 0xff, 0x00, 0x48, 0x4e, 0x2d]
+        );
+        assert_eq!(
+            decode_text_string(&encode_text_string("\u{1f600}")),
+            "\u{1f600}"
+        );
+    }
+}
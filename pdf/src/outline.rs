@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+
+use crate::destination::Destination;
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
+use crate::error::{PdfError, Result, ResultExt};
+use crate::object::dictionary::PdfDict;
+use crate::object::{ObjectId, PdfObject};
+use crate::xref::Xref;
+
+const ITALIC: i32 = 1;
+const BOLD: i32 = 2;
+
+#[derive(Debug, Clone, Default)]
+pub struct OutlineItem {
+    title: String,
+    destination: Option<Destination>,
+    action: Option<PdfDict>,
+    color: Option<[f32; 3]>,
+    flags: i32,
+    count: i32,
+    children: Vec<OutlineItem>,
+}
+
+impl OutlineItem {
+    pub fn title(&self) -> &str {
+        self.title.as_str()
+    }
+
+    // destination of /Dest or of a GoTo action
+    pub fn destination(&self) -> Option<&Destination> {
+        self.destination.as_ref()
+    }
+
+    // action dictionary /A
+    pub fn action(&self) -> Option<&PdfDict> {
+        self.action.as_ref()
+    }
+
+    // rgb color of the title, None is black
+    pub fn color(&self) -> Option<[f32; 3]> {
+        self.color
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.flags & ITALIC != 0
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.flags & BOLD != 0
+    }
+
+    // positive count means the item is open and children are shown
+    pub fn is_open(&self) -> bool {
+        self.count > 0
+    }
+
+    // number of visible descendants if open, negative if closed
+    pub fn count(&self) -> i32 {
+        self.count
+    }
+
+    pub fn children(&self) -> &[OutlineItem] {
+        self.children.as_slice()
+    }
+}
+
+// read items of the outline dictionary, resolve map /Dest or GoTo /D to destination
+pub(crate) fn read_outlines<F>(
+    outlines: &PdfDict,
+    xref: &Xref,
+    resolve: &F,
+) -> Result<Vec<OutlineItem>>
+where
+    F: Fn(&PdfObject) -> Result<Option<Destination>>,
+{
+    let mut visited = HashSet::new();
+    match outlines.get("First") {
+        Some(first) => read_items(first, xref, resolve, &mut visited),
+        None => Ok(Vec::new()),
+    }
+}
+
+// items linked by /Next, an item already visited means /First or /Next make a cycle
+fn read_items<F>(
+    first: &PdfObject,
+    xref: &Xref,
+    resolve: &F,
+    visited: &mut HashSet<ObjectId>,
+) -> Result<Vec<OutlineItem>>
+where
+    F: Fn(&PdfObject) -> Result<Option<Destination>>,
+{
+    let mut items = Vec::new();
+    let mut next = Some(first.to_owned());
+    while let Some(obj) = next {
+        if let PdfObject::Indirect(id) = obj {
+            if !visited.insert(id) {
+                xref.diagnostics().warn(
+                    Diagnostic::new(
+                        DiagnosticCategory::Structure,
+                        "outline item is visited twice, stop at the cycle".to_string(),
+                    )
+                    .with_object(id),
+                )?;
+                break;
+            }
+        }
+        let dict = match xref.read_object(&obj)? {
+            PdfObject::Dict(d) => d,
+            PdfObject::Null => break,
+            o => {
+                return Err(PdfError::DocumentStructure(format!(
+                    "outline item need dict got:{:?}",
+                    o
+                )))
+            }
+        };
+        let item = read_item(&dict, xref, resolve, visited);
+        let item = match obj {
+            PdfObject::Indirect(id) => item.with_object(id)?,
+            _ => item?,
+        };
+        items.push(item);
+        next = dict.get("Next").cloned();
+    }
+    Ok(items)
+}
+
+fn read_item<F>(
+    dict: &PdfDict,
+    xref: &Xref,
+    resolve: &F,
+    visited: &mut HashSet<ObjectId>,
+) -> Result<OutlineItem>
+where
+    F: Fn(&PdfObject) -> Result<Option<Destination>>,
+{
+    let mut item = OutlineItem::default();
+    if let Some(title) = dict.get("Title") {
+        item.title = xref.read_object(title)?.as_text_string()?;
+    }
+    if let Some(action) = dict.get("A") {
+        if let PdfObject::Dict(action) = xref.read_object(action)? {
+            item.action = Some(action);
+        }
+    }
+    if let Some(dest) = dict.get("Dest") {
+        item.destination = resolve(dest)?;
+    } else if let Some(action) = &item.action {
+        let is_goto = matches!(action.get("S"), Some(PdfObject::Name(n)) if n.name() == "GoTo");
+        if let (true, Some(dest)) = (is_goto, action.get("D")) {
+            item.destination = resolve(dest)?;
+        }
+    }
+    if let Some(PdfObject::Array(c)) = dict.get("C").map(|c| xref.read_object(c)).transpose()? {
+        if c.len() == 3 {
+            let mut color = [0.0; 3];
+            for (v, o) in color.iter_mut().zip(c.iter()) {
+                *v = o.as_number()?.real();
+            }
+            item.color = Some(color);
+        }
+    }
+    if let Some(f) = dict.get("F") {
+        item.flags = xref.read_object(f)?.integer()?;
+    }
+    if let Some(count) = dict.get("Count") {
+        item.count = xref.read_object(count)?.integer()?;
+    }
+    if let Some(first) = dict.get("First") {
+        item.children = read_items(first, xref, resolve, visited)?;
+    }
+    Ok(item)
+}
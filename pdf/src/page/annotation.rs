@@ -0,0 +1,588 @@
+use crate::error::{PdfError, Result};
+use crate::geom::coordinate::Point;
+use crate::geom::rect::Rect;
+use crate::object::array::PdfArray;
+use crate::object::dictionary::PdfDict;
+use crate::object::stream::PdfStream;
+use crate::object::{ObjectId, PdfObject};
+use crate::xref::Xref;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationSubtype {
+    Text,
+    Link,
+    FreeText,
+    Line,
+    Square,
+    Circle,
+    Polygon,
+    PolyLine,
+    Highlight,
+    Underline,
+    Squiggly,
+    StrikeOut,
+    Caret,
+    Stamp,
+    Ink,
+    Popup,
+    FileAttachment,
+    Sound,
+    Movie,
+    Screen,
+    Widget,
+    PrinterMark,
+    TrapNet,
+    Watermark,
+    ThreeD,
+    Redact,
+    Other(String),
+}
+
+impl AnnotationSubtype {
+    pub fn new(subtype: &str) -> Self {
+        match subtype {
+            "Text" => AnnotationSubtype::Text,
+            "Link" => AnnotationSubtype::Link,
+            "FreeText" => AnnotationSubtype::FreeText,
+            "Line" => AnnotationSubtype::Line,
+            "Square" => AnnotationSubtype::Square,
+            "Circle" => AnnotationSubtype::Circle,
+            "Polygon" => AnnotationSubtype::Polygon,
+            "PolyLine" => AnnotationSubtype::PolyLine,
+            "Highlight" => AnnotationSubtype::Highlight,
+            "Underline" => AnnotationSubtype::Underline,
+            "Squiggly" => AnnotationSubtype::Squiggly,
+            "StrikeOut" => AnnotationSubtype::StrikeOut,
+            "Caret" => AnnotationSubtype::Caret,
+            "Stamp" => AnnotationSubtype::Stamp,
+            "Ink" => AnnotationSubtype::Ink,
+            "Popup" => AnnotationSubtype::Popup,
+            "FileAttachment" => AnnotationSubtype::FileAttachment,
+            "Sound" => AnnotationSubtype::Sound,
+            "Movie" => AnnotationSubtype::Movie,
+            "Screen" => AnnotationSubtype::Screen,
+            "Widget" => AnnotationSubtype::Widget,
+            "PrinterMark" => AnnotationSubtype::PrinterMark,
+            "TrapNet" => AnnotationSubtype::TrapNet,
+            "Watermark" => AnnotationSubtype::Watermark,
+            "3D" => AnnotationSubtype::ThreeD,
+            "Redact" => AnnotationSubtype::Redact,
+            _ => AnnotationSubtype::Other(subtype.to_string()),
+        }
+    }
+
+    // markup annotations carry reviewer comments, like author and popup
+    pub fn is_markup(&self) -> bool {
+        !matches!(
+            self,
+            AnnotationSubtype::Link
+                | AnnotationSubtype::Popup
+                | AnnotationSubtype::Movie
+                | AnnotationSubtype::Screen
+                | AnnotationSubtype::Widget
+                | AnnotationSubtype::PrinterMark
+                | AnnotationSubtype::TrapNet
+                | AnnotationSubtype::Watermark
+                | AnnotationSubtype::ThreeD
+                | AnnotationSubtype::Other(_)
+        )
+    }
+}
+
+// bits of annotation /F
+pub const INVISIBLE: u32 = 1;
+pub const HIDDEN: u32 = 1 << 1;
+pub const PRINT: u32 = 1 << 2;
+pub const NO_ZOOM: u32 = 1 << 3;
+pub const NO_ROTATE: u32 = 1 << 4;
+pub const NO_VIEW: u32 = 1 << 5;
+pub const READ_ONLY: u32 = 1 << 6;
+pub const LOCKED: u32 = 1 << 7;
+pub const TOGGLE_NO_VIEW: u32 = 1 << 8;
+pub const LOCKED_CONTENTS: u32 = 1 << 9;
+
+// annotations shown on screen or printed, selected by flags
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AnnotationIntent {
+    #[default]
+    View,
+    Print,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Border {
+    horizontal_radius: f32,
+    vertical_radius: f32,
+    width: f32,
+    style: String,
+    dash: Vec<f32>,
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Border {
+            horizontal_radius: 0.0,
+            vertical_radius: 0.0,
+            width: 1.0,
+            style: "S".to_string(),
+            dash: vec![3.0],
+        }
+    }
+}
+
+impl Border {
+    // /BS border style dictionary take precedence over /Border array
+    fn try_new(dict: &PdfDict, xref: &Xref) -> Result<Self> {
+        let mut border = Border::default();
+        if let Some(b) = dict.get("Border") {
+            let b = xref.read_object(b)?;
+            let b = b.as_array()?;
+            let values = numbers(b, xref)?;
+            if values.len() >= 3 {
+                border.horizontal_radius = values[0];
+                border.vertical_radius = values[1];
+                border.width = values[2];
+            }
+            if let Some(PdfObject::Array(dash)) = b.get(3) {
+                border.style = "D".to_string();
+                border.dash = numbers(dash, xref)?;
+            }
+        }
+        if let Some(bs) = dict.get("BS") {
+            let bs = xref.read_object(bs)?;
+            if let Some(w) = bs.get_from_dict("W") {
+                border.width = xref.read_object(w)?.as_number()?.real();
+            }
+            if let Some(s) = bs.get_from_dict("S") {
+                border.style = xref.read_object(s)?.as_name()?.name().to_string();
+            }
+            if let Some(d) = bs.get_from_dict("D") {
+                border.dash = numbers(xref.read_object(d)?.as_array()?, xref)?;
+            }
+        }
+        Ok(border)
+    }
+
+    pub fn horizontal_radius(&self) -> f32 {
+        self.horizontal_radius
+    }
+
+    pub fn vertical_radius(&self) -> f32 {
+        self.vertical_radius
+    }
+
+    // 0 means no border is drawn
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    // S solid, D dashed, B beveled, I inset, U underline
+    pub fn style(&self) -> &str {
+        self.style.as_str()
+    }
+
+    pub fn dash(&self) -> &[f32] {
+        self.dash.as_slice()
+    }
+}
+
+// fields only some subtypes have
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationKind {
+    Link {
+        destination: Option<PdfObject>,
+        action: Option<PdfDict>,
+        highlight: String,
+    },
+    Text {
+        open: bool,
+        icon: String,
+        state: Option<String>,
+        state_model: Option<String>,
+    },
+    TextMarkup,
+    Ink {
+        ink_list: Vec<Vec<Point>>,
+    },
+    FreeText {
+        default_appearance: Option<String>,
+        quadding: i32,
+        rich_text: Option<String>,
+        default_style: Option<String>,
+        callout: Vec<f32>,
+    },
+    Shape {
+        interior_color: Option<Vec<f32>>,
+        rect_differences: Option<[f32; 4]>,
+    },
+    Stamp {
+        icon: String,
+    },
+    Widget {
+        field_name: Option<String>,
+        field_type: Option<String>,
+        highlight: String,
+        characteristics: Option<PdfDict>,
+        parent: Option<ObjectId>,
+    },
+    Popup {
+        parent: Option<ObjectId>,
+        open: bool,
+    },
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    id: Option<ObjectId>,
+    dict: PdfDict,
+    subtype: AnnotationSubtype,
+    rect: Rect,
+    contents: Option<String>,
+    name: Option<String>,
+    modified: Option<String>,
+    flags: u32,
+    border: Border,
+    color: Option<Vec<f32>>,
+    quad_points: Vec<[Point; 4]>,
+    appearance: Option<PdfDict>,
+    appearance_state: Option<String>,
+    author: Option<String>,
+    subject: Option<String>,
+    popup: Option<ObjectId>,
+    in_reply_to: Option<ObjectId>,
+    kind: AnnotationKind,
+}
+
+impl Annotation {
+    pub fn try_new(dict: PdfDict, id: Option<ObjectId>, xref: &Xref) -> Result<Self> {
+        let subtype = match dict.get("Subtype") {
+            Some(s) => AnnotationSubtype::new(xref.read_object(s)?.as_name()?.name()),
+            None => return Err(PdfError::Page("annotation has no Subtype".to_string())),
+        };
+        let rect = match dict.get("Rect") {
+            Some(r) => Rect::new_from_pdf_bbox(xref.read_object(r)?.as_array()?)?,
+            None => return Err(PdfError::Page("annotation has no Rect".to_string())),
+        };
+        let flags = match dict.get("F") {
+            Some(f) => xref.read_object(f)?.integer()? as u32,
+            None => 0,
+        };
+        let mut quad_points = Vec::new();
+        if let Some(qp) = dict.get("QuadPoints") {
+            let values = numbers(xref.read_object(qp)?.as_array()?, xref)?;
+            for q in values.chunks_exact(8) {
+                quad_points.push([
+                    Point::new(q[0], q[1]),
+                    Point::new(q[2], q[3]),
+                    Point::new(q[4], q[5]),
+                    Point::new(q[6], q[7]),
+                ]);
+            }
+        }
+        let appearance = match dict.get("AP") {
+            Some(ap) => match xref.read_object(ap)? {
+                PdfObject::Dict(d) => Some(d),
+                _ => None,
+            },
+            None => None,
+        };
+        let appearance_state = name(&dict, "AS", xref)?;
+        let (author, subject, popup, in_reply_to) = if subtype.is_markup() {
+            (
+                text(&dict, "T", xref)?,
+                text(&dict, "Subj", xref)?,
+                reference(&dict, "Popup"),
+                reference(&dict, "IRT"),
+            )
+        } else {
+            (None, None, None, None)
+        };
+        let kind = AnnotationKind::try_new(&subtype, &dict, xref)?;
+        Ok(Annotation {
+            id,
+            subtype,
+            rect,
+            contents: text(&dict, "Contents", xref)?,
+            name: text(&dict, "NM", xref)?,
+            modified: text(&dict, "M", xref)?,
+            flags,
+            border: Border::try_new(&dict, xref)?,
+            color: color(&dict, "C", xref)?,
+            quad_points,
+            appearance,
+            appearance_state,
+            author,
+            subject,
+            popup,
+            in_reply_to,
+            kind,
+            dict,
+        })
+    }
+
+    // object id, None if the annotation is a direct object in /Annots
+    pub fn id(&self) -> Option<ObjectId> {
+        self.id
+    }
+
+    pub fn dict(&self) -> &PdfDict {
+        &self.dict
+    }
+
+    pub fn subtype(&self) -> &AnnotationSubtype {
+        &self.subtype
+    }
+
+    pub fn rect(&self) -> &Rect {
+        &self.rect
+    }
+
+    pub fn contents(&self) -> Option<&str> {
+        self.contents.as_deref()
+    }
+
+    // unique name /NM in the page
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    // modification date, may be a PDF date or any text
+    pub fn modified(&self) -> Option<&str> {
+        self.modified.as_deref()
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.flags & HIDDEN != 0
+    }
+
+    pub fn is_print(&self) -> bool {
+        self.flags & PRINT != 0
+    }
+
+    pub fn is_no_view(&self) -> bool {
+        self.flags & NO_VIEW != 0
+    }
+
+    // Invisible only hide the annotation of unknown subtype
+    pub fn is_visible(&self, intent: AnnotationIntent) -> bool {
+        if self.flags & HIDDEN != 0 {
+            return false;
+        }
+        if self.flags & INVISIBLE != 0 && matches!(self.subtype, AnnotationSubtype::Other(_)) {
+            return false;
+        }
+        match intent {
+            AnnotationIntent::View => self.flags & NO_VIEW == 0,
+            AnnotationIntent::Print => self.flags & PRINT != 0,
+        }
+    }
+
+    pub fn border(&self) -> &Border {
+        &self.border
+    }
+
+    // gray, rgb or cmyk components, empty is transparent
+    pub fn color(&self) -> Option<&[f32]> {
+        self.color.as_deref()
+    }
+
+    // quadrilaterals of Link and text markup annotations
+    pub fn quad_points(&self) -> &[[Point; 4]] {
+        self.quad_points.as_slice()
+    }
+
+    // appearance dictionary /AP with N, R and D entries
+    pub fn appearance(&self) -> Option<&PdfDict> {
+        self.appearance.as_ref()
+    }
+
+    // appearance state /AS, select a subdictionary of /AP entries
+    pub fn appearance_state(&self) -> Option<&str> {
+        self.appearance_state.as_deref()
+    }
+
+    // normal appearance /AP /N, the one selected by /AS if it has states
+    pub fn normal_appearance(&self, xref: &Xref) -> Result<Option<PdfStream>> {
+        let normal = match self.appearance.as_ref().and_then(|ap| ap.get("N")) {
+            Some(n) => xref.read_object(n)?,
+            None => return Ok(None),
+        };
+        match normal {
+            PdfObject::Stream(s) => Ok(Some(s)),
+            PdfObject::Dict(states) => {
+                let state = match self.appearance_state.as_ref() {
+                    Some(state) => state,
+                    None => return Ok(None),
+                };
+                match states.get(state) {
+                    Some(s) => match xref.read_object(s)? {
+                        PdfObject::Stream(s) => Ok(Some(s)),
+                        _ => Ok(None),
+                    },
+                    None => Ok(None),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // author /T of markup annotation
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    pub fn popup(&self) -> Option<ObjectId> {
+        self.popup
+    }
+
+    // the annotation this one reply to
+    pub fn in_reply_to(&self) -> Option<ObjectId> {
+        self.in_reply_to
+    }
+
+    pub fn kind(&self) -> &AnnotationKind {
+        &self.kind
+    }
+}
+
+impl AnnotationKind {
+    fn try_new(subtype: &AnnotationSubtype, dict: &PdfDict, xref: &Xref) -> Result<Self> {
+        let kind = match subtype {
+            AnnotationSubtype::Link => AnnotationKind::Link {
+                destination: dict.get("Dest").cloned(),
+                action: match dict.get("A") {
+                    Some(a) => Some(xref.read_object(a)?.to_dict()?),
+                    None => None,
+                },
+                highlight: name(dict, "H", xref)?.unwrap_or("I".to_string()),
+            },
+            AnnotationSubtype::Text => AnnotationKind::Text {
+                open: boolean(dict, "Open", xref)?,
+                icon: name(dict, "Name", xref)?.unwrap_or("Note".to_string()),
+                state: text(dict, "State", xref)?,
+                state_model: text(dict, "StateModel", xref)?,
+            },
+            AnnotationSubtype::Highlight
+            | AnnotationSubtype::Underline
+            | AnnotationSubtype::Squiggly
+            | AnnotationSubtype::StrikeOut => AnnotationKind::TextMarkup,
+            AnnotationSubtype::Ink => {
+                let mut ink_list = Vec::new();
+                if let Some(list) = dict.get("InkList") {
+                    for path in xref.read_object(list)?.as_array()?.iter() {
+                        let values = numbers(xref.read_object(path)?.as_array()?, xref)?;
+                        let points = values
+                            .chunks_exact(2)
+                            .map(|p| Point::new(p[0], p[1]))
+                            .collect();
+                        ink_list.push(points);
+                    }
+                }
+                AnnotationKind::Ink { ink_list }
+            }
+            AnnotationSubtype::FreeText => AnnotationKind::FreeText {
+                default_appearance: text(dict, "DA", xref)?,
+                quadding: match dict.get("Q") {
+                    Some(q) => xref.read_object(q)?.integer()?,
+                    None => 0,
+                },
+                rich_text: match dict.get("RC") {
+                    Some(rc) => match xref.read_object(rc)? {
+                        PdfObject::Stream(s) => Some(
+                            String::from_utf8_lossy(s.decode_data(Some(xref))?.as_slice())
+                                .to_string(),
+                        ),
+                        o => Some(o.as_text_string()?),
+                    },
+                    None => None,
+                },
+                default_style: text(dict, "DS", xref)?,
+                callout: match dict.get("CL") {
+                    Some(cl) => numbers(xref.read_object(cl)?.as_array()?, xref)?,
+                    None => Vec::new(),
+                },
+            },
+            AnnotationSubtype::Square | AnnotationSubtype::Circle => AnnotationKind::Shape {
+                interior_color: color(dict, "IC", xref)?,
+                rect_differences: match dict.get("RD") {
+                    Some(rd) => {
+                        let values = numbers(xref.read_object(rd)?.as_array()?, xref)?;
+                        <[f32; 4]>::try_from(values.as_slice()).ok()
+                    }
+                    None => None,
+                },
+            },
+            AnnotationSubtype::Stamp => AnnotationKind::Stamp {
+                icon: name(dict, "Name", xref)?.unwrap_or("Draft".to_string()),
+            },
+            AnnotationSubtype::Widget => AnnotationKind::Widget {
+                field_name: text(dict, "T", xref)?,
+                field_type: name(dict, "FT", xref)?,
+                highlight: name(dict, "H", xref)?.unwrap_or("I".to_string()),
+                characteristics: match dict.get("MK") {
+                    Some(mk) => Some(xref.read_object(mk)?.to_dict()?),
+                    None => None,
+                },
+                parent: reference(dict, "Parent"),
+            },
+            AnnotationSubtype::Popup => AnnotationKind::Popup {
+                parent: reference(dict, "Parent"),
+                open: boolean(dict, "Open", xref)?,
+            },
+            _ => AnnotationKind::Other,
+        };
+        Ok(kind)
+    }
+}
+
+fn numbers(arr: &PdfArray, xref: &Xref) -> Result<Vec<f32>> {
+    let mut values = Vec::with_capacity(arr.len());
+    for v in arr.iter() {
+        values.push(xref.read_object(v)?.as_number()?.real());
+    }
+    Ok(values)
+}
+
+fn color(dict: &PdfDict, key: &str, xref: &Xref) -> Result<Option<Vec<f32>>> {
+    match dict.get(key) {
+        Some(c) => Ok(Some(numbers(xref.read_object(c)?.as_array()?, xref)?)),
+        None => Ok(None),
+    }
+}
+
+fn name(dict: &PdfDict, key: &str, xref: &Xref) -> Result<Option<String>> {
+    match dict.get(key) {
+        Some(n) => Ok(Some(xref.read_object(n)?.as_name()?.name().to_string())),
+        None => Ok(None),
+    }
+}
+
+fn text(dict: &PdfDict, key: &str, xref: &Xref) -> Result<Option<String>> {
+    match dict.get(key) {
+        Some(t) => Ok(Some(xref.read_object(t)?.as_text_string()?)),
+        None => Ok(None),
+    }
+}
+
+fn boolean(dict: &PdfDict, key: &str, xref: &Xref) -> Result<bool> {
+    match dict.get(key) {
+        Some(b) => Ok(xref.read_object(b)?.as_bool()?.0),
+        None => Ok(false),
+    }
+}
+
+fn reference(dict: &PdfDict, key: &str) -> Option<ObjectId> {
+    match dict.get(key) {
+        Some(PdfObject::Indirect(id)) => Some(*id),
+        _ => None,
+    }
+}
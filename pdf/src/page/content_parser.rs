@@ -25,10 +25,10 @@ impl ContentParser {
                 return Err(PdfError::ContentParser("Eof".to_string()));
             }
             let token = self.reader.peek_token()?;
-            match token {
+            match &token {
                 Token::Other(buf) => {
                     if is_command(buf) {
-                        if buf == b"BI" {
+                        if buf.as_ref() == b"BI" {
                             let t = self.reader.read_token()?;
                             let mut image_info = HashMap::new();
                             loop {
@@ -42,7 +42,7 @@ impl ContentParser {
                                         image_info.insert(key.name().to_string(), val);
                                     }
                                     Token::Other(buf) => {
-                                        if buf == b"ID" {
+                                        if buf.as_ref() == b"ID" {
                                             let t = self.reader.read_token()?;
                                             assert!(t.is_other_key(b"ID"));
                                             let _white_space = self.reader.read_byte();
@@ -119,12 +119,10 @@ mod tests {
             32, 84, 109, 10, 40, 43, 43, 41, 84, 106, 10, 69, 84, 10, 81, 10,
         ];
         let parser = ContentParser::new(content.to_vec());
-        loop {
-            let op = parser.read_operator();
-            println!("OP:{:?}", op);
-            if op.is_err() {
-                break;
-            }
+        let mut ops = Vec::new();
+        while let Ok(op) = parser.read_operator() {
+            ops.push(op.name().to_string());
         }
+        assert_eq!(ops.last().map(|n| n.as_str()), Some("Q"));
     }
 }
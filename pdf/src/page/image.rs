@@ -169,7 +169,7 @@ impl PdfImage {
                 None => None,
             };
             let name = f.as_name()?.name();
-            image.data = apply_filter(name, data, dp).unwrap();
+            image.data = apply_filter(name, data, dp, Some(xref.diagnostics()))?;
         } else {
             image.data = data.to_vec();
         }
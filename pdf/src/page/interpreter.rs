@@ -1,4 +1,4 @@
-use log::{debug, error, warn};
+use log::{debug, warn};
 
 use super::graphics_state::FillRule;
 use crate::{
@@ -7,7 +7,8 @@ use crate::{
         pattern::Pattern, value::ColorValue, ColorSpace,
     },
     device::Device,
-    error::{PdfError, Result},
+    diagnostics::{Diagnostic, DiagnosticCategory},
+    error::{PdfError, Result, ResultExt},
     font::{pdf_font::Font, WritingMode},
     geom::{
         coordinate::{Matrix, Point},
@@ -16,8 +17,8 @@ use crate::{
     },
     object::{number::PdfNumber, stream::PdfStream, PdfObject},
     page::{
-        content_parser::ContentParser, graphics_state::GraphicsState, image::PdfImage,
-        operator::Operator, resource::Resources, Page,
+        annotation::Annotation, content_parser::ContentParser, graphics_state::GraphicsState,
+        image::PdfImage, operator::Operator, resource::Resources, Page, PageBox,
     },
     xref::Xref,
 };
@@ -58,21 +59,22 @@ impl<'a> Interpreter<'a> {
     }
 
     pub fn run(&mut self, num: u32, device: &mut dyn Device) -> Result<()> {
-        let bbox = self.page.mediabox()?;
+        let bbox = self.page.page_box(self.page.display_box())?;
 
         let rotate = self.page.rotated()? % 360;
-        let kx = device.hdpi() / 72.0;
-        let ky = device.vdpi() / 72.0;
+        let userunit = self.page.user_unit()?;
+        let kx = device.hdpi() / 72.0 * userunit;
+        let ky = device.vdpi() / 72.0 * userunit;
 
         let (page_width, page_height) = match rotate {
             0 => {
-                self.state.ctm = Matrix::new(kx, 0.0, 0.0, -ky, kx * bbox.lx(), ky * bbox.uy());
+                self.state.ctm = Matrix::new(kx, 0.0, 0.0, -ky, -kx * bbox.lx(), ky * bbox.uy());
                 let page_width = kx * bbox.width();
                 let page_height = ky * bbox.height();
                 (page_width, page_height)
             }
             90 => {
-                self.state.ctm = Matrix::new(0.0, ky, kx, 0.0, -ky * bbox.ly(), -ky * bbox.lx());
+                self.state.ctm = Matrix::new(0.0, ky, kx, 0.0, -kx * bbox.ly(), -ky * bbox.lx());
                 let page_width = kx * bbox.height();
                 let page_height = ky * bbox.width();
                 (page_width, page_height)
@@ -94,22 +96,40 @@ impl<'a> Interpreter<'a> {
             }
         };
 
-        let _userunit = self.page.user_unit();
-
         device.start_page(&self.state, num, page_width, page_height)?;
-        if let Some(cropbox) = self.page.cropbox()? {
-            self.state.clipping_path.rect(cropbox);
+        if self.page.display_box() != PageBox::MediaBox {
+            self.state.clipping_path.rect(bbox);
             device.clip(&self.state)?;
         }
+        let page_state = self.state.clone();
 
         while let Ok(op) = self.parser.read_operator() {
-            match self.invoke_operator(op, device) {
-                Ok(()) => {
-                    //
+            let name = op.name().to_string();
+            let result = self
+                .invoke_operator(op, device)
+                .with_operator(&name)
+                .with_page(num);
+            if let Err(e) = result {
+                self.xref
+                    .diagnostics()
+                    .warn(Diagnostic::from_error(DiagnosticCategory::Content, &e))?;
+            }
+        }
+        if let Some(intent) = self.page.annotation_intent() {
+            for annot in self.page.annotations()? {
+                if !annot.is_visible(intent) {
+                    continue;
                 }
-                Err(e) => {
-                    println!("{:?}", e);
-                    error!("Operator error:{:?}", e);
+                // every annotation start with the initial state and resources of page
+                self.state = page_state.clone();
+                self.state_stack.clear();
+                self.resources.truncate(1);
+                if let Err(e) = self.draw_annotation(&annot, device).with_page(num) {
+                    let mut diagnostic = Diagnostic::from_error(DiagnosticCategory::Content, &e);
+                    if let Some(id) = annot.id() {
+                        diagnostic = diagnostic.with_object(id);
+                    }
+                    self.xref.diagnostics().warn(diagnostic)?;
                 }
             }
         }
@@ -117,6 +137,50 @@ impl<'a> Interpreter<'a> {
         Ok(())
     }
 
+    // map the appearance BBox transformed by its Matrix onto the annotation Rect
+    fn draw_annotation(&mut self, annot: &Annotation, device: &mut dyn Device) -> Result<()> {
+        let form = match annot.normal_appearance(self.xref)? {
+            Some(form) => form,
+            None => return Ok(()),
+        };
+        let bbox = match form.get_from_dict("BBox") {
+            Some(bbox) => Rect::new_from_pdf_bbox(self.xref.read_object(bbox)?.as_array()?)?,
+            None => {
+                return Err(PdfError::Interpreter(
+                    "annotation appearance has no BBox".to_string(),
+                ))
+            }
+        };
+        let matrix = form_matrix(&form, self.xref)?;
+        let corners = [
+            Point::new(bbox.lx(), bbox.ly()),
+            Point::new(bbox.ux(), bbox.ly()),
+            Point::new(bbox.lx(), bbox.uy()),
+            Point::new(bbox.ux(), bbox.uy()),
+        ]
+        .map(|p| p.transform(&matrix));
+        let min_x = corners.iter().map(|p| p.x()).fold(f32::MAX, f32::min);
+        let min_y = corners.iter().map(|p| p.y()).fold(f32::MAX, f32::min);
+        let max_x = corners.iter().map(|p| p.x()).fold(f32::MIN, f32::max);
+        let max_y = corners.iter().map(|p| p.y()).fold(f32::MIN, f32::max);
+        if max_x - min_x <= 0.0 || max_y - min_y <= 0.0 {
+            return Ok(());
+        }
+        let rect = annot.rect();
+        let sx = rect.width() / (max_x - min_x);
+        let sy = rect.height() / (max_y - min_y);
+        let a = Matrix::new(
+            sx,
+            0.0,
+            0.0,
+            sy,
+            rect.lx() - min_x * sx,
+            rect.ly() - min_y * sy,
+        );
+        self.state.update_ctm_matrix(&a);
+        self.do_form(&form, device)
+    }
+
     // q
     fn push_graph_state(&mut self) -> Result<()> {
         self.state_stack.push(self.state.clone());
@@ -150,80 +214,38 @@ impl<'a> Interpreter<'a> {
     }
 
     fn do_form(&mut self, xobject: &PdfStream, device: &mut dyn Device) -> Result<()> {
-        if let Some(res) = xobject.get_from_dict("Resources") {
-            match res {
-                PdfObject::Indirect(_) => {
-                    let res = self.xref.read_object(res)?.to_dict()?;
-                    let resources = Resources::try_new(&res, self.xref)?;
-                    self.resources.push(resources);
-                }
-                PdfObject::Dict(d) => {
-                    let resources = Resources::try_new(d, self.xref)?;
-                    self.resources.push(resources);
-                }
-                _ => {
-                    return Err(PdfError::Interpreter(
-                        "Form xojbect resources is invalid".to_string(),
-                    ));
-                }
+        // a form without Resources uses the resources of its parent
+        let pushed = match xobject.get_from_dict("Resources") {
+            Some(res @ PdfObject::Indirect(_)) => {
+                let res = self.xref.read_object(res)?.to_dict()?;
+                self.resources.push(Resources::try_new(&res, self.xref)?);
+                true
             }
-        }
+            Some(PdfObject::Dict(d)) => {
+                self.resources.push(Resources::try_new(d, self.xref)?);
+                true
+            }
+            Some(_) => {
+                return Err(PdfError::Interpreter(
+                    "Form xojbect resources is invalid".to_string(),
+                ));
+            }
+            None => false,
+        };
 
         self.push_graph_state()?;
-        if let Some(mat) = xobject.get_from_dict("Matrix") {
-            let mat = mat.as_array().map_err(|e| {
-                PdfError::Interpreter("Form xobject Matrix is not an array".to_string())
-            })?;
-
-            let a = mat
-                .get(0)
-                .ok_or(PdfError::Interpreter(
-                    "Form Matrix array element error".to_string(),
-                ))?
-                .as_number()?
-                .real();
-
-            let b = mat
-                .get(1)
-                .ok_or(PdfError::Interpreter(
-                    "Form Matrix array element error".to_string(),
-                ))?
-                .as_number()?
-                .real();
-
-            let c = mat
-                .get(2)
-                .ok_or(PdfError::Interpreter(
-                    "Form Matrix array element error".to_string(),
-                ))?
-                .as_number()?
-                .real();
-
-            let d = mat
-                .get(3)
-                .ok_or(PdfError::Interpreter(
-                    "Form Matrix array element error".to_string(),
-                ))?
-                .as_number()?
-                .real();
-
-            let e = mat
-                .get(4)
-                .ok_or(PdfError::Interpreter(
-                    "Form Matrix array element error".to_string(),
-                ))?
-                .as_number()?
-                .real();
-            let f = mat
-                .get(5)
-                .ok_or(PdfError::Interpreter(
-                    "Form Matrix array element error".to_string(),
-                ))?
-                .as_number()?
-                .real();
-            let fm = Matrix::new(a, b, c, d, e, f);
-            self.state.update_ctm_matrix(&fm);
+        let result = self.run_form(xobject, device);
+        // restore resources and state of the parent even if the form failed
+        if pushed {
+            self.resources.pop();
         }
+        self.pop_graph_state()?;
+        result
+    }
+
+    fn run_form(&mut self, xobject: &PdfStream, device: &mut dyn Device) -> Result<()> {
+        let fm = form_matrix(xobject, self.xref)?;
+        self.state.update_ctm_matrix(&fm);
         // TODO bbox as clip path
 
         let form_data = xobject.decode_data(Some(self.xref))?;
@@ -232,9 +254,6 @@ impl<'a> Interpreter<'a> {
         while let Ok(op) = parser.read_operator() {
             self.invoke_operator(op, device)?;
         }
-
-        self.pop_graph_state()?;
-        self.resources.pop();
         Ok(())
     }
 
@@ -270,7 +289,10 @@ impl<'a> Interpreter<'a> {
                 }
             }
         } else {
-            warn!("DO {:?} not found", xobject_name);
+            self.xref.diagnostics().warn(Diagnostic::new(
+                DiagnosticCategory::Content,
+                format!("Do {:?} not found in resources", xobject_name),
+            ))?;
         }
         Ok(())
     }
@@ -1327,3 +1349,26 @@ impl<'a> Interpreter<'a> {
         }
     }
 }
+
+// form xobject /Matrix, identity if it's not present
+fn form_matrix(xobject: &PdfStream, xref: &Xref) -> Result<Matrix> {
+    let mat = match xobject.get_from_dict("Matrix") {
+        Some(mat) => xref.read_object(mat)?,
+        None => return Ok(Matrix::default()),
+    };
+    let mat = mat
+        .as_array()
+        .map_err(|_| PdfError::Interpreter("Form xobject Matrix is not an array".to_string()))?;
+    let mut values = [0.0; 6];
+    for (i, v) in values.iter_mut().enumerate() {
+        *v = mat
+            .get(i)
+            .ok_or(PdfError::Interpreter(
+                "Form Matrix array element error".to_string(),
+            ))?
+            .as_number()?
+            .real();
+    }
+    let [a, b, c, d, e, f] = values;
+    Ok(Matrix::new(a, b, c, d, e, f))
+}
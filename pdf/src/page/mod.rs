@@ -1,13 +1,15 @@
 use crate::{
     device::Device,
-    error::{PdfError, Result},
-    geom::rect::Rect,
+    diagnostics::{Diagnostic, DiagnosticCategory},
+    error::{PdfError, Result, ResultExt},
+    geom::{coordinate::Point, rect::Rect},
     object::{stream::PdfStream, PdfObject},
     page::interpreter::Interpreter,
     pagetree::PageNodeRef,
     xref::Xref,
 };
 
+pub mod annotation;
 mod content_parser;
 pub mod graphics_state;
 mod interpreter;
@@ -17,56 +19,110 @@ mod resource;
 pub mod context;
 pub mod image;
 
+use annotation::{Annotation, AnnotationIntent};
 use resource::Resources;
 
+// page boundaries, CropBox is the visible area in viewers,
+// BleedBox, TrimBox and ArtBox are used in print production
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PageBox {
+    MediaBox,
+    #[default]
+    CropBox,
+    BleedBox,
+    TrimBox,
+    ArtBox,
+}
+
 pub struct Page<'a> {
     xref: &'a Xref,
     node: PageNodeRef,
     resources: resource::Resources,
+    display_box: PageBox,
+    annotation_intent: Option<AnnotationIntent>,
 }
 
 impl<'a> Page<'a> {
     pub fn try_new(node: PageNodeRef, xref: &'a Xref) -> Result<Self> {
-        let res_dict = node.borrow().resources(xref)?;
+        let res_dict = node.resources(xref)?;
         let resources = resource::Resources::try_new(&res_dict, xref)?;
         Ok(Self {
             xref,
             node,
             resources,
+            display_box: PageBox::default(),
+            annotation_intent: None,
         })
     }
     pub fn rotated(&self) -> Result<i32> {
-        if let Some(v) = self.node.borrow().dict().get("Rotate") {
-            return Ok(v.as_number()?.integer());
-        }
-        return Ok(0);
+        self.node.rotate(self.xref)
     }
+
+    // a page without MediaBox is an error in strict mode, otherwise it is reported
+    // as a diagnostic and US letter size is used
     pub fn mediabox(&self) -> Result<Rect> {
-        // TODO inheritable
-        if let Some(rec) = self.node.borrow().mediabox()? {
+        if let Some(rec) = self.node.mediabox(self.xref)? {
             return Ok(rec);
-        } else {
-            return Err(PdfError::Page("Page Mediabox is None".to_string()));
         }
+        let mut diagnostic = Diagnostic::new(
+            DiagnosticCategory::Structure,
+            format!("page {} has no MediaBox, use US letter size", self.index()),
+        );
+        if let Some(id) = self.node.id() {
+            diagnostic = diagnostic.with_object(id);
+        }
+        self.xref.diagnostics().warn(diagnostic)?;
+        Ok(Rect::new(Point::new(0.0, 0.0), 612.0, 792.0))
     }
 
     pub fn cropbox(&self) -> Result<Option<Rect>> {
-        if let Some(rec) = self.node.borrow().cropbox()? {
-            Ok(Some(rec))
-        } else {
-            Ok(None)
+        self.node.cropbox(self.xref)
+    }
+
+    // effective box, CropBox default to MediaBox, the others default to CropBox,
+    // all are reduced to their intersection with MediaBox
+    pub fn page_box(&self, kind: PageBox) -> Result<Rect> {
+        let mediabox = self.mediabox()?;
+        let rect = match kind {
+            PageBox::MediaBox => return Ok(mediabox),
+            PageBox::CropBox => self.cropbox()?,
+            PageBox::BleedBox => self.node.rect("BleedBox", self.xref)?,
+            PageBox::TrimBox => self.node.rect("TrimBox", self.xref)?,
+            PageBox::ArtBox => self.node.rect("ArtBox", self.xref)?,
+        };
+        match rect {
+            Some(rect) => Ok(rect.intersect(&mediabox)),
+            None if kind == PageBox::CropBox => Ok(mediabox),
+            None => self.page_box(PageBox::CropBox),
         }
     }
 
+    // the box shown by display, default is CropBox
+    pub fn display_box(&self) -> PageBox {
+        self.display_box
+    }
+
+    pub fn set_display_box(&mut self, kind: PageBox) {
+        self.display_box = kind;
+    }
+
+    // draw appearance streams of annotations visible for the intent, None draw only page content
+    pub fn annotation_intent(&self) -> Option<AnnotationIntent> {
+        self.annotation_intent
+    }
+
+    pub fn set_annotation_intent(&mut self, intent: Option<AnnotationIntent>) {
+        self.annotation_intent = intent;
+    }
+
     pub fn display(&self, p: u32, device: &mut dyn Device) -> Result<()> {
-        let mut interpreter = Interpreter::try_new(self, &self.xref)?;
-        interpreter.run(p, device).unwrap();
-        Ok(())
+        let mut interpreter = Interpreter::try_new(self, self.xref).with_page(self.index())?;
+        interpreter.run(p, device).with_page(self.index())
     }
 
     pub fn content_stream(&self) -> Result<Vec<PdfStream>> {
         let mut content_streams = Vec::new();
-        if let Some(contents) = self.node.borrow().dict().get("Contents") {
+        if let Some(contents) = self.node.dict().get("Contents") {
             let contents = self.xref.read_object(contents)?;
             match contents {
                 PdfObject::Array(arr) => {
@@ -89,18 +145,55 @@ impl<'a> Page<'a> {
         Ok(content_streams)
     }
 
+    // annotations in /Annots, the ones can't be read are reported and skipped
+    pub fn annotations(&self) -> Result<Vec<Annotation>> {
+        let annots = match self.node.dict().get("Annots") {
+            Some(annots) => self.xref.read_object(annots)?,
+            None => return Ok(Vec::new()),
+        };
+        let mut annotations = Vec::new();
+        for annot in annots.as_array()?.iter() {
+            let id = match annot {
+                PdfObject::Indirect(id) => Some(*id),
+                _ => None,
+            };
+            let annotation = self
+                .xref
+                .read_object(annot)
+                .and_then(|a| a.to_dict())
+                .and_then(|a| Annotation::try_new(a, id, self.xref));
+            match annotation {
+                Ok(a) => annotations.push(a),
+                Err(e) => {
+                    let mut diagnostic = Diagnostic::new(
+                        DiagnosticCategory::Structure,
+                        format!("read annotation of page {} failed:{:?}", self.index(), e),
+                    );
+                    if let Some(id) = id {
+                        diagnostic = diagnostic.with_object(id);
+                    }
+                    self.xref.diagnostics().warn(diagnostic)?;
+                }
+            }
+        }
+        Ok(annotations)
+    }
+
     pub fn resources(&self) -> &Resources {
         &self.resources
     }
 
     pub fn index(&self) -> u32 {
-        self.node.borrow().index()
+        self.node.index()
     }
+    // size of default user space unit in 1/72 inch
     pub fn user_unit(&self) -> Result<f32> {
-        if let Some(o) = self.node.borrow().dict().get("UserUnit") {
-            Ok(o.as_number()?.real())
-        } else {
-            Ok(1.0)
+        if let Some(o) = self.node.dict().get("UserUnit") {
+            let unit = self.xref.read_object(o)?.as_number()?.real();
+            if unit > 0.0 {
+                return Ok(unit);
+            }
         }
+        Ok(1.0)
     }
 }
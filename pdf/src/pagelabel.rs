@@ -0,0 +1,204 @@
+use crate::error::{PdfError, Result};
+use crate::nametree::NumberTree;
+use crate::object::PdfObject;
+use crate::xref::Xref;
+
+// the largest value written in roman numerals, MMMCMXCIX
+const MAX_ROMAN: u32 = 3999;
+// the largest value written in letters, ten Z
+const MAX_LETTERS: u32 = 26 * 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageLabelStyle {
+    Decimal,
+    UpperRoman,
+    LowerRoman,
+    UpperLetters,
+    LowerLetters,
+}
+
+impl PageLabelStyle {
+    fn new(style: &str) -> Result<Self> {
+        match style {
+            "D" => Ok(PageLabelStyle::Decimal),
+            "R" => Ok(PageLabelStyle::UpperRoman),
+            "r" => Ok(PageLabelStyle::LowerRoman),
+            "A" => Ok(PageLabelStyle::UpperLetters),
+            "a" => Ok(PageLabelStyle::LowerLetters),
+            _ => Err(PdfError::DocumentStructure(format!(
+                "unknown page label style:{}",
+                style
+            ))),
+        }
+    }
+
+    // roman numerals and letters grow with the value, larger values are in decimal
+    fn format(&self, n: u32) -> String {
+        match self {
+            PageLabelStyle::Decimal => n.to_string(),
+            PageLabelStyle::UpperRoman | PageLabelStyle::LowerRoman if n > MAX_ROMAN => {
+                n.to_string()
+            }
+            PageLabelStyle::UpperLetters | PageLabelStyle::LowerLetters if n > MAX_LETTERS => {
+                n.to_string()
+            }
+            PageLabelStyle::UpperRoman => roman(n),
+            PageLabelStyle::LowerRoman => roman(n).to_lowercase(),
+            PageLabelStyle::UpperLetters => letters(n),
+            PageLabelStyle::LowerLetters => letters(n).to_lowercase(),
+        }
+    }
+}
+
+fn roman(mut n: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut s = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            s.push_str(numeral);
+            n -= value;
+        }
+    }
+    s
+}
+
+// A to Z, then AA to ZZ, AAA to ZZZ ...
+fn letters(n: u32) -> String {
+    if n == 0 {
+        return String::new();
+    }
+    let letter = (b'A' + ((n - 1) % 26) as u8) as char;
+    letter.to_string().repeat(((n - 1) / 26 + 1) as usize)
+}
+
+// labels of the pages from start to the start of next range
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageLabelRange {
+    start: u32,
+    style: Option<PageLabelStyle>,
+    prefix: String,
+    first: u32,
+}
+
+impl PageLabelRange {
+    fn try_new(start: u32, dict: &PdfObject, xref: &Xref) -> Result<Self> {
+        let mut range = PageLabelRange {
+            start,
+            style: None,
+            prefix: String::new(),
+            first: 1,
+        };
+        let dict = xref.read_object(dict)?.to_dict()?;
+        if let Some(s) = dict.get("S") {
+            range.style = Some(PageLabelStyle::new(xref.read_object(s)?.as_name()?.name())?);
+        }
+        if let Some(p) = dict.get("P") {
+            range.prefix = xref.read_object(p)?.as_text_string()?;
+        }
+        if let Some(st) = dict.get("St") {
+            range.first = xref.read_object(st)?.integer()?.max(1) as u32;
+        }
+        Ok(range)
+    }
+
+    // index of the first page in the range
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    // None means the label has only the prefix
+    pub fn style(&self) -> Option<PageLabelStyle> {
+        self.style
+    }
+
+    pub fn prefix(&self) -> &str {
+        self.prefix.as_str()
+    }
+
+    // numeric value of the first page label in the range
+    pub fn first(&self) -> u32 {
+        self.first
+    }
+
+    fn label(&self, page: u32) -> String {
+        match self.style {
+            Some(style) => {
+                let n = self.first.saturating_add(page - self.start);
+                format!("{}{}", self.prefix, style.format(n))
+            }
+            None => self.prefix.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PageLabels {
+    ranges: Vec<PageLabelRange>,
+}
+
+impl PageLabels {
+    pub(crate) fn try_new(tree: &NumberTree, xref: &Xref) -> Result<Self> {
+        let mut ranges = Vec::new();
+        for entry in tree.iter() {
+            let (start, dict) = entry?;
+            if start < 0 {
+                return Err(PdfError::DocumentStructure(format!(
+                    "page label start at negative page index:{}",
+                    start
+                )));
+            }
+            ranges.push(PageLabelRange::try_new(start as u32, &dict, xref)?);
+        }
+        ranges.sort_by_key(|r| r.start);
+        Ok(PageLabels { ranges })
+    }
+
+    pub fn ranges(&self) -> &[PageLabelRange] {
+        self.ranges.as_slice()
+    }
+
+    // label of the page index, None if no range contains the page
+    pub fn label(&self, page: u32) -> Option<String> {
+        let range = self.ranges.iter().rev().find(|r| r.start <= page)?;
+        Some(range.label(page))
+    }
+
+    // index of the first page with the label, total is the number of pages
+    pub fn page_index(&self, label: &str, total: u32) -> Option<u32> {
+        (0..total).find(|page| self.label(*page).as_deref() == Some(label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{letters, roman, PageLabelStyle};
+
+    #[test]
+    fn test_page_label_style() {
+        assert_eq!(roman(1994), "MCMXCIV");
+        assert_eq!(roman(4), "IV");
+        assert_eq!(letters(1), "A");
+        assert_eq!(letters(26), "Z");
+        assert_eq!(letters(28), "BB");
+        assert_eq!(PageLabelStyle::LowerRoman.format(9), "ix");
+        assert_eq!(PageLabelStyle::LowerLetters.format(53), "aaa");
+        assert_eq!(PageLabelStyle::UpperRoman.format(3999), "MMMCMXCIX");
+        assert_eq!(PageLabelStyle::UpperRoman.format(2000000000), "2000000000");
+        assert_eq!(PageLabelStyle::UpperLetters.format(260), "ZZZZZZZZZZ");
+        assert_eq!(PageLabelStyle::LowerLetters.format(261), "261");
+    }
+}
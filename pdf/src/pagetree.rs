@@ -1,11 +1,11 @@
-use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
-use std::rc::{Rc, Weak};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Weak};
 
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
 use crate::error::{PdfError, Result};
 use crate::geom::rect::Rect;
 use crate::object::dictionary::PdfDict;
-use crate::object::PdfObject;
+use crate::object::{ObjectId, PdfObject};
 use crate::xref::Xref;
 
 #[derive(Debug, Clone, Default)]
@@ -15,20 +15,21 @@ enum PageNodeType {
     Leaf,
 }
 
-pub type PageNodeRef = Rc<RefCell<PageNode>>;
+pub type PageNodeRef = Arc<PageNode>;
 
 #[derive(Debug, Default)]
 pub struct PageNode {
     node_type: PageNodeType,
     count: u32,
-    parent: Option<Weak<RefCell<PageNode>>>,
+    parent: Option<Weak<PageNode>>,
     kids: Vec<PageNodeRef>,
     dict: PdfDict,
     index: u32,
+    id: Option<ObjectId>,
 }
 
 impl PageNode {
-    pub fn new(dict: PdfDict, parent: Option<Weak<RefCell<PageNode>>>) -> Self {
+    pub fn new(dict: PdfDict, parent: Option<Weak<PageNode>>) -> Self {
         let node_type = if dict.get("Kids").is_some() {
             PageNodeType::Intermediate
         } else {
@@ -45,6 +46,7 @@ impl PageNode {
             count: count as u32,
             kids: Vec::new(),
             index: 0,
+            id: None,
         }
     }
 
@@ -52,8 +54,9 @@ impl PageNode {
         self.index
     }
 
-    pub fn add_kid(&mut self, child: PageNodeRef) {
-        self.kids.push(child)
+    // object id of the node, None if the node is a direct object
+    pub fn id(&self) -> Option<ObjectId> {
+        self.id
     }
 
     pub fn count(&self) -> &u32 {
@@ -68,56 +71,62 @@ impl PageNode {
         self.kids.as_slice()
     }
 
+    // inheritable attribute of the node or the nearest ancestor that has it
+    pub fn inherited(&self, key: &str) -> Result<Option<PdfObject>> {
+        if let Some(v) = self.dict.get(key) {
+            return Ok(Some(v.to_owned()));
+        }
+        match self.parent {
+            Some(ref p) => match p.upgrade() {
+                Some(parent) => parent.inherited(key),
+                None => Err(PdfError::Page("Page parent is dropped".to_string())),
+            },
+            None => Ok(None),
+        }
+    }
+
     pub fn resources(&self, xref: &Xref) -> Result<PdfDict> {
-        match self.dict().get("Resources") {
-            Some(res) => match res {
-                PdfObject::Indirect(_) => {
-                    let obj = xref.read_object(res)?.as_dict()?.to_owned();
-                    Ok(obj)
-                }
-                PdfObject::Dict(obj) => Ok(obj.to_owned()),
+        match self.inherited("Resources")? {
+            Some(res) => match xref.read_object(&res)? {
+                PdfObject::Dict(obj) => Ok(obj),
                 _ => Err(PdfError::Page(format!(
                     "resource not a Dictionary obj:{:?}",
                     res
                 ))),
             },
-            None => match self.parent {
-                Some(ref p) => p.upgrade().unwrap().borrow().resources(xref),
-                None => Err(PdfError::Page("Page has no resource".to_string())),
-            },
+            None => Err(PdfError::Page("Page has no resource".to_string())),
         }
     }
-    pub fn mediabox(&self) -> Result<Option<Rect>> {
-        match self.dict.get("MediaBox") {
-            Some(o) => {
-                let bbox = o
-                    .as_array()
-                    .map_err(|_| PdfError::Page("MediaBox is not an array".to_string()))?;
-                let rect = Rect::new_from_pdf_bbox(bbox)
-                    .map_err(|e| PdfError::Page(format!("create Page mediabox error:{:?}", e)))?;
-                Ok(Some(rect))
-            }
-            None => match self.parent {
-                Some(ref p) => p.upgrade().unwrap().borrow().mediabox(),
-                None => Ok(None),
-            },
+
+    pub fn mediabox(&self, xref: &Xref) -> Result<Option<Rect>> {
+        self.inherited_rect("MediaBox", xref)
+    }
+
+    pub fn cropbox(&self, xref: &Xref) -> Result<Option<Rect>> {
+        self.inherited_rect("CropBox", xref)
+    }
+
+    // clockwise rotation in degrees, one of 0, 90, 180 and 270 for a valid page
+    pub fn rotate(&self, xref: &Xref) -> Result<i32> {
+        match self.inherited("Rotate")? {
+            // some writers use a real number like 90.0
+            Some(r) => Ok(xref.read_object(&r)?.as_number()?.integer().rem_euclid(360)),
+            None => Ok(0),
         }
     }
 
-    pub fn cropbox(&self) -> Result<Option<Rect>> {
-        match self.dict.get("CropBox") {
-            Some(o) => {
-                let bbox = o
-                    .as_array()
-                    .map_err(|_| PdfError::Page("CropBox is not an array".to_string()))?;
-                let rect = Rect::new_from_pdf_bbox(bbox)
-                    .map_err(|e| PdfError::Page(format!("create Page CropBox error:{:?}", e)))?;
-                Ok(Some(rect))
-            }
-            None => match self.parent {
-                Some(ref p) => p.upgrade().unwrap().borrow().cropbox(),
-                None => Ok(None),
-            },
+    // rectangle only in the node itself, like BleedBox, TrimBox and ArtBox
+    pub fn rect(&self, key: &str, xref: &Xref) -> Result<Option<Rect>> {
+        match self.dict.get(key) {
+            Some(o) => Ok(Some(read_rect(key, o, xref)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn inherited_rect(&self, key: &str, xref: &Xref) -> Result<Option<Rect>> {
+        match self.inherited(key)? {
+            Some(o) => Ok(Some(read_rect(key, &o, xref)?)),
+            None => Ok(None),
         }
     }
 }
@@ -126,75 +135,193 @@ impl PageNode {
 pub struct PageTree {
     root: PageNodeRef,
     pages: HashMap<u32, PageNodeRef>,
+    page_indexes: HashMap<ObjectId, u32>,
 }
 
 impl PageTree {
     pub fn try_new(catalog: PdfDict, xref: &Xref) -> Result<Self> {
         if let Some(pagesref) = catalog.get("Pages") {
             let pages = xref.read_object(pagesref)?;
-            let root = create_pagetree(pages.as_dict()?.to_owned(), xref, None)?;
-            let pages = create_pages(root.clone());
-
-            Ok(PageTree { root, pages })
+            let mut index = 0;
+            let mut visited: HashSet<ObjectId> = object_id(pagesref).into_iter().collect();
+            let root = create_pagetree(
+                pages.as_dict()?.to_owned(),
+                object_id(pagesref),
+                xref,
+                None,
+                &mut index,
+                &mut visited,
+            )?;
+            Ok(PageTree::new(root))
         } else {
-            let root = Rc::new(RefCell::new(PageNode::default()));
-            Ok(PageTree {
-                root,
-                pages: HashMap::new(),
-            })
+            Ok(PageTree::new(Arc::new(PageNode::default())))
+        }
+    }
+
+    fn new(root: PageNodeRef) -> Self {
+        let mut pages = HashMap::new();
+        collect_pages(&root, &mut pages);
+        let page_indexes = pages
+            .iter()
+            .filter_map(|(index, node)| node.id().map(|id| (id, *index)))
+            .collect();
+        PageTree {
+            root,
+            pages,
+            page_indexes,
+        }
+    }
+
+    // only the page at index and its ancestors, for the first page of a linearized file
+    pub fn try_new_single_page(page: &PdfObject, index: u32, xref: &Xref) -> Result<Self> {
+        let mut dicts = vec![xref.read_object(page)?.to_dict()?];
+        let mut visited = Vec::new();
+        while let Some(parent) = dicts.last().and_then(|d| d.get("Parent")) {
+            if let PdfObject::Indirect(id) = parent {
+                if visited.contains(id) {
+                    break;
+                }
+                visited.push(id.to_owned());
+            }
+            // parent may be not in the first page section
+            match xref.read_object(parent).and_then(|p| p.to_dict()) {
+                Ok(dict) => dicts.push(dict),
+                Err(PdfError::MissingRange(offset, len)) => {
+                    return Err(PdfError::MissingRange(offset, len))
+                }
+                Err(_) => break,
+            }
         }
+        dicts.reverse();
+        let root = create_branch(&dicts, None, index, object_id(page));
+        Ok(PageTree::new(root))
     }
 
     pub fn get_page(&self, index: &u32) -> Option<&PageNodeRef> {
         self.pages.get(index)
     }
 
+    // index of the page object, used to resolve destinations
+    pub fn page_index(&self, id: &ObjectId) -> Option<u32> {
+        self.page_indexes.get(id).copied()
+    }
+
     pub fn count(&self) -> Result<u32> {
-        let count = self.root.borrow().dict().get("Count").unwrap().integer()? as u32;
+        let count = self.root.dict().get("Count").unwrap().integer()? as u32;
         Ok(count)
     }
 }
 
-fn create_pages(root: PageNodeRef) -> HashMap<u32, PageNodeRef> {
-    let mut res = HashMap::new();
-    let mut index = 0;
-    let mut queue: VecDeque<PageNodeRef> = VecDeque::new();
-    queue.push_back(root);
-    while !queue.is_empty() {
-        if let Some(node) = queue.pop_front() {
-            match node.as_ref().borrow().node_type {
-                PageNodeType::Intermediate => {
-                    for kid in node.as_ref().borrow().kids() {
-                        queue.push_back(kid.clone());
-                    }
-                }
-                PageNodeType::Leaf => {
-                    res.insert(index, node.clone());
-                    index += 1;
-                }
+// leaves in depth-first order are the pages in document order
+fn collect_pages(node: &PageNodeRef, pages: &mut HashMap<u32, PageNodeRef>) {
+    match node.node_type {
+        PageNodeType::Intermediate => {
+            for kid in node.kids() {
+                collect_pages(kid, pages);
             }
         }
+        PageNodeType::Leaf => {
+            pages.insert(node.index(), node.clone());
+        }
+    }
+}
+
+fn read_rect(key: &str, obj: &PdfObject, xref: &Xref) -> Result<Rect> {
+    let obj = xref.read_object(obj)?;
+    let bbox = obj
+        .as_array()
+        .map_err(|_| PdfError::Page(format!("{} is not an array", key)))?;
+    Rect::new_from_pdf_bbox(bbox)
+        .map_err(|e| PdfError::Page(format!("create Page {} error:{:?}", key, e)))
+}
+
+fn object_id(obj: &PdfObject) -> Option<ObjectId> {
+    match obj {
+        PdfObject::Indirect(id) => Some(*id),
+        _ => None,
     }
-    res
+}
+
+// nodes from root to leaf, every node has only one kid
+fn create_branch(
+    dicts: &[PdfDict],
+    parent: Option<Weak<PageNode>>,
+    index: u32,
+    id: Option<ObjectId>,
+) -> PageNodeRef {
+    Arc::new_cyclic(|me| {
+        let mut node = PageNode::new(dicts[0].to_owned(), parent);
+        if dicts.len() > 1 {
+            node.node_type = PageNodeType::Intermediate;
+            node.kids
+                .push(create_branch(&dicts[1..], Some(me.clone()), index, id));
+        } else {
+            node.node_type = PageNodeType::Leaf;
+            node.index = index;
+            node.id = id;
+        }
+        node
+    })
 }
 
 fn create_pagetree(
     root: PdfDict,
+    id: Option<ObjectId>,
     xref: &Xref,
-    parent: Option<Weak<RefCell<PageNode>>>,
+    parent: Option<Weak<PageNode>>,
+    index: &mut u32,
+    visited: &mut HashSet<ObjectId>,
 ) -> Result<PageNodeRef> {
-    let node = PageNode::new(root.clone(), parent);
-    let noderef = Rc::new(RefCell::new(node));
-    if let Some(PdfObject::Array(kids)) = root.get("Kids") {
-        for kid in kids.iter() {
-            let kid_data = xref.read_object(kid)?;
-            let child = create_pagetree(
-                kid_data.as_dict()?.to_owned(),
-                xref,
-                Some(Rc::downgrade(&noderef)),
-            )?;
-            noderef.borrow_mut().add_kid(child);
+    let mut error = None;
+    let noderef = Arc::new_cyclic(|me| {
+        let mut node = PageNode::new(root.clone(), parent);
+        node.id = id;
+        if let PageNodeType::Leaf = node.node_type {
+            node.index = *index;
+            *index += 1;
+        }
+        if let Some(PdfObject::Array(kids)) = root.get("Kids") {
+            for kid in kids.iter() {
+                // a kid linking back to an ancestor or a node seen before is skipped
+                if let Some(kid_id) = object_id(kid) {
+                    if !visited.insert(kid_id) {
+                        let warning = xref.diagnostics().warn(
+                            Diagnostic::new(
+                                DiagnosticCategory::Structure,
+                                "page tree node is visited twice".to_string(),
+                            )
+                            .with_object(kid_id),
+                        );
+                        if let Err(e) = warning {
+                            error = Some(e);
+                            break;
+                        }
+                        continue;
+                    }
+                }
+                let child = xref.read_object(kid).and_then(|kid_data| {
+                    create_pagetree(
+                        kid_data.as_dict()?.to_owned(),
+                        object_id(kid),
+                        xref,
+                        Some(me.clone()),
+                        index,
+                        visited,
+                    )
+                });
+                match child {
+                    Ok(child) => node.kids.push(child),
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                }
+            }
         }
+        node
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(noderef),
     }
-    Ok(noderef)
 }
@@ -1,10 +1,13 @@
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::char;
 use std::collections::HashMap;
-use std::io::Read;
+use std::fmt::Debug;
 use std::path::Path;
+use std::sync::Arc;
 
 use crate::character::{self, is_white_space, u16_from_buffer, u32_from_buffer};
+use crate::diagnostics::{self, Diagnostic, DiagnosticCategory, Diagnostics};
 use crate::error::PdfError;
 use crate::error::Result;
 use crate::object::array::PdfArray;
@@ -15,17 +18,18 @@ use crate::object::number::PdfNumber;
 use crate::object::stream::PdfStream;
 use crate::object::string::{PdfHexString, PdfLiteral};
 use crate::object::PdfObject;
+use crate::source::{ByteSource, MemorySource};
 
-#[derive(Debug)]
 pub struct PdfReader {
-    buffer: Vec<u8>,
+    source: Arc<dyn ByteSource>,
     position: RefCell<usize>,
+    diagnostics: Option<Arc<Diagnostics>>,
 }
 
 #[derive(Debug)]
 pub enum Token<'a> {
-    Other(&'a [u8]),
-    Number(&'a [u8], bool),
+    Other(Cow<'a, [u8]>),
+    Number(Cow<'a, [u8]>, bool),
     StartName,
     StartHexString,
     EndHexString,
@@ -50,12 +54,12 @@ enum StringStatus {
 impl<'a> Token<'a> {
     pub fn is_other_key(&self, key: &[u8]) -> bool {
         match self {
-            &Token::Other(v) => v == key,
+            Token::Other(v) => v.as_ref() == key,
             _ => false,
         }
     }
 
-    pub fn buffer(&self) -> Option<&'a [u8]> {
+    pub fn buffer(&self) -> Option<&[u8]> {
         match self {
             Token::Other(buf) => Some(buf),
             Token::Number(buf, _) => Some(buf),
@@ -64,14 +68,46 @@ impl<'a> Token<'a> {
     }
 }
 
+impl Debug for PdfReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PdfReader")
+            .field("size", &self.size())
+            .field("position", &self.current_pos())
+            .finish()
+    }
+}
+
 impl PdfReader {
     pub fn new(buffer: Vec<u8>) -> Self {
+        Self::new_from_source(MemorySource::new(buffer))
+    }
+
+    pub fn new_from_source<S: ByteSource + 'static>(source: S) -> Self {
+        Self::new_from_shared(Arc::new(source))
+    }
+
+    // readers share the source, each one has its own position
+    pub fn new_from_shared(source: Arc<dyn ByteSource>) -> Self {
         Self {
-            buffer,
+            source,
             position: RefCell::new(0),
+            diagnostics: None,
         }
     }
 
+    pub fn source(&self) -> Arc<dyn ByteSource> {
+        self.source.clone()
+    }
+
+    // recoverable problems found by this reader are reported to diagnostics
+    pub fn set_diagnostics(&mut self, diagnostics: Arc<Diagnostics>) {
+        self.diagnostics = Some(diagnostics);
+    }
+
+    pub fn diagnostics(&self) -> Option<&Diagnostics> {
+        self.diagnostics.as_deref()
+    }
+
     pub fn peek_token(&self) -> Result<Token> {
         let pos = self.position.clone();
         let token = self.read_token()?;
@@ -80,86 +116,100 @@ impl PdfReader {
     }
 
     pub fn size(&self) -> usize {
-        self.buffer.len()
+        self.source.len()
+    }
+
+    // bytes in [start, end), the range must be already validated by caller
+    fn slice(&self, start: usize, end: usize) -> Result<Cow<'_, [u8]>> {
+        self.source.read(start, end - start)
     }
 
+    // the whole file is read into memory, very large files can be opened by new_from_source
+    // with a memory-mapped file or a ChunkedSource
     pub fn new_from_file<P: AsRef<Path>>(p: P) -> Result<Self> {
-        let mut f = std::fs::File::open(p).map_err(|e| {
-            PdfError::Reader(format!("Create PdfReader Failed to read from {:?}", e))
-        })?;
-        let mut data = Vec::new();
-        f.read_to_end(&mut data)
-            .map_err(|e| PdfError::Reader(format!("Crate PdfReader Failed read data {:?}", e)))?;
+        let path = p.as_ref().display().to_string();
+        let data = std::fs::read(p).map_err(|e| PdfError::Io(format!("read {}", path), e))?;
         Ok(Self::new(data))
     }
 
-    pub fn read_bytes(&self, n: usize) -> Result<&[u8]> {
+    pub fn read_bytes(&self, n: usize) -> Result<Cow<'_, [u8]>> {
         let mut pos = self.position.borrow_mut();
-        if *pos >= self.buffer.len() {
+        if *pos >= self.size() {
             return Err(PdfError::File("PdfReader read_bytes reach Eof".to_string()));
         }
-        let end = (*pos + n).min(self.buffer.len());
-        let data = &self.buffer[*pos..end];
+        let end = (*pos + n).min(self.size());
+        let data = self.slice(*pos, end)?;
+        *pos = end;
+        Ok(data)
+    }
+
+    // read n bytes as owned data, n can be large as the whole stream
+    pub fn read_data(&self, n: usize) -> Result<Vec<u8>> {
+        let mut pos = self.position.borrow_mut();
+        if *pos >= self.size() {
+            return Err(PdfError::File("PdfReader read_data reach Eof".to_string()));
+        }
+        let end = (*pos + n).min(self.size());
+        let data = self.source.read_to_vec(*pos, end - *pos)?;
         *pos = end;
         Ok(data)
     }
 
     pub fn reset_offset(&self, offset: usize) {
         let mut pos = self.position.borrow_mut();
-        if offset > self.buffer.len() {
-            *pos = self.buffer.len();
+        if offset > self.size() {
+            *pos = self.size();
         } else {
             *pos = offset
         }
     }
 
-    pub fn read_byte(&self) -> Result<&u8> {
+    pub fn read_byte(&self) -> Result<u8> {
         let mut pos = self.position.borrow_mut();
-        if *pos >= self.buffer.len() {
+        if *pos >= self.size() {
             return Err(PdfError::Reader(
                 "PdfReader read_bytes reach Eof".to_string(),
             ));
         }
-        let v = &self.buffer[*pos];
+        let v = self.source.read_byte(*pos)?;
         *pos += 1;
         Ok(v)
     }
 
-    pub fn peek_byte(&self) -> Result<&u8> {
+    pub fn peek_byte(&self) -> Result<u8> {
         if self.is_eof() {
             return Err(PdfError::File("Pdf reader peek_byte reach Eof".to_string()));
         }
         let pos = self.position.borrow();
-        let v = &self.buffer[*pos];
-        Ok(v)
+        self.source.read_byte(*pos)
     }
 
-    pub fn peek_bytes(&self, len: usize) -> Result<&[u8]> {
+    pub fn peek_bytes(&self, len: usize) -> Result<Cow<'_, [u8]>> {
         let pos = self.position.borrow();
-        if *pos + len > self.buffer.len() {
+        if *pos + len > self.size() {
             return Err(PdfError::Reader(
                 "Pdf Reader peek_bytes reach Eof".to_string(),
             ));
         }
-        Ok(&self.buffer[*pos..*pos + len])
+        self.slice(*pos, *pos + len)
     }
 
-    pub fn read_line(&self) -> Result<&[u8]> {
+    pub fn read_line(&self) -> Result<Cow<'_, [u8]>> {
         let start = self.position.clone();
         let mut strip = 1;
         while let Ok(ch) = self.read_byte() {
-            if ch == &character::LINE_FEED {
+            if ch == character::LINE_FEED {
                 break;
-            } else if ch == &character::CARRIAGE_RETURN {
+            } else if ch == character::CARRIAGE_RETURN {
                 let nch = self.peek_byte()?;
-                if nch == &character::LINE_FEED {
+                if nch == character::LINE_FEED {
                     strip = 2;
                     self.read_byte()?;
                     break;
                 }
             }
         }
-        let data = &self.buffer[*start.borrow()..*self.position.borrow() - strip];
+        let data = self.slice(*start.borrow(), *self.position.borrow() - strip)?;
         Ok(data)
     }
 
@@ -167,29 +217,29 @@ impl PdfReader {
         self.skip_white_space()?;
         let start = self.position.clone();
         let ch = self.read_byte()?;
-        if character::is_number(ch) {
+        if character::is_number(&ch) {
             let mut is_real = false;
-            if ch == &b'.' {
+            if ch == b'.' {
                 is_real = true;
             }
             while let Ok(nch) = self.peek_byte() {
-                if nch == &b'.' {
+                if nch == b'.' {
                     is_real = true;
                 }
-                if !character::is_number(nch) {
+                if !character::is_number(&nch) {
                     break;
                 }
                 self.read_byte()?;
             }
-            let data = &self.buffer[*start.borrow()..*self.position.borrow()];
+            let data = self.slice(*start.borrow(), *self.position.borrow())?;
             return Ok(Token::Number(data, is_real));
         }
-        match *ch {
+        match ch {
             character::SOLIDUS => Ok(Token::StartName),
             character::LEFT_PARENTHESIS => Ok(Token::StartLiteralString),
             character::LESS_THAN_SIGN => {
                 let nch = self.peek_byte()?;
-                if nch == &character::LESS_THAN_SIGN {
+                if nch == character::LESS_THAN_SIGN {
                     self.read_byte()?;
                     Ok(Token::StartDict)
                 } else {
@@ -200,12 +250,12 @@ impl PdfReader {
             character::PERCENT_SIGN => Ok(Token::StartComment),
             _ => {
                 while let Ok(nch) = self.peek_byte() {
-                    if character::is_white_space(nch) || character::is_delimiter(nch) {
+                    if character::is_white_space(&nch) || character::is_delimiter(&nch) {
                         break;
                     }
                     self.read_byte()?;
                 }
-                let data = &self.buffer[*start.borrow()..*self.position.borrow()];
+                let data = self.slice(*start.borrow(), *self.position.borrow())?;
                 Ok(Token::Other(data))
             }
         }
@@ -213,7 +263,7 @@ impl PdfReader {
 
     pub fn skip_white_space(&self) -> Result<()> {
         while let Ok(ch) = self.peek_byte() {
-            if character::is_white_space(ch) {
+            if character::is_white_space(&ch) {
                 self.read_byte()?;
             } else {
                 break;
@@ -227,7 +277,12 @@ impl PdfReader {
         let _n = self.read_token()?;
         let _g = self.read_token()?;
         let obj_key = self.read_token()?;
-        assert!(obj_key.is_other_key(b"obj"));
+        if !obj_key.is_other_key(b"obj") {
+            return Err(PdfError::Reader(format!(
+                "indirect object at {} need obj keyword",
+                offset
+            )));
+        }
         self.read_object()
     }
 
@@ -264,17 +319,17 @@ impl PdfReader {
                     if let Token::Number(n2, _) = self.read_token()? {
                         let r = self.read_token()?;
                         if r.is_other_key(b"R") {
-                            let object_id = u32_from_buffer(buf).map_err(|e| {
+                            let object_id = u32_from_buffer(&buf).map_err(|e| {
                                 PdfError::Reader(format!("Indirect ObjectId read error:{:?}", e))
                             })?;
-                            let gen = u16_from_buffer(n2).map_err(|e| {
+                            let gen = u16_from_buffer(&n2).map_err(|e| {
                                 PdfError::Reader(format!("Indirect Object gen error:{:?}", e))
                             })?;
                             return Ok(PdfObject::Indirect((object_id, gen)));
                         }
                     }
                     self.reset_offset(*pos.borrow());
-                    let num = PdfNumber::from_buffer(buf, is_real);
+                    let num = PdfNumber::from_buffer(&buf, is_real);
                     return Ok(PdfObject::Number(num));
                 }
                 Token::StartName => {
@@ -300,29 +355,27 @@ impl PdfReader {
         Err(PdfError::Reader("Read Object Error reach EOF".to_string()))
     }
 
-    fn find_end_stream_content(&self) -> Result<usize> {
-        let pos = self.current_pos();
-        let end_pos = self.find_tag(b"endstream")?;
-        let line_marker = self.find_end_of_line_marker(end_pos - 2)?;
-        let end = end_pos - line_marker;
-        self.reset_offset(pos);
-        Ok(end)
-    }
-
-    fn find_end_of_line_marker(&self, start: usize) -> Result<usize> {
+    // end of stream data start from start, end of line marker before `endstream` is not data
+    fn find_end_stream_content(&self, start: usize) -> Result<usize> {
         self.reset_offset(start);
-        self.read_end_of_line_marker()
-    }
-
-    fn read_end_of_line_marker(&self) -> Result<usize> {
-        match self.read_byte()? {
-            b'\r' => match self.read_byte()? {
-                b'\n' => Ok(2),
-                _ => Ok(1),
-            },
-            b'\n' => Ok(1),
-            _ => Ok(0),
-        }
+        let end = match self.find_tag(b"endstream") {
+            Ok(end) => end,
+            Err(PdfError::MissingRange(offset, len)) => {
+                return Err(PdfError::MissingRange(offset, len))
+            }
+            Err(_) => {
+                self.reset_offset(start);
+                self.find_tag(b"endobj")?
+            }
+        };
+        let before = self.slice(start.max(end.saturating_sub(2)), end)?;
+        let line_marker = match before.as_ref() {
+            [.., b'\r', b'\n'] => 2,
+            [.., b'\n'] | [.., b'\r'] => 1,
+            _ => 0,
+        };
+        self.reset_offset(start);
+        Ok(end - line_marker)
     }
 
     fn find_tag(&self, tag: &[u8]) -> Result<usize> {
@@ -330,12 +383,12 @@ impl PdfReader {
         let mut cur = 0;
         loop {
             let ch = self.read_byte()?;
-            if ch == &tag[cur] {
+            if ch == tag[cur] {
                 if cur == tag.len() - 1 {
                     break;
                 }
                 cur += 1;
-            } else if ch == &tag[0] {
+            } else if ch == tag[0] {
                 cur = 1;
             } else {
                 cur = 0;
@@ -356,13 +409,13 @@ impl PdfReader {
         self.skip_white_space()?;
         let mut bytes = Vec::new();
         while let Ok(ch) = self.peek_byte() {
-            if ch == &character::NUMBER_SIGN {
+            if ch == character::NUMBER_SIGN {
                 self.read_byte()?;
                 let c = self.read_bytes(2)?;
-                let s = String::from_utf8(c.to_owned()).unwrap();
+                let s = String::from_utf8(c.into_owned()).unwrap();
                 let c = u8::from_str_radix(s.as_str(), 16).unwrap();
                 bytes.push(c);
-            } else if character::is_white_space(ch) || character::is_delimiter(ch) {
+            } else if character::is_white_space(&ch) || character::is_delimiter(&ch) {
                 break;
             } else {
                 let c = self.read_byte()?;
@@ -373,65 +426,103 @@ impl PdfReader {
     }
     pub fn read_stream(&self) -> Result<PdfStream> {
         let dict = self.read_dict()?;
-        let pos = self.position.clone();
-        let next = self.read_token()?;
-        if next.is_other_key(b"stream") {
-            // TODO handle this length is not in Dict, read to keyward endstream
+        let length = match dict.get("Length") {
+            Some(PdfObject::Number(length)) => Some(length.integer() as usize),
+            _ => None,
+        };
+        let data = self.read_stream_data(length)?;
+        Ok(PdfStream::new(dict, data))
+    }
 
-            let pos = self.current_pos();
-            let c = self.peek_byte()?;
-            if *c == b'\r' {
-                self.read_byte()?;
-                let nc = self.peek_byte()?;
-                if *nc == b'\n' {
-                    self.read_byte()?;
-                } else {
-                    self.reset_offset(pos);
-                }
-            } else if *c == b'\n' {
-                self.read_byte()?;
-            }
-            if let Some(PdfObject::Number(length)) = dict.get("Length") {
-                let data = self.read_bytes(length.integer() as usize)?;
-                Ok(PdfStream::new(dict, data.to_owned()))
-            } else {
-                let end_of_stream = self.find_end_stream_content()?;
-                let len = end_of_stream - self.current_pos();
-                let data = self.read_bytes(len)?;
-                Ok(PdfStream::new(dict, data.to_owned()))
-            }
-        } else {
+    // read stream data after the dictionary, if Length is missing or `endstream`
+    // not follow the data, scan for `endstream` or `endobj` to find the real end
+    pub fn read_stream_data(&self, length: Option<usize>) -> Result<Vec<u8>> {
+        let next = self.read_token()?;
+        if !next.is_other_key(b"stream") {
             return Err(PdfError::Reader(
                 "Stream need stream as keyword".to_string(),
             ));
         }
+        let start = self.skip_stream_line_marker()?;
+        if let Some(length) = length {
+            if self.is_stream_end(start, length)? {
+                self.reset_offset(start);
+                let data = self.read_data(length)?;
+                self.read_token()?;
+                return Ok(data);
+            }
+            diagnostics::report(
+                self.diagnostics(),
+                Diagnostic::new(
+                    DiagnosticCategory::Stream,
+                    format!("stream Length {} is wrong, scan for endstream", length),
+                )
+                .with_offset(start),
+            )?;
+        }
+        let end = self.find_end_stream_content(start)?;
+        self.reset_offset(start);
+        let data = self.read_data(end - start)?;
+        if self.peek_token()?.is_other_key(b"endstream") {
+            self.read_token()?;
+        }
+        Ok(data)
     }
-    pub fn read_stream_data(&self, length: usize) -> Result<Vec<u8>> {
-        let next = self.read_token()?;
-        assert!(next.is_other_key(b"stream"));
+
+    // move past the end of line after the `stream` keyword, return start of the data
+    fn skip_stream_line_marker(&self) -> Result<usize> {
         let pos = self.current_pos();
         let c = self.peek_byte()?;
-        if *c == b'\r' {
+        if c == b'\r' {
             self.read_byte()?;
             let nc = self.peek_byte()?;
-            if *nc == b'\n' {
+            if nc == b'\n' {
                 self.read_byte()?;
             } else {
                 self.reset_offset(pos);
             }
-        } else if *c == b'\n' {
+        } else if c == b'\n' {
             self.read_byte()?;
         }
-        let data = self.read_bytes(length)?;
-        let token = self.read_token()?;
-        assert!(token.is_other_key(b"endstream"));
-        Ok(data.to_owned())
+        Ok(self.current_pos())
+    }
+
+    // move past the data and `endstream` of a stream whose `stream` keyword is already read,
+    // length is only a hint, the data is scanned for `endstream` if it is wrong
+    pub(crate) fn skip_stream_data(&self, length: Option<usize>) -> Result<()> {
+        let start = self.skip_stream_line_marker()?;
+        if let Some(length) = length {
+            if self.is_stream_end(start, length)? {
+                return Ok(());
+            }
+        }
+        self.reset_offset(start);
+        let end = self.find_tag(b"endstream")?;
+        self.reset_offset(end + b"endstream".len());
+        Ok(())
+    }
+
+    // `endstream` follows the data of length
+    fn is_stream_end(&self, start: usize, length: usize) -> Result<bool> {
+        match start.checked_add(length) {
+            Some(end) if end <= self.size() => {
+                self.reset_offset(end);
+                match self.read_token() {
+                    Ok(token) => Ok(token.is_other_key(b"endstream")),
+                    Err(PdfError::MissingRange(offset, len)) => {
+                        Err(PdfError::MissingRange(offset, len))
+                    }
+                    Err(_) => Ok(false),
+                }
+            }
+            _ => Ok(false),
+        }
     }
 
     pub fn read_array(&self) -> Result<PdfArray> {
         let mut elements = Vec::new();
         while let Ok(ch) = self.peek_byte() {
-            if ch == &character::RIGHT_SQUARE_BRACKET {
+            if ch == character::RIGHT_SQUARE_BRACKET {
                 self.read_byte()?;
                 break;
             }
@@ -449,11 +540,11 @@ impl PdfReader {
             if self.is_eof() {
                 break;
             }
-            if is_white_space(ch) {
+            if is_white_space(&ch) {
                 ch = self.read_byte()?;
                 continue;
             }
-            if ch == &character::GREATER_THAN_SIGN {
+            if ch == character::GREATER_THAN_SIGN {
                 break;
             }
             bytes.push(ch.to_owned());
@@ -473,7 +564,7 @@ impl PdfReader {
         let mut esc_octal = String::new();
         loop {
             match status {
-                StringStatus::Normal => match *ch {
+                StringStatus::Normal => match ch {
                     character::LEFT_PARENTHESIS => {
                         bytes.push(ch.to_owned());
                         nest_level += 1;
@@ -558,7 +649,7 @@ impl PdfReader {
                 }
                 StringStatus::CarriageReturn => {
                     status = StringStatus::Normal;
-                    if ch != &character::LINE_FEED {
+                    if ch != character::LINE_FEED {
                         continue;
                     }
                 }
@@ -576,7 +667,7 @@ impl PdfReader {
     }
 
     pub fn is_eof(&self) -> bool {
-        *self.position.borrow() >= self.buffer.len()
+        *self.position.borrow() >= self.size()
     }
 
     pub fn read_number(&self) -> Result<PdfNumber> {
@@ -584,16 +675,16 @@ impl PdfReader {
         let pos = self.position.clone();
         let mut is_real = false;
         while let Ok(ch) = self.peek_byte() {
-            if ch == &b'.' {
+            if ch == b'.' {
                 is_real = true;
             }
-            if !character::is_number(ch) {
+            if !character::is_number(&ch) {
                 break;
             }
             self.read_byte()?;
         }
-        let buffer = &self.buffer[*pos.borrow()..*self.position.borrow()];
-        Ok(PdfNumber::from_buffer(buffer, is_real))
+        let buffer = self.slice(*pos.borrow(), *self.position.borrow())?;
+        Ok(PdfNumber::from_buffer(&buffer, is_real))
     }
 
     pub fn current_pos(&self) -> usize {
@@ -603,16 +694,26 @@ impl PdfReader {
     pub fn read_dict(&self) -> Result<PdfDict> {
         self.skip_white_space()?;
         let mut values = HashMap::new();
-        while self.peek_bytes(2)? != b">>" {
+        while self.peek_bytes(2)?.as_ref() != b">>" {
+            // comment in dictionary is same as white space
+            if self.peek_byte()? == b'%' {
+                self.read_line()?;
+                self.skip_white_space()?;
+                continue;
+            }
             let start_name = self.read_byte()?;
-            assert_eq!(start_name, &b'/');
+            if start_name != character::SOLIDUS {
+                return Err(PdfError::Reader(format!(
+                    "Dictionary key need a name got:{:?}",
+                    start_name
+                )));
+            }
             let name = self.read_name()?;
             let obj = self.read_object()?;
             values.insert(name.to_string(), obj);
             self.skip_white_space()?;
         }
-        let end_dict = self.read_bytes(2)?;
-        assert_eq!(end_dict, b">>");
+        self.read_bytes(2)?;
         Ok(PdfDict::new(values))
     }
 
@@ -833,7 +934,7 @@ special characters(*!&}^% and so on)."
         let buffer = "abc def";
         let reader = new_reader(buffer);
         let t = reader.read_bytes(3).unwrap();
-        assert_eq!(t, b"abc");
+        assert_eq!(t.as_ref(), b"abc");
         assert_eq!(reader.current_pos(), 3);
     }
 
@@ -842,7 +943,29 @@ special characters(*!&}^% and so on)."
         let buffer = "abc def";
         let reader = new_reader(buffer);
         let t = reader.read_byte().unwrap();
-        assert_eq!(t, &b'a');
+        assert_eq!(t, b'a');
         assert_eq!(reader.current_pos(), 1);
     }
+
+    #[test]
+    fn test_read_stream_length() {
+        let stream_data = |buffer: &str| {
+            let reader = new_reader(buffer);
+            reader.read_token().unwrap();
+            let data = reader.read_stream().unwrap().raw_data().to_vec();
+            let next = reader.read_token().unwrap();
+            assert!(next.is_other_key(b"endobj"));
+            data
+        };
+        let right = "<</Length 5>>\nstream\nhello\nendstream\nendobj";
+        assert_eq!(stream_data(right), b"hello");
+        let short = "<</Length 3>>\nstream\nhello\nendstream\nendobj";
+        assert_eq!(stream_data(short), b"hello");
+        let long = "<</Length 30>>\nstream\nhello\r\nendstream\nendobj";
+        assert_eq!(stream_data(long), b"hello");
+        let missing = "<</Filter /None>>\nstream\r\nhello world\nendstream\nendobj";
+        assert_eq!(stream_data(missing), b"hello world");
+        let no_endstream = "<</Length 50>>\nstream\nhello\nendobj";
+        assert_eq!(stream_data(no_endstream), b"hello");
+    }
 }
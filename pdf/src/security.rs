@@ -110,11 +110,11 @@ impl CryptFilter {
                 }
                 "AESV2" => {
                     let fm_key = self.compute_key(key, id, gen, length)?;
-                    let de_data = aes128_decrypt(fm_key.as_slice(), data);
+                    let de_data = aes128_decrypt(fm_key.as_slice(), data)?;
                     return Ok(de_data);
                 }
                 "AESV3" => {
-                    let de_data = aes256_decrypt(key, data);
+                    let de_data = aes256_decrypt(key, data)?;
                     return Ok(de_data);
                 }
                 _ => {
@@ -337,7 +337,7 @@ impl SecurityHandler {
         }
         let iv = [0; 16];
         let tmp_key = self.calcute_hash_v5(password.as_slice(), &self.u[40..48], &[])?;
-        let dkey = aes_cbc_decrypt(tmp_key.as_slice(), &iv, self.ue.as_slice());
+        let dkey = aes_cbc_decrypt(tmp_key.as_slice(), &iv, self.ue.as_slice())?;
         return Ok(Some(dkey));
     }
 
@@ -355,7 +355,7 @@ impl SecurityHandler {
         let salt = &self.o[40..48];
         let iv: [u8; 16] = [0; 16];
         let tmp_key = self.calcute_hash_v5(password.as_slice(), salt, &self.u[..48])?;
-        let res = aes_cbc_decrypt(tmp_key.as_slice(), &iv, self.oe.as_slice());
+        let res = aes_cbc_decrypt(tmp_key.as_slice(), &iv, self.oe.as_slice())?;
         return Ok(Some(res));
     }
     fn calcute_hash_v5(&self, password: &[u8], salt: &[u8], u_data: &[u8]) -> Result<Vec<u8>> {
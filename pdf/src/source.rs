@@ -0,0 +1,297 @@
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+
+use crate::cache::LruCache;
+use crate::error::{PdfError, Result};
+
+// random access to the bytes of a pdf file, shared by readers in different threads
+pub trait ByteSource: Debug + Send + Sync {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // bytes in [offset, offset + len), shorter if reach the end of source,
+    // borrowed if the source keeps all of its bytes in memory
+    fn read(&self, offset: usize, len: usize) -> Result<Cow<'_, [u8]>>;
+
+    // copy of bytes in [offset, offset + len), used for large data like stream content
+    fn read_to_vec(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
+        Ok(self.read(offset, len)?.into_owned())
+    }
+
+    fn read_byte(&self, offset: usize) -> Result<u8> {
+        match self.read(offset, 1)?.first() {
+            Some(v) => Ok(*v),
+            None => Err(PdfError::Reader(format!(
+                "read offset {} out of source length {}",
+                offset,
+                self.len()
+            ))),
+        }
+    }
+}
+
+// whole file in memory, Vec<u8>, Arc<[u8]>, memory-mapped file or any AsRef<[u8]>
+pub struct MemorySource<T> {
+    data: T,
+}
+
+impl<T: AsRef<[u8]>> MemorySource<T> {
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+}
+
+impl<T: AsRef<[u8]> + Send + Sync> ByteSource for MemorySource<T> {
+    fn len(&self) -> usize {
+        self.data.as_ref().len()
+    }
+
+    fn read(&self, offset: usize, len: usize) -> Result<Cow<'_, [u8]>> {
+        let data = self.data.as_ref();
+        if offset > data.len() {
+            return Err(PdfError::Reader(format!(
+                "read offset {} out of source length {}",
+                offset,
+                data.len()
+            )));
+        }
+        let end = offset.saturating_add(len).min(data.len());
+        Ok(Cow::Borrowed(&data[offset..end]))
+    }
+}
+
+impl<T: AsRef<[u8]>> Debug for MemorySource<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MemorySource {{ len: {} }}", self.data.as_ref().len())
+    }
+}
+
+// fetch bytes from a slow or remote storage, e.g. http range request,
+// return PdfError::MissingRange if the bytes are not available yet
+pub trait RangeFetch: Send + Sync {
+    fn fetch(&self, offset: usize, len: usize) -> Result<Vec<u8>>;
+}
+
+const CHUNK_SIZE: usize = 256 * 1024;
+// loaded chunks kept in memory, 16MB with the default chunk size
+const CHUNK_CACHE_SIZE: usize = 64;
+
+// load source chunk by chunk on demand, only the recently used chunks are kept
+// and an evicted chunk is fetched again when it is read
+pub struct ChunkedSource<F> {
+    fetcher: F,
+    len: usize,
+    chunk_size: usize,
+    chunks: Mutex<LruCache<usize, Arc<Vec<u8>>>>,
+}
+
+impl<F: RangeFetch> ChunkedSource<F> {
+    pub fn new(fetcher: F, len: usize) -> Self {
+        Self::with_chunk_size(fetcher, len, CHUNK_SIZE)
+    }
+
+    // smaller chunks fetch less data than needed, e.g. only the first page of a linearized file
+    pub fn with_chunk_size(fetcher: F, len: usize, chunk_size: usize) -> Self {
+        Self::with_cache_size(fetcher, len, chunk_size, CHUNK_CACHE_SIZE)
+    }
+
+    // at most cache_size chunks are kept in memory
+    pub fn with_cache_size(fetcher: F, len: usize, chunk_size: usize, cache_size: usize) -> Self {
+        Self {
+            fetcher,
+            len,
+            chunk_size: chunk_size.max(1),
+            chunks: Mutex::new(LruCache::new(cache_size.max(1))),
+        }
+    }
+
+    fn chunk(&self, index: usize) -> Result<Arc<Vec<u8>>> {
+        if let Ok(mut chunks) = self.chunks.lock() {
+            if let Some(data) = chunks.get(&index) {
+                return Ok(data.clone());
+            }
+        }
+        let start = index * self.chunk_size;
+        let end = (start + self.chunk_size).min(self.len);
+        let data = self.fetcher.fetch(start, end - start)?;
+        if data.len() != end - start {
+            return Err(PdfError::Reader(format!(
+                "fetch {} bytes at {} got {} bytes",
+                end - start,
+                start,
+                data.len()
+            )));
+        }
+        let data = Arc::new(data);
+        if let Ok(mut chunks) = self.chunks.lock() {
+            chunks.insert(index, data.clone());
+        }
+        Ok(data)
+    }
+
+    fn check_offset(&self, offset: usize) -> Result<()> {
+        if offset > self.len {
+            return Err(PdfError::Reader(format!(
+                "read offset {} out of source length {}",
+                offset, self.len
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<F: RangeFetch> ByteSource for ChunkedSource<F> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn read(&self, offset: usize, len: usize) -> Result<Cow<'_, [u8]>> {
+        Ok(Cow::Owned(self.read_to_vec(offset, len)?))
+    }
+
+    // bytes may be in several chunks
+    fn read_to_vec(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
+        self.check_offset(offset)?;
+        let end = offset.saturating_add(len).min(self.len);
+        let mut data = Vec::with_capacity(end - offset);
+        let mut pos = offset;
+        while pos < end {
+            let index = pos / self.chunk_size;
+            let chunk_start = index * self.chunk_size;
+            let chunk = self.chunk(index)?;
+            let e = (end - chunk_start).min(chunk.len());
+            data.extend_from_slice(&chunk[pos - chunk_start..e]);
+            pos = chunk_start + e;
+        }
+        Ok(data)
+    }
+
+    fn read_byte(&self, offset: usize) -> Result<u8> {
+        self.check_offset(offset)?;
+        let index = offset / self.chunk_size;
+        let chunk = self.chunk(index)?;
+        chunk
+            .get(offset - index * self.chunk_size)
+            .copied()
+            .ok_or(PdfError::Reader(format!(
+                "read offset {} out of source length {}",
+                offset, self.len
+            )))
+    }
+}
+
+impl<F> Debug for ChunkedSource<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ChunkedSource {{ len: {} }}", self.len)
+    }
+}
+
+// RangeFetch for anything implement Read + Seek, like std::fs::File
+pub struct SeekFetch<R> {
+    inner: Mutex<R>,
+}
+
+impl<R: Read + Seek + Send> RangeFetch for SeekFetch<R> {
+    fn fetch(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| PdfError::Reader("SeekFetch reader lock is poisoned".to_string()))?;
+        inner
+            .seek(SeekFrom::Start(offset as u64))
+            .map_err(|e| PdfError::Io(format!("SeekFetch seek to {}", offset), e))?;
+        let mut data = vec![0; len];
+        inner
+            .read_exact(data.as_mut_slice())
+            .map_err(|e| PdfError::Io(format!("SeekFetch read {} bytes at {}", len, offset), e))?;
+        Ok(data)
+    }
+}
+
+impl<R: Read + Seek + Send> ChunkedSource<SeekFetch<R>> {
+    pub fn from_reader(mut reader: R) -> Result<Self> {
+        let len = reader
+            .seek(SeekFrom::End(0))
+            .map_err(|e| PdfError::Io("ChunkedSource seek to end".to_string(), e))?;
+        let fetcher = SeekFetch {
+            inner: Mutex::new(reader),
+        };
+        Ok(ChunkedSource::new(fetcher, len as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{ByteSource, ChunkedSource, MemorySource, RangeFetch, CHUNK_SIZE};
+    use crate::error::Result;
+
+    // count fetches to know which chunks are loaded again
+    struct Counter {
+        data: Vec<u8>,
+        fetches: AtomicUsize,
+    }
+
+    impl RangeFetch for &Counter {
+        fn fetch(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+            Ok(self.data[offset..offset + len].to_vec())
+        }
+    }
+
+    #[test]
+    fn test_memory_source() {
+        let source = MemorySource::new(b"hello world".to_vec());
+        assert_eq!(source.len(), 11);
+        assert_eq!(source.read(6, 100).unwrap().as_ref(), b"world");
+        assert_eq!(source.read_byte(4).unwrap(), b'o');
+        assert!(source.read(12, 1).is_err());
+        assert!(source.read_byte(11).is_err());
+    }
+
+    #[test]
+    fn test_chunked_source() {
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 100).map(|v| v as u8).collect();
+        let source = ChunkedSource::from_reader(Cursor::new(data.clone())).unwrap();
+        assert_eq!(source.len(), data.len());
+        let offset = CHUNK_SIZE - 10;
+        assert_eq!(
+            source.read(offset, 20).unwrap().as_ref(),
+            &data[offset..offset + 20]
+        );
+        // longer than a chunk
+        assert_eq!(
+            source.read(5, CHUNK_SIZE * 2).unwrap().as_ref(),
+            &data[5..CHUNK_SIZE * 2 + 5]
+        );
+        assert_eq!(source.read_to_vec(5, data.len()).unwrap(), &data[5..]);
+        assert_eq!(
+            source.read_byte(CHUNK_SIZE * 2).unwrap(),
+            data[CHUNK_SIZE * 2]
+        );
+    }
+
+    #[test]
+    fn test_chunk_eviction() {
+        let counter = Counter {
+            data: (0..100).collect(),
+            fetches: AtomicUsize::new(0),
+        };
+        let source = ChunkedSource::with_cache_size(&counter, 100, 10, 2);
+        assert_eq!(source.read(5, 30).unwrap().as_ref(), &counter.data[5..35]);
+        assert_eq!(counter.fetches.load(Ordering::Relaxed), 4);
+        // the last two chunks are kept
+        assert_eq!(source.read_byte(25).unwrap(), 25);
+        assert_eq!(source.read_byte(31).unwrap(), 31);
+        assert_eq!(counter.fetches.load(Ordering::Relaxed), 4);
+        assert_eq!(source.read_byte(0).unwrap(), 0);
+        assert_eq!(counter.fetches.load(Ordering::Relaxed), 5);
+    }
+}
@@ -0,0 +1,539 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::character::{is_delimiter, NUMBER_SIGN};
+use crate::error::{PdfError, Result};
+use crate::filter::flate::flate_encode;
+use crate::object::array::PdfArray;
+use crate::object::dictionary::PdfDict;
+use crate::object::name::PdfName;
+use crate::object::number::PdfNumber;
+use crate::object::stream::PdfStream;
+use crate::object::string::{PdfHexString, PdfLiteral};
+use crate::object::{ObjectId, PdfObject};
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+// cross-reference entry of an object in the written file
+#[derive(Debug, Clone, PartialEq)]
+pub enum XrefEntry {
+    // generation used if the object number is used again
+    Free(u16),
+    // offset and generation
+    Normal(usize, u16),
+    // object stream number and index in it
+    Compressed(u32, u16),
+}
+
+// write pdf syntax, keep the position in output for building cross reference table
+pub struct PdfWriter<W: Write> {
+    inner: W,
+    position: usize,
+}
+
+impl<W: Write> PdfWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_position(inner, 0)
+    }
+
+    // append to existing data, position is the length of data already written
+    pub fn with_position(inner: W, position: usize) -> Self {
+        PdfWriter { inner, position }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner
+            .write_all(bytes)
+            .map_err(|e| PdfError::Io(format!("write {} bytes", bytes.len()), e))?;
+        self.position += bytes.len();
+        Ok(())
+    }
+
+    // header and a comment line with binary characters, so the file is treated as binary
+    pub fn write_header(&mut self, version: &str) -> Result<()> {
+        self.write_bytes(format!("%PDF-{}\n", version).as_bytes())?;
+        self.write_bytes(b"%\xE2\xE3\xCF\xD3\n")
+    }
+
+    pub fn write_object(&mut self, obj: &PdfObject) -> Result<()> {
+        match obj {
+            PdfObject::Null => self.write_bytes(b"null"),
+            PdfObject::Bool(b) => {
+                if b.0 {
+                    self.write_bytes(b"true")
+                } else {
+                    self.write_bytes(b"false")
+                }
+            }
+            PdfObject::Name(name) => self.write_name(name),
+            PdfObject::LiteralString(s) => self.write_literal(s),
+            PdfObject::HexString(s) => self.write_hex_string(s),
+            PdfObject::Number(n) => self.write_number(n),
+            PdfObject::Stream(s) => self.write_stream(s),
+            PdfObject::Array(arr) => {
+                self.write_bytes(b"[")?;
+                for (i, obj) in arr.iter().enumerate() {
+                    if i > 0 {
+                        self.write_bytes(b" ")?;
+                    }
+                    self.write_object(obj)?;
+                }
+                self.write_bytes(b"]")
+            }
+            PdfObject::Dict(d) => self.write_dict(d),
+            PdfObject::Indirect((num, gen)) => {
+                self.write_bytes(format!("{} {} R", num, gen).as_bytes())
+            }
+        }
+    }
+
+    // write `num gen obj ... endobj`, return offset of the object
+    pub fn write_indirect_object(&mut self, id: ObjectId, obj: &PdfObject) -> Result<usize> {
+        let offset = self.position;
+        self.write_bytes(format!("{} {} obj\n", id.0, id.1).as_bytes())?;
+        self.write_object(obj)?;
+        self.write_bytes(b"\nendobj\n")?;
+        Ok(offset)
+    }
+
+    pub fn write_name(&mut self, name: &PdfName) -> Result<()> {
+        let bytes = escape_name(name.name().as_bytes());
+        self.write_bytes(b"/")?;
+        self.write_bytes(bytes.as_slice())
+    }
+
+    pub fn write_literal(&mut self, s: &PdfLiteral) -> Result<()> {
+        let bytes = escape_literal(s.bytes());
+        self.write_bytes(b"(")?;
+        self.write_bytes(bytes.as_slice())?;
+        self.write_bytes(b")")
+    }
+
+    pub fn write_hex_string(&mut self, s: &PdfHexString) -> Result<()> {
+        let bytes = encode_hex(s.raw_bytes()?.as_slice());
+        self.write_bytes(b"<")?;
+        self.write_bytes(bytes.as_slice())?;
+        self.write_bytes(b">")
+    }
+
+    pub fn write_number(&mut self, n: &PdfNumber) -> Result<()> {
+        match n {
+            PdfNumber::Integer(v) => self.write_bytes(v.to_string().as_bytes()),
+            PdfNumber::Real(r) => self.write_bytes(format_real(*r).as_bytes()),
+        }
+    }
+
+    // write `xref` table and trailer, entries are grouped into subsections of consecutive numbers,
+    // return offset of the table for startxref
+    pub fn write_xref_table(
+        &mut self,
+        entries: &BTreeMap<u32, XrefEntry>,
+        trailer: &PdfDict,
+    ) -> Result<usize> {
+        let offset = self.position;
+        let links = free_links(entries);
+        self.write_bytes(b"xref\n")?;
+        for (start, nums) in subsections(entries) {
+            self.write_bytes(format!("{} {}\n", start, nums.len()).as_bytes())?;
+            for num in nums {
+                let line = match entries[&num] {
+                    XrefEntry::Free(gen) => format!("{:010} {:05} f\r\n", links[&num], gen),
+                    XrefEntry::Normal(offset, gen) => format!("{:010} {:05} n\r\n", offset, gen),
+                    XrefEntry::Compressed(_, _) => {
+                        return Err(PdfError::Xref(format!(
+                            "compressed object {} can't be in xref table",
+                            num
+                        )));
+                    }
+                };
+                self.write_bytes(line.as_bytes())?;
+            }
+        }
+        self.write_bytes(b"trailer\n")?;
+        self.write_dict(trailer)?;
+        self.write_bytes(b"\n")?;
+        Ok(offset)
+    }
+
+    // write cross-reference stream as object num, trailer entries are in its dictionary,
+    // return offset of the stream for startxref
+    pub fn write_xref_stream(
+        &mut self,
+        num: u32,
+        entries: &BTreeMap<u32, XrefEntry>,
+        trailer: &PdfDict,
+    ) -> Result<usize> {
+        let offset = self.position;
+        let mut entries = entries.to_owned();
+        entries.insert(num, XrefEntry::Normal(offset, 0));
+        let links = free_links(&entries);
+        let max = entries
+            .iter()
+            .map(|(num, e)| match e {
+                XrefEntry::Free(_) => links[num] as usize,
+                XrefEntry::Normal(offset, _) => *offset,
+                XrefEntry::Compressed(stream, _) => *stream as usize,
+            })
+            .max()
+            .unwrap_or(0);
+        let width = (usize::BITS - max.leading_zeros()).div_ceil(8).max(1) as usize;
+
+        let mut data = Vec::new();
+        let mut index = Vec::new();
+        for (start, nums) in subsections(&entries) {
+            index.push(PdfObject::Number(PdfNumber::Integer(start as i32)));
+            index.push(PdfObject::Number(PdfNumber::Integer(nums.len() as i32)));
+            for num in nums {
+                let (t, field2, field3) = match entries[&num] {
+                    XrefEntry::Free(gen) => (0, links[&num] as usize, gen),
+                    XrefEntry::Normal(offset, gen) => (1, offset, gen),
+                    XrefEntry::Compressed(stream, index) => (2, stream as usize, index),
+                };
+                data.push(t);
+                data.extend_from_slice(&field2.to_be_bytes()[(usize::BITS / 8) as usize - width..]);
+                data.extend_from_slice(&field3.to_be_bytes());
+            }
+        }
+
+        let mut dict = trailer.to_owned();
+        dict.insert("Type", PdfObject::Name(PdfName::new("XRef".to_string())));
+        dict.insert(
+            "W",
+            PdfObject::Array(PdfArray::new(vec![
+                PdfObject::Number(PdfNumber::Integer(1)),
+                PdfObject::Number(PdfNumber::Integer(width as i32)),
+                PdfObject::Number(PdfNumber::Integer(2)),
+            ])),
+        );
+        dict.insert("Index", PdfObject::Array(PdfArray::new(index)));
+        let size = entries.keys().max().map(|n| n + 1).unwrap_or(0);
+        let size = match trailer.get("Size") {
+            Some(s) => size.max(s.integer()? as u32),
+            None => size,
+        };
+        dict.insert("Size", PdfObject::Number(PdfNumber::Integer(size as i32)));
+        let stream = PdfStream::new(dict, data);
+        self.write_indirect_object((num, 0), &PdfObject::Stream(stream))?;
+        Ok(offset)
+    }
+
+    // `startxref` and end of file marker, the last part of a file or an incremental update
+    pub fn write_startxref(&mut self, xref_offset: usize) -> Result<()> {
+        self.write_bytes(format!("startxref\n{}\n%%EOF\n", xref_offset).as_bytes())
+    }
+
+    // keys are sorted, the same dictionary is always written the same
+    pub fn write_dict(&mut self, dict: &PdfDict) -> Result<()> {
+        self.write_dict_entries(dict, None)
+    }
+
+    // Length is always the length of the data, the Length in dictionary may be an indirect object
+    pub fn write_stream(&mut self, stream: &PdfStream) -> Result<()> {
+        let data = stream.raw_data();
+        self.write_dict_entries(stream.dict(), Some(data.len()))?;
+        self.write_bytes(b"\nstream\n")?;
+        self.write_bytes(data)?;
+        self.write_bytes(b"\nendstream")
+    }
+
+    fn write_dict_entries(&mut self, dict: &PdfDict, length: Option<usize>) -> Result<()> {
+        let mut keys: Vec<&String> = dict.entries().keys().collect();
+        keys.sort();
+        self.write_bytes(b"<<")?;
+        let mut first = true;
+        for key in keys {
+            let obj = match dict.get(key) {
+                Some(_) if length.is_some() && key == "Length" => continue,
+                Some(obj) => obj,
+                None => continue,
+            };
+            if !first {
+                self.write_bytes(b" ")?;
+            }
+            first = false;
+            self.write_bytes(b"/")?;
+            self.write_bytes(escape_name(key.as_bytes()).as_slice())?;
+            self.write_bytes(b" ")?;
+            self.write_object(obj)?;
+        }
+        if let Some(length) = length {
+            if !first {
+                self.write_bytes(b" ")?;
+            }
+            self.write_bytes(format!("/Length {}", length).as_bytes())?;
+        }
+        self.write_bytes(b">>")
+    }
+}
+
+// serialize an object to bytes
+pub fn to_bytes(obj: &PdfObject) -> Result<Vec<u8>> {
+    let mut writer = PdfWriter::new(Vec::new());
+    writer.write_object(obj)?;
+    Ok(writer.into_inner())
+}
+
+// pack objects into a compressed `/Type /ObjStm` stream, stream objects can't be packed
+pub fn object_stream(objects: &[(u32, PdfObject)]) -> Result<PdfStream> {
+    let mut header = Vec::new();
+    let mut body = PdfWriter::new(Vec::new());
+    for (num, obj) in objects {
+        if matches!(obj, PdfObject::Stream(_)) {
+            return Err(PdfError::Object(format!(
+                "stream object {} can't be in object stream",
+                num
+            )));
+        }
+        header.extend_from_slice(format!("{} {} ", num, body.position()).as_bytes());
+        body.write_object(obj)?;
+        body.write_bytes(b"\n")?;
+    }
+    let first = header.len();
+    header.extend_from_slice(body.into_inner().as_slice());
+    let data = flate_encode(header.as_slice())?;
+
+    let mut dict = PdfDict::default();
+    dict.insert("Type", PdfObject::Name(PdfName::new("ObjStm".to_string())));
+    dict.insert(
+        "N",
+        PdfObject::Number(PdfNumber::Integer(objects.len() as i32)),
+    );
+    dict.insert("First", PdfObject::Number(PdfNumber::Integer(first as i32)));
+    dict.insert(
+        "Filter",
+        PdfObject::Name(PdfName::new("FlateDecode".to_string())),
+    );
+    Ok(PdfStream::new(dict, data))
+}
+
+// group sorted object numbers into runs of consecutive numbers
+fn subsections(entries: &BTreeMap<u32, XrefEntry>) -> Vec<(u32, Vec<u32>)> {
+    let mut sections: Vec<(u32, Vec<u32>)> = Vec::new();
+    for num in entries.keys() {
+        match sections.last_mut() {
+            Some((start, nums)) if *start + nums.len() as u32 == *num => nums.push(*num),
+            _ => sections.push((*num, vec![*num])),
+        }
+    }
+    sections
+}
+
+// free entries are a list from entry 0 in the order of object number, each one has
+// the number of the next free object and the last one links back to 0
+fn free_links(entries: &BTreeMap<u32, XrefEntry>) -> HashMap<u32, u32> {
+    let free: Vec<u32> = entries
+        .iter()
+        .filter(|(_, e)| matches!(e, XrefEntry::Free(_)))
+        .map(|(num, _)| *num)
+        .collect();
+    free.iter()
+        .enumerate()
+        .map(|(i, num)| (*num, free.get(i + 1).copied().unwrap_or(0)))
+        .collect()
+}
+
+// regular characters are written as is, others as #xx
+fn escape_name(name: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(name.len());
+    for ch in name {
+        if (b'!'..=b'~').contains(ch) && ch != &NUMBER_SIGN && !is_delimiter(ch) {
+            bytes.push(*ch);
+        } else {
+            bytes.push(NUMBER_SIGN);
+            bytes.push(HEX_DIGITS[(ch >> 4) as usize]);
+            bytes.push(HEX_DIGITS[(ch & 0x0f) as usize]);
+        }
+    }
+    bytes
+}
+
+// parentheses are always escaped, so they need not be balanced
+fn escape_literal(s: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(s.len());
+    for ch in s {
+        match ch {
+            b'(' | b')' | b'\\' => {
+                bytes.push(b'\\');
+                bytes.push(*ch);
+            }
+            b'\n' => bytes.extend_from_slice(b"\\n"),
+            b'\r' => bytes.extend_from_slice(b"\\r"),
+            b'\t' => bytes.extend_from_slice(b"\\t"),
+            0x08 => bytes.extend_from_slice(b"\\b"),
+            0x0c => bytes.extend_from_slice(b"\\f"),
+            0x20..=0x7e => bytes.push(*ch),
+            _ => bytes.extend_from_slice(format!("\\{:03o}", ch).as_bytes()),
+        }
+    }
+    bytes
+}
+
+pub(crate) fn encode_hex(data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(data.len() * 2);
+    for ch in data {
+        bytes.push(HEX_DIGITS[(ch >> 4) as usize]);
+        bytes.push(HEX_DIGITS[(ch & 0x0f) as usize]);
+    }
+    bytes
+}
+
+// pdf has no exponent form of real, nan and infinite are written as 0
+fn format_real(r: f32) -> String {
+    if !r.is_finite() || r == 0.0 {
+        return "0.0".to_string();
+    }
+    let s = r.to_string();
+    if s.contains('.') {
+        s
+    } else {
+        format!("{}.0", s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::{to_bytes, PdfWriter, XrefEntry};
+    use crate::object::array::PdfArray;
+    use crate::object::bool::PdfBool;
+    use crate::object::dictionary::PdfDict;
+    use crate::object::name::PdfName;
+    use crate::object::number::PdfNumber;
+    use crate::object::stream::PdfStream;
+    use crate::object::string::{PdfHexString, PdfLiteral};
+    use crate::object::PdfObject;
+    use crate::reader::PdfReader;
+
+    fn write(obj: &PdfObject) -> String {
+        String::from_utf8(to_bytes(obj).unwrap()).unwrap()
+    }
+
+    fn round_trip(obj: PdfObject) {
+        let mut bytes = to_bytes(&obj).unwrap();
+        bytes.extend_from_slice(b"\nendobj");
+        let reader = PdfReader::new(bytes);
+        assert_eq!(reader.read_object().unwrap(), obj);
+    }
+
+    #[test]
+    fn test_write_name() {
+        let name = |s: &str| PdfObject::Name(PdfName::new(s.to_string()));
+        assert_eq!(write(&name("Name1")), "/Name1");
+        assert_eq!(write(&name("lime Green")), "/lime#20Green");
+        assert_eq!(
+            write(&name("paired()parentheses")),
+            "/paired#28#29parentheses"
+        );
+        assert_eq!(
+            write(&name("The_Key_of_F#_Minor")),
+            "/The_Key_of_F#23_Minor"
+        );
+        round_trip(name("A/B[C]#D E"));
+    }
+
+    #[test]
+    fn test_write_string() {
+        let literal = |s: &[u8]| PdfObject::LiteralString(PdfLiteral::new(s.to_vec()));
+        assert_eq!(write(&literal(b"a (b) \\c")), "(a \\(b\\) \\\\c)");
+        assert_eq!(
+            write(&literal(b"line\r\n\t\x05\xff")),
+            "(line\\r\\n\\t\\005\\377)"
+        );
+        round_trip(literal(b"unbalanced ( \\ \x00\x08\x0c\xfe)"));
+
+        let hex = PdfObject::HexString(PdfHexString::new(b"901fa3".to_vec()));
+        assert_eq!(write(&hex), "<901FA3>");
+        round_trip(PdfObject::HexString(PdfHexString::new(b"901FA3".to_vec())));
+    }
+
+    #[test]
+    fn test_write_number() {
+        let real = |r: f32| write(&PdfObject::Number(PdfNumber::Real(r)));
+        assert_eq!(real(3.0), "3.0");
+        assert_eq!(real(-0.5), "-0.5");
+        assert_eq!(real(0.1), "0.1");
+        assert_eq!(real(1e-7), "0.0000001");
+        assert_eq!(real(f32::NAN), "0.0");
+        assert_eq!(write(&PdfObject::Number(PdfNumber::Integer(-12))), "-12");
+        round_trip(PdfObject::Number(PdfNumber::Real(612.25)));
+    }
+
+    #[test]
+    fn test_write_dict_and_stream() {
+        let mut entries = HashMap::new();
+        entries.insert(
+            "Type".to_string(),
+            PdfObject::Name(PdfName::new("Page".to_string())),
+        );
+        entries.insert("Parent".to_string(), PdfObject::Indirect((1, 0)));
+        entries.insert(
+            "MediaBox".to_string(),
+            PdfObject::Array(PdfArray::new(vec![
+                PdfObject::Number(PdfNumber::Integer(0)),
+                PdfObject::Number(PdfNumber::Real(612.5)),
+            ])),
+        );
+        entries.insert("Hidden".to_string(), PdfObject::Bool(PdfBool(false)));
+        entries.insert("Opt".to_string(), PdfObject::Null);
+        let dict = PdfObject::Dict(PdfDict::new(entries));
+        assert_eq!(
+            write(&dict),
+            "<</Hidden false /MediaBox [0 612.5] /Opt null /Parent 1 0 R /Type /Page>>"
+        );
+        round_trip(dict);
+
+        let mut entries = HashMap::new();
+        entries.insert("Length".to_string(), PdfObject::Indirect((9, 0)));
+        let stream = PdfStream::new(PdfDict::new(entries), b"BT ET".to_vec());
+        let mut writer = PdfWriter::with_position(Vec::new(), 15);
+        let offset = writer
+            .write_indirect_object((4, 0), &PdfObject::Stream(stream))
+            .unwrap();
+        assert_eq!(offset, 15);
+        let bytes = writer.into_inner();
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "4 0 obj\n<</Length 5>>\nstream\nBT ET\nendstream\nendobj\n"
+        );
+        let reader = PdfReader::new(bytes);
+        let dict = reader.read_indirect_object(0).unwrap().to_dict().unwrap();
+        assert_eq!(dict.get("Length").unwrap().integer().unwrap(), 5);
+        assert_eq!(reader.read_stream_data(Some(5)).unwrap(), b"BT ET");
+    }
+
+    #[test]
+    fn test_write_xref_table() {
+        let entries = BTreeMap::from([
+            (0, XrefEntry::Free(65535)),
+            (1, XrefEntry::Normal(15, 0)),
+            (2, XrefEntry::Free(1)),
+            (3, XrefEntry::Normal(80, 0)),
+            (5, XrefEntry::Free(2)),
+        ]);
+        let mut writer = PdfWriter::with_position(Vec::new(), 100);
+        let offset = writer
+            .write_xref_table(&entries, &PdfDict::default())
+            .unwrap();
+        assert_eq!(offset, 100);
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "xref\n0 4\n\
+             0000000002 65535 f\r\n\
+             0000000015 00000 n\r\n\
+             0000000005 00001 f\r\n\
+             0000000080 00000 n\r\n\
+             5 1\n\
+             0000000000 00002 f\r\n\
+             trailer\n<<>>\n"
+        );
+    }
+}
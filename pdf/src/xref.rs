@@ -1,22 +1,32 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use md5::{Digest, Md5};
+
+use crate::cache::LruCache;
 use crate::character::{u16_from_buffer, u32_from_buffer, usize_from_buffer};
-use crate::error::{PdfError, Result};
-use crate::object;
+use crate::diagnostics::{Diagnostic, DiagnosticCategory, Diagnostics};
+use crate::error::{PdfError, Result, ResultExt};
 use crate::object::{
-    array::PdfArray, dictionary::PdfDict, number::PdfNumber, stream::PdfStream, ObjectId, PdfObject,
+    array::PdfArray, dictionary::PdfDict, number::PdfNumber, stream::PdfStream,
+    string::PdfHexString, ObjectId, PdfObject,
 };
-use crate::reader::PdfReader;
+use crate::reader::{PdfReader, Token};
 use crate::security::SecurityHandler;
+use crate::source::{ByteSource, MemorySource};
+use crate::writer::{encode_hex, object_stream, PdfWriter, XrefEntry};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ObjectState {
     Normal,
     Free,
     Compressed,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ObjectInfo {
     offset: usize,
     state: ObjectState,
@@ -27,24 +37,167 @@ impl ObjectInfo {
     pub fn new(offset: usize, state: ObjectState, gen: u16) -> Self {
         Self { offset, state, gen }
     }
+}
+
+type ObjectTable = HashMap<u32, ObjectInfo>;
+
+// object changed after the document is loaded, saved by an incremental update
+#[derive(Debug, Clone)]
+pub enum ObjectUpdate {
+    Changed(u16, PdfObject),
+    // generation used if the object number is used again
+    Deleted(u16),
+}
+
+// trailer entries kept in the trailer of an update
+const UPDATE_TRAILER_KEYS: [&str; 3] = ["Root", "Info", "ID"];
+// objects in one object stream written by write_full
+const OBJECT_STREAM_SIZE: usize = 100;
+
+const OBJECT_STREAM_CACHE_SIZE: usize = 16;
+const OBJECT_CACHE_SIZE: usize = 1024;
+
+// decoded `/Type /ObjStm` stream and the (objnum, offset) pairs from its header
+#[derive(Debug)]
+struct ObjectStream {
+    data: Arc<dyn ByteSource>,
+    offsets: Vec<(u32, usize)>,
+}
+
+impl ObjectStream {
+    fn try_new(stream: &PdfStream, xref: &Xref) -> Result<Self> {
+        let decode_data = stream.decode_data(Some(xref))?;
+        let data: Arc<dyn ByteSource> = Arc::new(MemorySource::new(decode_data));
+        let reader = PdfReader::new_from_shared(data.clone());
+        let n = stream
+            .get_from_dict("N")
+            .ok_or(PdfError::Xref("Objects Stream N is None".to_string()))?
+            .integer()
+            .map_err(|_| PdfError::Xref("N is not number in Objects Stream".to_string()))?;
+        let first = stream
+            .get_from_dict("First")
+            .ok_or(PdfError::Xref("Objects stream First is None".to_string()))?
+            .integer()? as usize;
+
+        let mut offsets = Vec::new();
+        for _ in 0..n {
+            let objnum = reader.read_number()?.integer();
+            let offset = reader.read_number()?.integer();
+            offsets.push((objnum as u32, first + offset as usize));
+        }
+        Ok(ObjectStream { data, offsets })
+    }
+
+    fn offsets(&self) -> &[(u32, usize)] {
+        self.offsets.as_slice()
+    }
+
+    // object num at index, if index is wrong find it by num
+    fn read_object(&self, num: u32, index: usize, diagnostics: &Diagnostics) -> Result<PdfObject> {
+        let entry = match self.offsets.get(index) {
+            Some((n, _)) if *n == num => self.offsets.get(index),
+            _ => {
+                diagnostics.warn(
+                    Diagnostic::new(
+                        DiagnosticCategory::Object,
+                        format!("object is not at index {} of objects stream", index),
+                    )
+                    .with_object((num, 0)),
+                )?;
+                self.offsets.iter().find(|(n, _)| *n == num)
+            }
+        };
+        match entry {
+            Some((_, offset)) => {
+                let reader = PdfReader::new_from_shared(self.data.clone());
+                reader.reset_offset(*offset);
+                reader.read_object()
+            }
+            None => Err(PdfError::Xref(format!(
+                "object {} not found in objects stream",
+                num
+            ))),
+        }
+    }
+}
+
+// one cross-reference section with its trailer, the original document or an incremental update
+#[derive(Debug)]
+pub struct Revision {
+    offset: usize,
+    objects: ObjectTable,
+    trailer: PdfDict,
+}
+
+impl Revision {
     pub fn offset(&self) -> usize {
         self.offset
     }
-    pub fn gen(&self) -> u16 {
-        self.gen
+
+    pub fn trailer(&self) -> &PdfDict {
+        &self.trailer
+    }
+
+    pub fn objects_num(&self) -> usize {
+        self.objects.len()
     }
 }
 
 #[derive(Debug)]
 pub struct Xref {
-    reader: PdfReader,
+    source: Arc<dyn ByteSource>,
     objects: HashMap<u32, ObjectInfo>,
     trailer: PdfDict,
+    revisions: Vec<Revision>,
+    revision: usize,
+    object_streams: Mutex<LruCache<u32, Arc<ObjectStream>>>,
+    objects_cache: Mutex<LruCache<ObjectId, PdfObject>>,
     security_handler: Option<SecurityHandler>,
+    reconstructed: bool,
+    diagnostics: Arc<Diagnostics>,
+    updates: BTreeMap<u32, ObjectUpdate>,
 }
 
 impl Xref {
+    pub fn new(reader: PdfReader) -> Self {
+        Xref {
+            source: reader.source(),
+            objects: HashMap::new(),
+            trailer: PdfDict::default(),
+            revisions: Vec::new(),
+            revision: 0,
+            object_streams: Mutex::new(LruCache::new(OBJECT_STREAM_CACHE_SIZE)),
+            objects_cache: Mutex::new(LruCache::new(OBJECT_CACHE_SIZE)),
+            security_handler: None,
+            reconstructed: false,
+            diagnostics: Arc::new(Diagnostics::default()),
+            updates: BTreeMap::new(),
+        }
+    }
+
+    pub fn set_diagnostics(&mut self, diagnostics: Arc<Diagnostics>) {
+        self.diagnostics = diagnostics;
+    }
+
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
     pub fn try_new(reader: PdfReader, password: Option<&[u8]>) -> Result<Self> {
+        let mut xref = Xref::new(reader);
+        xref.load(password)?;
+        Ok(xref)
+    }
+
+    pub fn try_rebuild(reader: PdfReader, password: Option<&[u8]>) -> Result<Self> {
+        let mut xref = Xref::new(reader);
+        xref.rebuild(password)?;
+        Ok(xref)
+    }
+
+    // read cross-reference sections start from startxref
+    pub fn load(&mut self, password: Option<&[u8]>) -> Result<()> {
+        let reader = self.reader();
         let start_xref = find_start_xref(&reader)?;
         reader.reset_offset(start_xref);
         let v = reader.read_token()?;
@@ -52,128 +205,636 @@ impl Xref {
             return Err(PdfError::File("read startxref failed".to_string()));
         }
         let xref_offset = reader.read_number()?;
-        reader.reset_offset(xref_offset.integer() as usize);
-        let nb = reader.read_token()?;
-        let mut xref = if nb.is_other_key(b"xref") {
-            read_xref_v4(reader, xref_offset)?
-        } else {
-            read_xref_v5(reader, xref_offset)?
+        let revisions = read_revisions(&reader, xref_offset.integer() as usize)?;
+        self.revision = revisions.len() - 1;
+        self.objects = merge_revisions(&revisions);
+        self.trailer = revisions[self.revision].trailer.to_owned();
+        self.revisions = revisions;
+        self.security_handler = None;
+        self.clear_cache();
+        self.reconstructed = false;
+        self.load_security_handler(password)
+    }
+
+    // read only the first-page cross-reference section of a linearized file,
+    // objects of other pages are not available until load is called
+    pub fn load_first_page(&mut self, offset: usize, password: Option<&[u8]>) -> Result<()> {
+        let reader = self.reader();
+        let revision = read_revision(&reader, offset)?;
+        self.objects = revision.objects.to_owned();
+        self.trailer = revision.trailer.to_owned();
+        self.revisions = vec![revision];
+        self.revision = 0;
+        self.security_handler = None;
+        self.clear_cache();
+        self.reconstructed = false;
+        self.load_security_handler(password)
+    }
+
+    // reconstruct cross-reference by scan all `N G obj` and trailer in file
+    pub fn rebuild(&mut self, password: Option<&[u8]>) -> Result<()> {
+        let reader = self.reader();
+        let (objects, mut trailers) = scan_objects(&reader)?;
+        self.objects = objects;
+        self.security_handler = None;
+        self.reconstructed = true;
+        self.clear_cache();
+
+        let mut xref_streams = Vec::new();
+        let mut object_streams = Vec::new();
+        let mut catalogs = Vec::new();
+        for (num, info) in self.objects.iter() {
+            let dict = match reader.read_indirect_object(info.offset) {
+                Ok(PdfObject::Dict(d)) => d,
+                _ => continue,
+            };
+            match dict.get("Type") {
+                Some(PdfObject::Name(t)) if t.name() == "XRef" => {
+                    xref_streams.push((info.offset, dict));
+                }
+                Some(PdfObject::Name(t)) if t.name() == "ObjStm" => {
+                    object_streams.push((info.offset, *num));
+                }
+                Some(PdfObject::Name(t)) if t.name() == "Catalog" => {
+                    catalogs.push((info.offset, (*num, info.gen)));
+                }
+                _ => {}
+            }
+        }
+
+        trailers.extend(xref_streams);
+        trailers.sort_by_key(|(offset, _)| *offset);
+        let mut entries = HashMap::new();
+        for (_, tr) in trailers {
+            for key in ["Root", "Info", "ID", "Encrypt"] {
+                if let Some(v) = tr.get(key) {
+                    entries.insert(key.to_string(), v.to_owned());
+                }
+            }
+        }
+        let size = self.objects.keys().max().map(|n| n + 1).unwrap_or(0);
+        entries.insert(
+            "Size".to_string(),
+            PdfObject::Number(PdfNumber::Integer(size as i32)),
+        );
+        self.trailer = PdfDict::new(entries);
+        self.load_security_handler(password)?;
+
+        object_streams.sort_by_key(|(offset, _)| *offset);
+        let mut compressed = HashMap::new();
+        for (_, num) in object_streams {
+            match self.load_object_stream(num) {
+                Ok(objects_stream) => {
+                    for (index, (n, _)) in objects_stream.offsets().iter().enumerate() {
+                        compressed.insert(
+                            *n,
+                            ObjectInfo::new(num as usize, ObjectState::Compressed, index as u16),
+                        );
+                    }
+                }
+                Err(e) => {
+                    self.diagnostics.warn(
+                        Diagnostic::new(
+                            DiagnosticCategory::Xref,
+                            format!("rebuild xref, read object stream failed:{:?}", e),
+                        )
+                        .with_object((num, 0)),
+                    )?;
+                }
+            }
+        }
+        for (n, info) in compressed {
+            self.objects.entry(n).or_insert(info);
+        }
+
+        let root_exist = match self.trailer.get("Root") {
+            Some(PdfObject::Indirect(id)) => self.objects.contains_key(&id.0),
+            _ => false,
         };
+        if !root_exist {
+            catalogs.sort_by_key(|(offset, _)| *offset);
+            match catalogs.last() {
+                Some((_, id)) => {
+                    let mut entries = self.trailer.entries().to_owned();
+                    entries.insert("Root".to_string(), PdfObject::Indirect(*id));
+                    self.trailer = PdfDict::new(entries);
+                }
+                None => {
+                    return Err(PdfError::Xref(
+                        "rebuild xref failed, Catalog not found".to_string(),
+                    ));
+                }
+            }
+        }
+        self.revisions = vec![Revision {
+            offset: 0,
+            objects: self.objects.to_owned(),
+            trailer: self.trailer.to_owned(),
+        }];
+        self.revision = 0;
+        Ok(())
+    }
 
-        if let Some(encrypt) = xref.trailer().get("Encrypt") {
-            let encrypt = xref.read_object(encrypt)?.to_dict()?;
-            let sec = SecurityHandler::try_new(&encrypt, &xref, password)?;
-            xref.security_handler = Some(sec);
+    // revisions in file order, the first one is the original document
+    pub fn revisions(&self) -> &[Revision] {
+        self.revisions.as_slice()
+    }
+
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    // view the document as it was saved at revision, later updates are ignored
+    pub fn set_revision(&mut self, revision: usize) -> Result<()> {
+        if revision >= self.revisions.len() {
+            return Err(PdfError::Xref(format!(
+                "revision {} out of range, document has {} revisions",
+                revision,
+                self.revisions.len()
+            )));
         }
-        Ok(xref)
+        self.objects = merge_revisions(&self.revisions[..=revision]);
+        self.trailer = self.revisions[revision].trailer.to_owned();
+        self.revision = revision;
+        self.clear_cache();
+        Ok(())
+    }
+
+    fn load_security_handler(&mut self, password: Option<&[u8]>) -> Result<()> {
+        if let Some(encrypt) = self.trailer().get("Encrypt") {
+            let encrypt = self.read_object(encrypt)?.to_dict()?;
+            let sec = SecurityHandler::try_new(&encrypt, self, password)?;
+            self.security_handler = Some(sec);
+        }
+        Ok(())
+    }
+
+    pub fn is_reconstructed(&self) -> bool {
+        self.reconstructed
     }
 
     pub fn read_object(&self, obj: &PdfObject) -> Result<PdfObject> {
         match obj {
-            PdfObject::Indirect(object_id) => {
-                let o = self.read_indirect_object(object_id)?;
-                return Ok(o);
-            }
+            PdfObject::Indirect(object_id) => self.read_indirect_object(object_id),
             _ => Ok(obj.to_owned()),
         }
     }
     pub fn read_indirect_object(&self, objectid: &ObjectId) -> Result<PdfObject> {
+        if let Some(update) = self.updates.get(&objectid.0) {
+            return match update {
+                ObjectUpdate::Changed(gen, obj) if *gen == objectid.1 => Ok(obj.to_owned()),
+                _ => Ok(PdfObject::Null),
+            };
+        }
+        if let Ok(mut cache) = self.objects_cache.lock() {
+            if let Some(obj) = cache.get(objectid) {
+                return Ok(obj.to_owned());
+            }
+        }
+        let obj = self.load_indirect_object(objectid).with_object(*objectid)?;
+        // stream data may be large and is usually used once, only cache the others
+        if !matches!(obj, PdfObject::Stream(_)) {
+            if let Ok(mut cache) = self.objects_cache.lock() {
+                cache.insert(objectid.to_owned(), obj.clone());
+            }
+        }
+        Ok(obj)
+    }
+
+    fn load_indirect_object(&self, objectid: &ObjectId) -> Result<PdfObject> {
         match self.objects.get(&objectid.0) {
+            // reference to free object or other generation is null
             Some(info) => match info.state {
-                ObjectState::Normal | ObjectState::Free => {
-                    let obj = self.reader.read_indirect_object(info.offset)?;
-                    let nt = self.reader.peek_token()?;
-                    if nt.is_other_key(b"stream") {
-                        match obj {
-                            PdfObject::Dict(dict) => match dict.get("Length") {
-                                Some(PdfObject::Number(n)) => {
-                                    let data = self.reader.read_stream_data(n.integer() as usize)?;
-                                    let o = PdfObject::Stream(PdfStream::new(dict, data));
-                                    if let Some(secu) = self.security_handler.as_ref() {
-                                        let o = secu.decrypt_object(&o, objectid.0, objectid.1)?;
-                                        return Ok(o);
-                                    } else {
-                                        return Ok(o);
-                                    }
-                                }
-                                Some(PdfObject::Indirect(lo)) => {
-                                    let cur = self.reader.current_pos();
-                                    let len = self
-                                        .read_indirect_object(lo)?
-                                        .as_number()
-                                        .map_err(|_| {
-                                            PdfError::Xref(
-                                                "PdfStreem Length is not a number".to_string(),
-                                            )
-                                        })?
-                                        .integer();
-                                    self.reader.reset_offset(cur);
-                                    let data = self.reader.read_stream_data(len as usize)?;
-                                    let stream = PdfStream::new(dict, data);
-                                    let o = PdfObject::Stream(stream);
-                                    if let Some(secu) = self.security_handler.as_ref() {
-                                        let o = secu.decrypt_object(&o, objectid.0, objectid.1)?;
-                                        return Ok(o);
-                                    } else {
-                                        return Ok(o);
-                                    }
-                                }
-                                _ => {
-                                    return Err(PdfError::Xref(
-                                        "PdfStream Length is error".to_string(),
-                                    ));
-                                }
-                            },
-                            _ => {
-                                return Ok(obj);
-                            }
-                        }
-                    } else {
-                        return Ok(obj);
-                    }
+                ObjectState::Free => Ok(PdfObject::Null),
+                ObjectState::Normal if info.gen != objectid.1 => {
+                    self.diagnostics.warn(
+                        Diagnostic::new(
+                            DiagnosticCategory::Object,
+                            format!("generation is {} in xref, resolved to null", info.gen),
+                        )
+                        .with_object(objectid.to_owned()),
+                    )?;
+                    Ok(PdfObject::Null)
+                }
+                ObjectState::Normal => self
+                    .load_object_at(info.offset, objectid)
+                    .with_offset(info.offset),
+                // objects in objects stream are always generation 0
+                ObjectState::Compressed if objectid.1 != 0 => {
+                    self.diagnostics.warn(
+                        Diagnostic::new(
+                            DiagnosticCategory::Object,
+                            "object in object stream has generation 0, resolved to null"
+                                .to_string(),
+                        )
+                        .with_object(objectid.to_owned()),
+                    )?;
+                    Ok(PdfObject::Null)
                 }
-                ObjectState::Compressed => self.read_objects_stream(info),
+                ObjectState::Compressed => self.read_objects_stream(objectid.0, info),
             },
             None => Err(PdfError::Xref(format!("Objedct not found:{:?}", objectid))),
         }
     }
 
+    // read indirect object at offset, objectid is used to decrypt it
+    fn load_object_at(&self, offset: usize, objectid: &ObjectId) -> Result<PdfObject> {
+        let reader = self.reader();
+        reader.reset_offset(offset);
+        let num = reader.read_number()?.integer();
+        let gen = reader.read_number()?.integer();
+        if !reader.read_token()?.is_other_key(b"obj") {
+            return Err(PdfError::Xref(format!(
+                "object {:?} at {} need obj keyword",
+                objectid, offset
+            )));
+        }
+        if num != objectid.0 as i32 || gen != objectid.1 as i32 {
+            return Err(PdfError::Xref(format!(
+                "object at {} is {} {}, expected {:?}",
+                offset, num, gen, objectid
+            )));
+        }
+        let obj = reader.read_object()?;
+        let nt = reader.peek_token()?;
+        if !nt.is_other_key(b"stream") {
+            return Ok(obj);
+        }
+        let dict = match obj {
+            PdfObject::Dict(dict) => dict,
+            _ => return Ok(obj),
+        };
+        let length = match dict.get("Length") {
+            Some(PdfObject::Number(n)) => Some(n.integer() as usize),
+            Some(PdfObject::Indirect(lo)) => {
+                match self
+                    .read_indirect_object(lo)
+                    .and_then(|l| Ok(l.as_number()?.integer()))
+                {
+                    Ok(len) => Some(len as usize),
+                    Err(PdfError::MissingRange(offset, len)) => {
+                        return Err(PdfError::MissingRange(offset, len))
+                    }
+                    Err(e) => {
+                        self.diagnostics.warn(
+                            Diagnostic::new(
+                                DiagnosticCategory::Stream,
+                                format!("stream Length {:?} is invalid:{:?}", lo, e),
+                            )
+                            .with_object(objectid.to_owned())
+                            .with_offset(offset),
+                        )?;
+                        None
+                    }
+                }
+            }
+            _ => {
+                self.diagnostics.warn(
+                    Diagnostic::new(
+                        DiagnosticCategory::Stream,
+                        "stream Length is missing, scan for endstream".to_string(),
+                    )
+                    .with_object(objectid.to_owned())
+                    .with_offset(offset),
+                )?;
+                None
+            }
+        };
+        let data = reader.read_stream_data(length)?;
+        let o = PdfObject::Stream(PdfStream::new(dict, data));
+        match self.security_handler.as_ref() {
+            Some(secu) => secu.decrypt_object(&o, objectid.0, objectid.1),
+            None => Ok(o),
+        }
+    }
+
+    // read the indirect object at offset which may be not in the cross-reference table
+    pub fn read_object_at(&self, offset: usize) -> Result<PdfObject> {
+        let reader = self.reader();
+        reader.reset_offset(offset);
+        let num = reader.read_number()?.integer() as u32;
+        let gen = reader.read_number()?.integer() as u16;
+        self.load_object_at(offset, &(num, gen))
+            .with_object((num, gen))
+            .with_offset(offset)
+    }
+
     pub fn objects_num(&self) -> usize {
         self.objects.len()
     }
-    fn read_objects_stream(&self, info: &ObjectInfo) -> Result<PdfObject> {
-        // TODO cache
-        let objects_stream = self
-            .read_indirect_object(&(info.offset as u32, 0))?
-            .to_stream()?;
-        let decode_data = objects_stream.decode_data(None)?;
-        let reader = PdfReader::new(decode_data);
-        let n = objects_stream
-            .get_from_dict("N")
-            .ok_or(PdfError::Xref("Objects Stream N is None".to_string()))?
-            .integer()
-            .map_err(|_| PdfError::Xref("N is not number in Objects Stream".to_string()))?;
-        let first = objects_stream
-            .get_from_dict("First")
-            .ok_or(PdfError::Xref("Objects stream First is None".to_string()))?
-            .integer()? as usize;
 
-        let tn = (n * 2) as usize;
-        let mut ns = Vec::new();
-        for _ in 0..tn {
-            let v = reader.read_number()?;
-            ns.push(v.integer());
+    // replace or create object, it is read from the update until the document is reloaded
+    pub fn set_object(&mut self, objectid: ObjectId, obj: PdfObject) -> Result<()> {
+        if objectid.0 == 0 {
+            return Err(PdfError::Xref("object number 0 is reserved".to_string()));
         }
-        for (i, v) in ns.chunks(2).enumerate() {
-            if i == info.gen as usize {
-                reader.reset_offset(first + v[1] as usize);
-                return reader.read_object();
+        self.updates
+            .insert(objectid.0, ObjectUpdate::Changed(objectid.1, obj));
+        Ok(())
+    }
+
+    // add object with a new object number
+    pub fn add_object(&mut self, obj: PdfObject) -> ObjectId {
+        let objectid = (self.next_object_number(), 0);
+        self.updates
+            .insert(objectid.0, ObjectUpdate::Changed(objectid.1, obj));
+        objectid
+    }
+
+    pub fn delete_object(&mut self, objectid: ObjectId) -> Result<()> {
+        let exist = match self.updates.get(&objectid.0) {
+            Some(ObjectUpdate::Changed(gen, _)) => *gen == objectid.1,
+            Some(ObjectUpdate::Deleted(_)) => false,
+            None => matches!(
+                self.objects.get(&objectid.0),
+                Some(info) if !matches!(info.state, ObjectState::Free)
+                    && (matches!(info.state, ObjectState::Compressed) || info.gen == objectid.1)
+            ),
+        };
+        if !exist {
+            return Err(PdfError::Xref(format!(
+                "delete object {:?} not in document",
+                objectid
+            )));
+        }
+        self.updates.insert(
+            objectid.0,
+            ObjectUpdate::Deleted(objectid.1.saturating_add(1)),
+        );
+        Ok(())
+    }
+
+    pub fn updates(&self) -> &BTreeMap<u32, ObjectUpdate> {
+        &self.updates
+    }
+
+    // object numbers are not reused, new object is always after the last one
+    pub fn next_object_number(&self) -> u32 {
+        let size = match self.trailer.get("Size") {
+            Some(PdfObject::Number(n)) => n.integer().max(0) as u32,
+            _ => 0,
+        };
+        let objects = self.objects.keys().max().map(|n| n + 1).unwrap_or(0);
+        let updates = self.updates.keys().max().map(|n| n + 1).unwrap_or(0);
+        size.max(objects).max(updates).max(1)
+    }
+
+    // file identifier of a saved file, the first string is kept and the second one
+    // is new for each save, both are new if the document has no ID
+    fn saved_id(&self) -> PdfObject {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let mut hasher = Md5::new();
+        hasher.update(now.to_le_bytes());
+        hasher.update(self.source.len().to_le_bytes());
+        for num in self.updates.keys() {
+            hasher.update(num.to_le_bytes());
+        }
+        let changed = PdfObject::HexString(PdfHexString::new(encode_hex(&hasher.finalize())));
+        let permanent = match self.trailer.get("ID") {
+            Some(PdfObject::Array(id)) => match id.get(0) {
+                Some(s @ (PdfObject::LiteralString(_) | PdfObject::HexString(_))) => s.to_owned(),
+                _ => changed.to_owned(),
+            },
+            _ => changed.to_owned(),
+        };
+        PdfObject::Array(PdfArray::new(vec![permanent, changed]))
+    }
+
+    // write the original file and append the updated objects, cross-reference section
+    // of the same kind as the last one in file, and trailer with Prev to the last section
+    pub fn write_incremental<W: Write>(&self, out: W) -> Result<()> {
+        if self.reconstructed {
+            return Err(PdfError::Xref(
+                "cross-reference is reconstructed, incremental update needs a valid one"
+                    .to_string(),
+            ));
+        }
+        if self.security_handler.is_some() {
+            return Err(PdfError::Xref(
+                "incremental update of encrypted document is not supported".to_string(),
+            ));
+        }
+        let last = match self.revisions.last() {
+            Some(r) if self.revision + 1 == self.revisions.len() => r,
+            _ => {
+                return Err(PdfError::Xref(
+                    "incremental update need the last revision".to_string(),
+                ))
             }
+        };
+
+        let mut writer = PdfWriter::new(out);
+        let data = self.source.read_to_vec(0, self.source.len())?;
+        writer.write_bytes(data.as_slice())?;
+        if !matches!(data.last(), Some(b'\n') | Some(b'\r')) {
+            writer.write_bytes(b"\n")?;
+        }
+
+        let mut entries = BTreeMap::new();
+        for (num, update) in self.updates.iter() {
+            match update {
+                ObjectUpdate::Changed(gen, obj) => {
+                    let offset = writer.write_indirect_object((*num, *gen), obj)?;
+                    entries.insert(*num, XrefEntry::Normal(offset, *gen));
+                }
+                ObjectUpdate::Deleted(gen) => {
+                    entries.insert(*num, XrefEntry::Free(*gen));
+                }
+            }
+        }
+        // the list of free entries starts from entry 0
+        if entries.values().any(|e| matches!(e, XrefEntry::Free(_))) {
+            entries.insert(0, XrefEntry::Free(65535));
+        }
+
+        let mut trailer = PdfDict::default();
+        for key in UPDATE_TRAILER_KEYS {
+            if let Some(v) = self.trailer.get(key) {
+                trailer.insert(key, v.to_owned());
+            }
+        }
+        trailer.insert("ID", self.saved_id());
+        trailer.insert(
+            "Prev",
+            PdfObject::Number(PdfNumber::Integer(last.offset as i32)),
+        );
+        let is_stream =
+            matches!(last.trailer.get("Type"), Some(PdfObject::Name(t)) if t.name() == "XRef");
+        let xref_offset = if is_stream {
+            let num = self.next_object_number();
+            trailer.insert(
+                "Size",
+                PdfObject::Number(PdfNumber::Integer(num as i32 + 1)),
+            );
+            writer.write_xref_stream(num, &entries, &trailer)?
+        } else {
+            let num = self.next_object_number();
+            trailer.insert("Size", PdfObject::Number(PdfNumber::Integer(num as i32)));
+            writer.write_xref_table(&entries, &trailer)?
+        };
+        writer.write_startxref(xref_offset)
+    }
+
+    // write a new file with only the objects reachable from trailer Root and Info, numbered
+    // from 1 in the order they are found. Objects are written decrypted and without Encrypt.
+    // With object_streams, objects other than streams are packed into object streams
+    // and the cross-reference is a stream
+    pub fn write_full<W: Write>(&self, out: W, object_streams: bool) -> Result<()> {
+        let root = match self.trailer.get("Root") {
+            Some(PdfObject::Indirect(id)) => *id,
+            _ => {
+                return Err(PdfError::DocumentStructure(
+                    "Root not in trailer".to_string(),
+                ))
+            }
+        };
+        let mut queue = VecDeque::from([root]);
+        if let Some(PdfObject::Indirect(id)) = self.trailer.get("Info") {
+            queue.push_back(*id);
+        }
+        let mut visited = HashSet::new();
+        let mut found = Vec::new();
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let obj = match self.read_indirect_object(&id) {
+                Ok(obj) => obj,
+                Err(
+                    e @ (PdfError::WrongPassword
+                    | PdfError::MissingRange(_, _)
+                    | PdfError::Diagnostic(_)),
+                ) => return Err(e),
+                Err(e) => {
+                    self.diagnostics.warn(
+                        Diagnostic::new(
+                            DiagnosticCategory::Object,
+                            format!("read object failed:{}, references to it are null", e),
+                        )
+                        .with_object(id),
+                    )?;
+                    continue;
+                }
+            };
+            // reference to free object is null, no need to write it
+            if obj.is_null() {
+                continue;
+            }
+            references(&obj, &mut queue);
+            found.push((id, obj));
+        }
+
+        let numbers: HashMap<ObjectId, u32> = found
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| (*id, i as u32 + 1))
+            .collect();
+        let objects: Vec<(u32, PdfObject)> = found
+            .iter()
+            .enumerate()
+            .map(|(i, (_, obj))| (i as u32 + 1, renumber(obj, &numbers)))
+            .collect();
+        let mut trailer = PdfDict::default();
+        for key in UPDATE_TRAILER_KEYS {
+            if let Some(v) = self.trailer.get(key) {
+                trailer.insert(key, renumber(v, &numbers));
+            }
+        }
+        trailer.insert("ID", self.saved_id());
+
+        let mut version = self.version();
+        if object_streams && version.parse::<f32>().unwrap_or(0.0) < 1.5 {
+            version = "1.5".to_string();
+        }
+        let mut writer = PdfWriter::new(out);
+        writer.write_header(version.as_str())?;
+        let mut entries = BTreeMap::from([(0, XrefEntry::Free(65535))]);
+        let mut packed = Vec::new();
+        for (num, obj) in objects {
+            if object_streams && !matches!(obj, PdfObject::Stream(_)) {
+                packed.push((num, obj));
+                continue;
+            }
+            let offset = writer.write_indirect_object((num, 0), &obj)?;
+            entries.insert(num, XrefEntry::Normal(offset, 0));
+        }
+        let xref_offset = if object_streams {
+            let mut next = found.len() as u32 + 1;
+            for chunk in packed.chunks(OBJECT_STREAM_SIZE) {
+                let stream = object_stream(chunk)?;
+                for (index, (num, _)) in chunk.iter().enumerate() {
+                    entries.insert(*num, XrefEntry::Compressed(next, index as u16));
+                }
+                let offset = writer.write_indirect_object((next, 0), &PdfObject::Stream(stream))?;
+                entries.insert(next, XrefEntry::Normal(offset, 0));
+                next += 1;
+            }
+            trailer.insert("Size", PdfObject::Number(PdfNumber::Integer(next as i32)));
+            writer.write_xref_stream(next, &entries, &trailer)?
+        } else {
+            let size = found.len() as i32 + 1;
+            trailer.insert("Size", PdfObject::Number(PdfNumber::Integer(size)));
+            writer.write_xref_table(&entries, &trailer)?
+        };
+        writer.write_startxref(xref_offset)
+    }
+
+    // version in file header, 1.7 if header is broken
+    fn version(&self) -> String {
+        let header = self.source.read(0, 16).unwrap_or_default();
+        let version = header
+            .strip_prefix(b"%PDF-")
+            .map(|v| {
+                v.iter()
+                    .take_while(|c| c.is_ascii_digit() || **c == b'.')
+                    .map(|c| *c as char)
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        if version.parse::<f32>().is_ok() {
+            version
+        } else {
+            "1.7".to_string()
         }
-        return Err(PdfError::Xref(format!(
-            "{:?} not found in objects stream",
-            info
-        )));
+    }
+
+    fn read_objects_stream(&self, num: u32, info: &ObjectInfo) -> Result<PdfObject> {
+        let objects_stream = self.load_object_stream(info.offset as u32)?;
+        objects_stream.read_object(num, info.gen as usize, &self.diagnostics)
+    }
+
+    fn load_object_stream(&self, num: u32) -> Result<Arc<ObjectStream>> {
+        if let Ok(mut cache) = self.object_streams.lock() {
+            if let Some(objects_stream) = cache.get(&num) {
+                return Ok(objects_stream.clone());
+            }
+        }
+        let gen = self.objects.get(&num).map(|info| info.gen).unwrap_or(0);
+        let stream = self.read_indirect_object(&(num, gen))?.to_stream()?;
+        let objects_stream = Arc::new(ObjectStream::try_new(&stream, self)?);
+        if let Ok(mut cache) = self.object_streams.lock() {
+            cache.insert(num, objects_stream.clone());
+        }
+        Ok(objects_stream)
+    }
+
+    fn clear_cache(&self) {
+        if let Ok(mut cache) = self.object_streams.lock() {
+            cache.clear();
+        }
+        if let Ok(mut cache) = self.objects_cache.lock() {
+            cache.clear();
+        }
+    }
+
+    // every read has its own reader, so Xref can be shared between threads
+    fn reader(&self) -> PdfReader {
+        let mut reader = PdfReader::new_from_shared(self.source.clone());
+        reader.set_diagnostics(self.diagnostics.clone());
+        reader
     }
 
     pub fn trailer(&self) -> &PdfDict {
@@ -190,86 +851,146 @@ impl Xref {
             _ => None,
         }
     }
+}
 
-    pub fn reset_offset(&self, offset: usize) {
-        self.reader.reset_offset(offset)
+// indirect references in obj, Length of stream is not needed because it is written directly
+fn references(obj: &PdfObject, refs: &mut VecDeque<ObjectId>) {
+    match obj {
+        PdfObject::Indirect(id) => refs.push_back(*id),
+        PdfObject::Array(arr) => arr.iter().for_each(|o| references(o, refs)),
+        PdfObject::Dict(d) => d.entries().values().for_each(|o| references(o, refs)),
+        PdfObject::Stream(s) => s
+            .dict()
+            .entries()
+            .iter()
+            .filter(|(k, _)| k.as_str() != "Length")
+            .for_each(|(_, o)| references(o, refs)),
+        _ => {}
     }
+}
 
-    pub fn read_line(&self) -> Result<&[u8]> {
-        self.reader.read_line()
+// replace references with new object numbers, reference to object not written is null
+fn renumber(obj: &PdfObject, numbers: &HashMap<ObjectId, u32>) -> PdfObject {
+    match obj {
+        PdfObject::Indirect(id) => match numbers.get(id) {
+            Some(num) => PdfObject::Indirect((*num, 0)),
+            None => PdfObject::Null,
+        },
+        PdfObject::Array(arr) => PdfObject::Array(PdfArray::new(
+            arr.iter().map(|o| renumber(o, numbers)).collect(),
+        )),
+        PdfObject::Dict(d) => PdfObject::Dict(renumber_dict(d, numbers)),
+        PdfObject::Stream(s) => {
+            let mut dict = renumber_dict(s.dict(), numbers);
+            dict.remove("Length");
+            PdfObject::Stream(PdfStream::new(dict, s.raw_data().to_vec()))
+        }
+        _ => obj.to_owned(),
     }
 }
 
+fn renumber_dict(dict: &PdfDict, numbers: &HashMap<ObjectId, u32>) -> PdfDict {
+    PdfDict::new(
+        dict.entries()
+            .iter()
+            .map(|(k, v)| (k.to_owned(), renumber(v, numbers)))
+            .collect(),
+    )
+}
+
 fn find_start_xref(reader: &PdfReader) -> Result<usize> {
     let len = reader.size().min(4096);
     let pos = reader.size() - len;
     reader.reset_offset(pos);
+    let tail = reader.read_data(len)?;
     let tag = "startxref".as_bytes();
-    let mut cur = 0;
-    let mut i = 0;
-    while i < len {
-        if cur == tag.len() {
+    // incremental updates append new startxref, the last one is valid
+    match tail.windows(tag.len()).rposition(|w| w == tag) {
+        Some(i) => Ok(pos + i),
+        None => Err(PdfError::File("startxref not found".to_string())),
+    }
+}
+
+// follow the Prev chain from offset, return revisions in file order
+fn read_revisions(reader: &PdfReader, offset: usize) -> Result<Vec<Revision>> {
+    let mut revisions = Vec::new();
+    let mut visited = Vec::new();
+    let mut next = Some(offset);
+    while let Some(offset) = next {
+        if visited.contains(&offset) {
             break;
         }
-        let ch = reader.read_byte()?;
-        if ch == &tag[cur] {
-            cur += 1;
-        } else if ch == &tag[0] {
-            cur = 1;
-        } else {
-            cur = 0
-        }
-        i += 1;
+        visited.push(offset);
+        let revision = read_revision(reader, offset)?;
+        next = match revision.trailer.get("Prev") {
+            Some(p) => Some(p.integer()? as usize),
+            None => None,
+        };
+        revisions.push(revision);
     }
-    let startxref = pos + i - tag.len();
-    Ok(startxref)
+    revisions.reverse();
+    Ok(revisions)
 }
 
-fn read_xref_v4(reader: PdfReader, offset: PdfNumber) -> Result<Xref> {
-    let (mut objects, trailer) = read_xref_section(&reader, offset.integer() as usize)?;
-    if let Some(p) = trailer.get("Prev") {
-        let mut visited = Vec::new();
-        visited.push(offset.integer());
-        let mut prev_offset = p.integer()?;
-        loop {
-            if visited.contains(&prev_offset) {
-                break;
-            }
-            let (objs, tr) = read_xref_section(&reader, prev_offset as usize)?;
-            objects.extend(objs);
-            visited.push(prev_offset);
-            if let Some(p) = tr.get("Prev") {
-                prev_offset = p.integer()?;
-            } else {
-                break;
+fn read_revision(reader: &PdfReader, offset: usize) -> Result<Revision> {
+    reader.reset_offset(offset);
+    let token = reader.read_token()?;
+    if token.is_other_key(b"xref") {
+        let (mut objects, trailer) = read_xref_section(reader, offset)?;
+        // hybrid-reference file, compressed objects are only in the XRefStm stream
+        if let Some(stm) = trailer.get("XRefStm") {
+            let st = read_xref_stream(reader, stm.integer()? as usize)?;
+            for (num, info) in parse_xref_stream(&st, reader.diagnostics())? {
+                if !matches!(objects.get(&num), Some(o) if matches!(o.state, ObjectState::Normal)) {
+                    objects.insert(num, info);
+                }
             }
         }
+        Ok(Revision {
+            offset,
+            objects,
+            trailer,
+        })
+    } else {
+        let st = read_xref_stream(reader, offset)?;
+        let objects = parse_xref_stream(&st, reader.diagnostics())?;
+        Ok(Revision {
+            offset,
+            objects,
+            trailer: st.dict().to_owned(),
+        })
     }
-    Ok(Xref {
-        reader,
-        objects,
-        trailer,
-        security_handler: None,
-    })
 }
 
-fn read_xref_section(
-    reader: &PdfReader,
-    offset: usize,
-) -> Result<(HashMap<u32, ObjectInfo>, PdfDict)> {
+// newer revision entries override older ones
+fn merge_revisions(revisions: &[Revision]) -> ObjectTable {
+    let mut objects = HashMap::new();
+    for revision in revisions {
+        for (num, info) in revision.objects.iter() {
+            objects.insert(*num, info.to_owned());
+        }
+    }
+    objects
+}
+
+fn read_xref_section(reader: &PdfReader, offset: usize) -> Result<(ObjectTable, PdfDict)> {
     reader.reset_offset(offset);
     reader.skip_white_space()?;
     let xref_token = reader.read_token()?;
     let mut objects = HashMap::new();
     if xref_token.is_other_key(b"xref") {
         loop {
-            let start = u32_from_buffer(reader.read_token()?.buffer().unwrap())?;
-            let count = u32_from_buffer(reader.read_token()?.buffer().unwrap())?;
+            let start = u32_from_buffer(reader.read_token()?.buffer().ok_or(PdfError::Xref(
+                "xref subsection start is not a number".to_string(),
+            ))?)?;
+            let count = u32_from_buffer(reader.read_token()?.buffer().ok_or(PdfError::Xref(
+                "xref subsection count is not a number".to_string(),
+            ))?)?;
             let entries = read_xref_table(reader, start, count)?;
             objects.extend(entries);
             reader.skip_white_space()?;
             let next = reader.peek_bytes(7)?;
-            if next == b"trailer" {
+            if next.as_ref() == b"trailer" {
                 reader.read_bytes(7)?;
                 reader.skip_white_space()?;
                 let _start_dict_token = reader.read_token()?;
@@ -289,6 +1010,9 @@ fn read_xref_table(reader: &PdfReader, start: u32, count: u32) -> Result<HashMap
     let mut objects = HashMap::new();
     for i in 0..count {
         let line = reader.read_bytes(20)?;
+        if line.len() < 18 {
+            return Err(PdfError::Xref("xref table entry is truncated".to_string()));
+        }
         let offset = usize_from_buffer(&line[0..10])?;
         let gen = u16_from_buffer(&line[11..16])?;
         let n = start + i;
@@ -307,53 +1031,25 @@ fn read_xref_table(reader: &PdfReader, start: u32, count: u32) -> Result<HashMap
     Ok(objects)
 }
 
-fn read_xref_v5(reader: PdfReader, offset: PdfNumber) -> Result<Xref> {
-    reader.reset_offset(offset.integer() as usize);
+fn read_xref_stream(reader: &PdfReader, offset: usize) -> Result<PdfStream> {
+    reader.reset_offset(offset);
     let _obj_num = reader.read_token()?;
     let _obj_version = reader.read_token()?;
-    let _obj_start = reader.read_token()?;
-    assert!(_obj_start.is_other_key(b"obj"));
-    let _dict_start = reader.read_token()?;
-    let st = reader.read_stream()?;
-    let mut objects = parse_xref_stream(&reader, &st)?;
-    let trailer = st.dict().to_owned();
-
-    if let Some(prev) = trailer.get("Prev") {
-        let mut prev_offset = prev.integer()?;
-        let mut visited = Vec::new();
-        visited.push(offset.integer());
-        loop {
-            if visited.contains(&prev_offset) {
-                break;
-            }
-            visited.push(prev_offset);
-
-            reader.reset_offset(prev_offset as usize);
-            let _obj_num = reader.read_token()?;
-            let _obj_version = reader.read_token()?;
-            let _obj_start = reader.read_token()?;
-            assert!(_obj_start.is_other_key(b"obj"));
-            let _dict_start = reader.read_token()?;
-            let st = reader.read_stream()?;
-            let pre_objs = parse_xref_stream(&reader, &st)?;
-            let trailer = st.dict().to_owned();
-            objects.extend(pre_objs);
-            if let Some(p) = trailer.get("Prev") {
-                prev_offset = p.integer()?;
-            } else {
-                break;
-            }
-        }
+    let obj_start = reader.read_token()?;
+    if !obj_start.is_other_key(b"obj") {
+        return Err(PdfError::Xref(format!(
+            "xref stream at {} is not an indirect object",
+            offset
+        )));
     }
-    Ok(Xref {
-        reader,
-        objects,
-        trailer,
-        security_handler: None,
-    })
+    let _dict_start = reader.read_token()?;
+    reader.read_stream()
 }
 
-fn parse_xref_stream(reader: &PdfReader, stream: &PdfStream) -> Result<HashMap<u32, ObjectInfo>> {
+fn parse_xref_stream(
+    stream: &PdfStream,
+    diagnostics: Option<&Diagnostics>,
+) -> Result<HashMap<u32, ObjectInfo>> {
     let wobj: &PdfArray = stream
         .get_from_dict("W")
         .ok_or(PdfError::Object("W dos'nt in xref stream".to_string()))?
@@ -381,13 +1077,16 @@ fn parse_xref_stream(reader: &PdfReader, stream: &PdfStream) -> Result<HashMap<u
         None => vec![0, size],
     };
 
-    let buffer = stream.decode_data(None)?;
+    let buffer = stream.decode_data_with(diagnostics)?;
     let mut entries = HashMap::new();
     let mut bptr = 0;
     for v in index.chunks(2) {
         let start = v[0];
         let length = v[1];
         for num in start..(start + length) {
+            if bptr + (w[0] + w[1] + w[2]) as usize > buffer.len() {
+                return Err(PdfError::Xref("xref stream data is truncated".to_string()));
+            }
             let t = if w[0] > 0 {
                 let mut t = 0_u32;
                 for _ in 0..w[0] {
@@ -437,10 +1136,80 @@ fn parse_xref_stream(reader: &PdfReader, stream: &PdfStream) -> Result<HashMap<u
     Ok(entries)
 }
 
+// scan the whole file for `N G obj` headers and trailer dictionaries
+fn scan_objects(reader: &PdfReader) -> Result<(ObjectTable, Vec<(usize, PdfDict)>)> {
+    let mut objects = HashMap::new();
+    let mut trailers = Vec::new();
+    let mut numbers: [Option<(usize, Cow<[u8]>)>; 2] = [None, None];
+    // /Length of the current object, a hint to skip its stream data
+    let mut length = None;
+    let mut after_length = false;
+    reader.reset_offset(0);
+    loop {
+        reader.skip_white_space()?;
+        if reader.is_eof() {
+            break;
+        }
+        let pos = reader.current_pos();
+        let token = match reader.read_token() {
+            Ok(t) => t,
+            Err(e @ PdfError::MissingRange(_, _)) => return Err(e),
+            Err(_) => {
+                reader.reset_offset(pos + 1);
+                numbers = [None, None];
+                continue;
+            }
+        };
+        if after_length {
+            if let Token::Number(buf, false) = &token {
+                length = usize_from_buffer(buf).ok();
+            }
+        }
+        after_length = token.is_other_key(b"Length");
+        match token {
+            Token::Number(buf, false) => {
+                numbers = [numbers[1].take(), Some((pos, buf))];
+                continue;
+            }
+            Token::StartComment if reader.read_line().is_err() => {
+                break;
+            }
+            _ if token.is_other_key(b"obj") => {
+                if let [Some((offset, n)), Some((_, g))] = &numbers {
+                    if let (Ok(n), Ok(g)) = (u32_from_buffer(n), u16_from_buffer(g)) {
+                        objects.insert(n, ObjectInfo::new(*offset, ObjectState::Normal, g));
+                    }
+                }
+                length = None;
+            }
+            // stream data may look like anything, the scan goes on after `endstream`
+            _ if token.is_other_key(b"stream") => match reader.skip_stream_data(length.take()) {
+                Ok(()) => {}
+                Err(e @ PdfError::MissingRange(_, _)) => return Err(e),
+                Err(_) => reader.reset_offset(pos + b"stream".len()),
+            },
+            _ if token.is_other_key(b"trailer") => {
+                reader.skip_white_space()?;
+                if matches!(reader.peek_bytes(2).as_deref(), Ok(b"<<")) {
+                    reader.read_bytes(2)?;
+                    if let Ok(dict) = reader.read_dict() {
+                        trailers.push((pos, dict));
+                    }
+                }
+            }
+            _ => {}
+        }
+        numbers = [None, None];
+    }
+    Ok((objects, trailers))
+}
+
 #[cfg(test)]
 mod tests {
     use super::find_start_xref;
     use super::read_xref_section;
+    use super::Xref;
+    use crate::filter::flate::flate_encode;
     use crate::reader::PdfReader;
     fn new_reader(buffer: &str) -> PdfReader {
         PdfReader::new(buffer.as_bytes().to_vec())
@@ -452,20 +1221,105 @@ mod tests {
         let startxref = find_start_xref(&reader).unwrap();
         reader.reset_offset(startxref);
         let tag = reader.read_bytes(9).unwrap();
-        assert_eq!(tag, "startxref".as_bytes());
+        assert_eq!(tag.as_ref(), "startxref".as_bytes());
     }
 
     #[test]
     fn test_parse_xref_table() {
         let buffer = b"xref\r\n0 6\r\n0000000003 65535 f\r\n0000000017 00000 n\r\n0000000081 00000 n\r\n0000000000 00007 f\r\n0000000331 00000 n\r\n0000000409 00000 n\r\ntrailer<</Root 1 0 R>>";
         let reader = PdfReader::new(buffer.to_vec());
-        let (xref, trailer) = read_xref_section(&reader, 0).unwrap();
-        assert_eq!(xref.get(&(4)).unwrap().offset(), 331_usize);
+        let (xref, _) = read_xref_section(&reader, 0).unwrap();
+        assert_eq!(xref.get(&(4)).unwrap().offset, 331_usize);
 
         let buffer = b"xref\r\n0 1\r\n0000000000 65535 f\r\n3 1\r\n0000025325 00000 n\r\n23 2\r\n0000025518 00002 n\r\n0000025635 00000 n\r\n30 1\r\n0000025777 00000 n\r\ntrailer<</Root 1 0 R>>";
         let reader = PdfReader::new(buffer.to_vec());
-        let (xref_table, trailer) = read_xref_section(&reader, 0).unwrap();
+        let (xref_table, _) = read_xref_section(&reader, 0).unwrap();
         assert_eq!(xref_table.len(), 5);
-        assert_eq!(xref_table.get(&(24)).unwrap().offset(), 25635);
+        assert_eq!(xref_table.get(&(24)).unwrap().offset, 25635);
+    }
+
+    #[test]
+    fn test_rebuild_xref() {
+        let buffer = b"%PDF-1.5\n1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n3 0 obj <</Type /ObjStm /N 1 /First 4 /Length 17>>\nstream\n2 0 <</Count 0>>\nendstream\nendobj\nstartxref\n0\n%%EOF";
+        let reader = PdfReader::new(buffer.to_vec());
+        let xref = Xref::try_rebuild(reader, None).unwrap();
+        assert!(xref.is_reconstructed());
+        assert_eq!(xref.objects_num(), 3);
+        assert!(xref.trailer().get("Root").is_some());
+        let pages = xref.read_indirect_object(&(2, 0)).unwrap();
+        assert_eq!(pages.get_from_dict("Count").unwrap().integer().unwrap(), 0);
+        assert!(xref.read_indirect_object(&(2, 1)).unwrap().is_null());
+        let warnings = xref.diagnostics().warnings();
+        assert_eq!(warnings.last().unwrap().object(), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_rebuild_xref_binary_stream() {
+        let data = flate_encode(&(0..=255u8).cycle().take(2000).collect::<Vec<u8>>()).unwrap();
+        let mut buffer = b"%PDF-1.5\n1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n".to_vec();
+        buffer.extend_from_slice(
+            format!(
+                "3 0 obj <</Filter /FlateDecode /Length {}>>\nstream\n",
+                data.len()
+            )
+            .as_bytes(),
+        );
+        buffer.extend_from_slice(&data);
+        // Length is wrong, the data looks like an object header and unbalanced delimiters
+        buffer.extend_from_slice(b"\nendstream\nendobj\n4 0 obj <</Length 3>>\nstream\n");
+        buffer.extend_from_slice(b"\x00\xff)>9 0 obj (<\x80\nendstream\nendobj\n");
+        buffer.extend_from_slice(b"2 0 obj <</Type /Pages /Count 0 /Kids []>> endobj\n");
+        buffer.extend_from_slice(b"trailer <</Root 1 0 R /Size 5>>\n%%EOF");
+        let reader = PdfReader::new(buffer);
+        let xref = Xref::try_rebuild(reader, None).unwrap();
+        assert_eq!(xref.objects_num(), 4);
+        assert!(xref.read_indirect_object(&(9, 0)).is_err());
+        let pages = xref.read_indirect_object(&(2, 0)).unwrap();
+        assert_eq!(pages.get_from_dict("Count").unwrap().integer().unwrap(), 0);
+        let stream = xref.read_indirect_object(&(3, 0)).unwrap();
+        assert_eq!(
+            stream.as_stream().unwrap().decode_data(None).unwrap().len(),
+            2000
+        );
+    }
+
+    #[test]
+    fn test_object_stream_cache() {
+        let buffer = b"%PDF-1.5\n1 0 obj <</Type /Catalog /Pages 2 0 R>> endobj\n3 0 obj <</Type /ObjStm /N 1 /First 4 /Length 17>>\nstream\n2 0 <</Count 0>>\nendstream\nendobj\nstartxref\n0\n%%EOF";
+        let reader = PdfReader::new(buffer.to_vec());
+        let xref = Xref::try_rebuild(reader, None).unwrap();
+        let pages = xref.read_indirect_object(&(2, 0)).unwrap();
+        assert!(xref.object_streams.lock().unwrap().get(&3).is_some());
+        assert_eq!(
+            xref.objects_cache.lock().unwrap().get(&(2, 0)).cloned(),
+            Some(pages)
+        );
+        assert!(xref.objects_cache.lock().unwrap().get(&(3, 0)).is_none());
+    }
+
+    #[test]
+    fn test_free_and_generation() {
+        let body = "%PDF-1.4\n1 0 obj <</Type /Catalog>> endobj\n2 1 obj (two) endobj\n";
+        let entry = |name: &str| format!("{:010} ", body.find(name).unwrap());
+        let xref_offset = body.len();
+        let buffer = format!(
+            "{}xref\n0 5\n0000000000 65535 f \n{}00000 n \n{}00001 n \n0000000000 00001 f \n{}00000 n \ntrailer <</Size 5 /Root 1 0 R>>\nstartxref\n{}\n%%EOF",
+            body,
+            entry("1 0 obj"),
+            entry("2 1 obj"),
+            entry("1 0 obj"),
+            xref_offset
+        );
+        let xref = Xref::try_new(new_reader(buffer.as_str()), None).unwrap();
+        assert!(xref
+            .read_indirect_object(&(2, 1))
+            .unwrap()
+            .as_literal()
+            .is_ok());
+        assert!(xref.read_indirect_object(&(2, 0)).unwrap().is_null());
+        assert!(xref.read_indirect_object(&(3, 0)).unwrap().is_null());
+        assert!(xref.read_indirect_object(&(3, 1)).unwrap().is_null());
+        // entry of object 4 points to object 1
+        assert!(xref.read_indirect_object(&(4, 0)).is_err());
     }
 }
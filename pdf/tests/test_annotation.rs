@@ -0,0 +1,192 @@
+use pdf::device::Device;
+use pdf::document::Document;
+use pdf::error::Result;
+use pdf::geom::coordinate::Point;
+use pdf::geom::path::Path;
+use pdf::page::annotation::{AnnotationIntent, AnnotationKind, AnnotationSubtype};
+use pdf::page::graphics_state::{FillRule, GraphicsState};
+
+#[test]
+fn test_annotations() {
+    let doc = Document::new_from_file("./tests/resources/annotations.pdf", None).unwrap();
+    let page = doc.get_page(&0).unwrap();
+    let annots = page.annotations().unwrap();
+    // the one without Rect is skipped
+    assert_eq!(annots.len(), 10);
+    let warnings = doc.diagnostics().warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].object(), Some((19, 0)));
+
+    let link = &annots[0];
+    assert_eq!(link.subtype(), &AnnotationSubtype::Link);
+    assert_eq!(link.border().width(), 0.0);
+    assert_eq!(link.quad_points().len(), 1);
+    assert_eq!(link.quad_points()[0][0], Point::new(50.0, 720.0));
+    match link.kind() {
+        AnnotationKind::Link {
+            destination,
+            highlight,
+            ..
+        } => {
+            assert!(destination.is_some());
+            assert_eq!(highlight, "O");
+        }
+        k => panic!("link annotation got {:?}", k),
+    }
+
+    let text = &annots[1];
+    assert_eq!(text.id(), Some((11, 0)));
+    assert_eq!(text.contents(), Some("Check this number"));
+    assert_eq!(text.author(), Some("Alice"));
+    assert_eq!(text.subject(), Some("Comment"));
+    assert_eq!(text.name(), Some("note-1"));
+    assert_eq!(text.modified(), Some("D:20240102030405Z"));
+    assert_eq!(text.color(), Some([1.0, 1.0, 0.0].as_slice()));
+    assert_eq!(text.popup(), Some((12, 0)));
+    assert!(text.is_print());
+    assert!(matches!(
+        text.kind(),
+        AnnotationKind::Text { open: true, icon, .. } if icon == "Comment"
+    ));
+
+    let popup = &annots[2];
+    assert!(popup.is_hidden());
+    assert!(popup.author().is_none());
+    assert!(matches!(
+        popup.kind(),
+        AnnotationKind::Popup {
+            parent: Some((11, 0)),
+            open: false
+        }
+    ));
+
+    let highlight = &annots[3];
+    assert_eq!(highlight.subtype(), &AnnotationSubtype::Highlight);
+    assert_eq!(highlight.kind(), &AnnotationKind::TextMarkup);
+    assert_eq!(highlight.in_reply_to(), Some((11, 0)));
+    assert_eq!(highlight.author(), Some("Bob"));
+
+    let ink = &annots[4];
+    assert_eq!(ink.border().width(), 2.0);
+    assert_eq!(ink.border().style(), "D");
+    assert_eq!(ink.border().dash(), &[2.0, 1.0]);
+    match ink.kind() {
+        AnnotationKind::Ink { ink_list } => {
+            assert_eq!(ink_list.len(), 1);
+            assert_eq!(ink_list[0].len(), 3);
+        }
+        k => panic!("ink annotation got {:?}", k),
+    }
+
+    match annots[5].kind() {
+        AnnotationKind::FreeText {
+            default_appearance,
+            quadding,
+            callout,
+            ..
+        } => {
+            assert_eq!(default_appearance.as_deref(), Some("/Helv 12 Tf 0 g"));
+            assert_eq!(*quadding, 1);
+            assert_eq!(callout.len(), 6);
+        }
+        k => panic!("free text annotation got {:?}", k),
+    }
+
+    assert_eq!(
+        annots[6].kind(),
+        &AnnotationKind::Shape {
+            interior_color: Some(vec![0.0, 0.0, 1.0]),
+            rect_differences: Some([1.0; 4]),
+        }
+    );
+
+    let stamp = &annots[7];
+    assert!(stamp.is_no_view());
+    assert!(stamp.appearance().unwrap().get("N").is_some());
+    assert!(matches!(stamp.kind(), AnnotationKind::Stamp { icon } if icon == "Approved"));
+
+    let widget = &annots[8];
+    assert_eq!(widget.appearance_state(), Some("On"));
+    assert!(widget.author().is_none());
+    match widget.kind() {
+        AnnotationKind::Widget {
+            field_name,
+            field_type,
+            characteristics,
+            ..
+        } => {
+            assert_eq!(field_name.as_deref(), Some("agree"));
+            assert_eq!(field_type.as_deref(), Some("Btn"));
+            assert!(characteristics.is_some());
+        }
+        k => panic!("widget annotation got {:?}", k),
+    }
+
+    let circle = &annots[9];
+    assert_eq!(circle.id(), None);
+    assert_eq!(circle.subtype(), &AnnotationSubtype::Circle);
+    assert_eq!(circle.rect().width(), 50.0);
+}
+
+// ctm of painted paths
+#[derive(Default)]
+struct Paint {
+    fills: Vec<[f32; 6]>,
+    strokes: Vec<[f32; 6]>,
+}
+
+fn ctm(state: &GraphicsState) -> [f32; 6] {
+    let m = &state.ctm;
+    [m.a, m.b, m.c, m.d, m.e, m.f]
+}
+
+impl Device for Paint {
+    fn fill_path(&mut self, _path: &Path, state: &GraphicsState, _rule: FillRule) -> Result<()> {
+        self.fills.push(ctm(state));
+        Ok(())
+    }
+
+    fn stroke_path(&mut self, _path: &Path, state: &GraphicsState) -> Result<()> {
+        self.strokes.push(ctm(state));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_annotation_appearance() {
+    let doc = Document::new_from_file("./tests/resources/annotations.pdf", None).unwrap();
+    let mut page = doc.get_page(&0).unwrap();
+    let mut device = Paint::default();
+    page.display(0, &mut device).unwrap();
+    // only the page content
+    assert_eq!(device.strokes.len(), 1);
+    assert!(device.fills.is_empty());
+
+    // the checked widget, the stamp is NoView
+    page.set_annotation_intent(Some(AnnotationIntent::View));
+    let mut device = Paint::default();
+    page.display(0, &mut device).unwrap();
+    assert_eq!(device.strokes.len(), 2);
+    assert_eq!(device.strokes[1], [1.0, 0.0, 0.0, -1.0, 50.0, 742.0]);
+    assert!(device.fills.is_empty());
+
+    // the stamp BBox [0 0 100 50] is scaled to Rect [400 100 600 200]
+    page.set_annotation_intent(Some(AnnotationIntent::Print));
+    let mut device = Paint::default();
+    page.display(0, &mut device).unwrap();
+    assert_eq!(device.strokes.len(), 2);
+    assert_eq!(device.fills, vec![[2.0, 0.0, 0.0, -2.0, 400.0, 692.0]]);
+}
+
+#[test]
+fn test_annotation_page_resources() {
+    let doc = Document::new_from_file("./tests/resources/annotation_resources.pdf", None).unwrap();
+    let mut page = doc.get_page(&0).unwrap();
+    page.set_annotation_intent(Some(AnnotationIntent::View));
+    let mut device = Paint::default();
+    page.display(0, &mut device).unwrap();
+    // the second appearance paints /Fx of the page resources
+    assert_eq!(device.strokes, vec![[1.0, 0.0, 0.0, -1.0, 100.0, 692.0]]);
+    assert_eq!(device.fills, vec![[1.0, 0.0, 0.0, -1.0, 200.0, 692.0]]);
+    assert!(doc.diagnostics().warnings().is_empty());
+}
@@ -0,0 +1,126 @@
+use pdf::device::trace::Trace;
+use pdf::diagnostics::DiagnosticCategory;
+use pdf::document::Document;
+use pdf::error::PdfError;
+use pdf::source::MemorySource;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_document_send_sync() {
+    assert_send_sync::<Document>();
+}
+
+#[test]
+fn test_display_pages_in_threads() {
+    let doc = Document::new_from_file("./tests/resources/xref_stream.pdf", None).unwrap();
+    let contents: Vec<String> = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                s.spawn(|| {
+                    let page = doc.get_page(&0).unwrap();
+                    let mut device = Trace::new();
+                    page.display(0, &mut device).unwrap();
+                    device.content().to_string()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert!(!contents[0].is_empty());
+    assert!(contents.iter().all(|c| c == &contents[0]));
+}
+
+#[test]
+fn test_stream_without_length() {
+    let doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    let page = doc.get_page(&0).unwrap();
+    let contents = page.content_stream().unwrap();
+    assert!(contents[0].raw_data().ends_with(b"ET"));
+    let warnings = doc.diagnostics().warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].category(), DiagnosticCategory::Stream);
+    assert_eq!(warnings[0].object(), Some((6, 0)));
+
+    let data = std::fs::read("./tests/resources/hello_world.pdf").unwrap();
+    let doc = Document::from_source_strict(MemorySource::new(data), None).unwrap();
+    let page = doc.get_page(&0).unwrap();
+    assert!(page.content_stream().is_err());
+}
+
+#[test]
+fn test_diagnostics() {
+    let doc = Document::new_from_file("./tests/resources/broken_xref.pdf", None).unwrap();
+    let warnings = doc.diagnostics().warnings();
+    assert!(!warnings.is_empty());
+    assert_eq!(warnings[0].category(), DiagnosticCategory::Xref);
+
+    let doc = Document::new_from_file("./tests/resources/xref_stream.pdf", None).unwrap();
+    assert!(doc.diagnostics().warnings().is_empty());
+
+    let data = std::fs::read("./tests/resources/broken_xref.pdf").unwrap();
+    match Document::from_source_strict(MemorySource::new(data), None) {
+        Err(PdfError::Diagnostic(d)) => assert_eq!(d.category(), DiagnosticCategory::Xref),
+        _ => panic!("strict mode should fail on broken xref"),
+    }
+}
+
+#[test]
+fn test_io_error_source() {
+    use std::error::Error;
+
+    let e = match Document::new_from_file("./tests/resources/not_exist.pdf", None) {
+        Err(e) => e,
+        Ok(_) => panic!("open a file not exist should fail"),
+    };
+    assert_eq!(e.kind(), "Io");
+    let source = e.source().unwrap();
+    assert!(source.downcast_ref::<std::io::Error>().is_some());
+}
+
+#[test]
+fn test_info_and_metadata() {
+    let doc = Document::new_from_file("./tests/resources/xref_stream.pdf", None).unwrap();
+    let info = doc.info().unwrap().unwrap();
+    assert_eq!(info.author(), Some("veraPDF Consortium"));
+    assert_eq!(info.creator(), Some("veraPDF Test Builder"));
+    assert_eq!(info.producer(), Some("veraPDF Test Builder 1.0"));
+    assert_eq!(info.title(), None);
+    let created = info.creation_date().unwrap();
+    assert_eq!(created.to_string(), "2023-05-12T08:21:27+03:00");
+
+    let xmp = doc.metadata().unwrap().unwrap();
+    assert_eq!(xmp.creators(), &["veraPDF Consortium".to_string()]);
+    assert_eq!(xmp.creator_tool(), Some("veraPDF Test Builder"));
+    assert_eq!(xmp.producer(), Some("veraPDF Test Builder 1.0"));
+    assert_eq!(xmp.create_date(), Some(created));
+    assert_eq!(
+        xmp.modify_date().map(|d| d.to_string()),
+        info.mod_date().map(|d| d.to_string())
+    );
+
+    let doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    assert!(doc.info().unwrap().is_none());
+    assert!(doc.metadata().unwrap().is_none());
+}
+
+#[test]
+fn test_info_entries_not_string() {
+    let doc = Document::new_from_file("./tests/resources/info.pdf", None).unwrap();
+    let info = doc.info().unwrap().unwrap();
+    assert_eq!(info.title(), None);
+    assert_eq!(info.author(), Some("Jane Roe"));
+    assert_eq!(info.creation_date().unwrap().year(), 2024);
+    let custom: Vec<(&str, &str)> = info
+        .custom()
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(custom, vec![("Company", "ACME")]);
+    // Title, Extra and Pages are skipped
+    let warnings = doc.diagnostics().warnings();
+    assert_eq!(warnings.len(), 3);
+    assert!(warnings
+        .iter()
+        .all(|w| w.category() == DiagnosticCategory::Structure));
+}
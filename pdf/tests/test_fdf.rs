@@ -0,0 +1,127 @@
+use pdf::document::Document;
+use pdf::fdf::{FdfField, FieldValue, FormData};
+
+#[test]
+fn test_export_form_data() {
+    let doc = Document::new_from_file("./tests/resources/form.pdf", None).unwrap();
+    let data = doc.export_form_data(false).unwrap();
+    let fields: Vec<(&str, &FieldValue)> = data
+        .fields()
+        .iter()
+        .map(|f| (f.name(), f.value()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("person.name", &FieldValue::Text("Alice".to_string())),
+            ("agree", &FieldValue::Name("Yes".to_string())),
+            ("color", &FieldValue::Name("Blue".to_string())),
+            ("country", &FieldValue::Text("fr".to_string())),
+        ]
+    );
+    assert!(data.annotations().is_empty());
+
+    let fdf = String::from_utf8_lossy(&data.to_fdf().unwrap()).into_owned();
+    assert!(fdf.starts_with("%FDF-1.2"));
+    assert!(fdf.contains("/Kids [<</T (name) /V (Alice)>>]"));
+    assert_eq!(FormData::from_fdf(data.to_fdf().unwrap()).unwrap(), data);
+
+    let xfdf = data.to_xfdf();
+    assert!(xfdf.contains("<field name=\"person\">"));
+    assert!(xfdf.contains("<value>Blue</value>"));
+}
+
+#[test]
+fn test_export_annotations() {
+    let doc = Document::new_from_file("./tests/resources/annotations.pdf", None).unwrap();
+    let data = doc.export_form_data(true).unwrap();
+    // markup annotations, links popups and widgets are not exported
+    assert_eq!(data.annotations().len(), 7);
+    let text = &data.annotations()[0];
+    assert_eq!(text.page(), 0);
+    assert!(text.dict().get("Popup").is_none());
+
+    let fdf = FormData::from_fdf(data.to_fdf().unwrap()).unwrap();
+    assert_eq!(fdf.annotations(), data.annotations());
+
+    let xfdf = data.to_xfdf();
+    assert!(xfdf.contains(
+        "<text page=\"0\" rect=\"200,700,220,720\" color=\"#FFFF00\" name=\"note-1\" \
+         date=\"D:20240102030405Z\" title=\"Alice\" subject=\"Comment\" flags=\"print\">"
+    ));
+    assert!(xfdf.contains("<contents>Check this number</contents>"));
+    assert!(xfdf.contains("coords=\"50,665,150,665,50,650,150,650\""));
+    assert!(xfdf.contains("<gesture>10,10;20,20;30,10</gesture>"));
+}
+
+#[test]
+fn test_import_form_data() {
+    let xfdf = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xfdf xmlns="http://ns.adobe.com/xfdf/" xml:space="preserve">
+  <f href="form.pdf"/>
+  <fields>
+    <field name="person">
+      <field name="name"><value>Bob</value></field>
+    </field>
+    <field name="agree"><value>Off</value></field>
+    <field name="color"><value>Red</value></field>
+    <field name="missing"><value>x</value></field>
+  </fields>
+</xfdf>"#;
+    let data = FormData::from_xfdf(xfdf).unwrap();
+    assert_eq!(data.file(), Some("form.pdf"));
+
+    let mut doc = Document::new_from_file("./tests/resources/form.pdf", None).unwrap();
+    doc.import_form_data(&data).unwrap();
+    // the missing field is reported
+    let missing = doc
+        .diagnostics()
+        .warnings()
+        .iter()
+        .filter(|w| w.message().contains("field missing"))
+        .count();
+    assert_eq!(missing, 1);
+
+    let mut fdf = FormData::new(None);
+    fdf.add_field(FdfField::new(
+        "country".to_string(),
+        FieldValue::Text("de".to_string()),
+    ));
+    doc.import_form_data(&FormData::from_fdf(fdf.to_fdf().unwrap()).unwrap())
+        .unwrap();
+
+    let form = doc.acro_form().unwrap().unwrap();
+    let value = |name: &str| form.field(name).unwrap().value_text();
+    assert_eq!(value("person.name").as_deref(), Some("Bob"));
+    assert_eq!(value("agree").as_deref(), Some("Off"));
+    assert_eq!(value("color").as_deref(), Some("Red"));
+    assert_eq!(value("country").as_deref(), Some("de"));
+}
+
+#[test]
+fn test_import_several_values() {
+    let mut data = FormData::new(None);
+    data.add_field(FdfField::new(
+        "langs".to_string(),
+        FieldValue::List(vec!["de".to_string(), "en".to_string()]),
+    ));
+    data.add_field(FdfField::new(
+        "size".to_string(),
+        FieldValue::List(vec!["S".to_string(), "M".to_string()]),
+    ));
+    let mut doc = Document::new_from_file("./tests/resources/list_form.pdf", None).unwrap();
+    doc.import_form_data(&data).unwrap();
+    // size is not MultiSelect
+    let warnings = doc.diagnostics().warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].message().contains("size takes only one value"));
+
+    let form = doc.acro_form().unwrap().unwrap();
+    assert!(form.need_appearances());
+    let langs = form.field("langs").unwrap();
+    assert_eq!(langs.value_text().as_deref(), Some("de,en"));
+    let indices = langs.dict().get("I").unwrap().as_array().unwrap();
+    let indices: Vec<i32> = indices.iter().map(|i| i.integer().unwrap()).collect();
+    assert_eq!(indices, vec![0, 2]);
+    assert!(form.field("size").unwrap().value().is_none());
+}
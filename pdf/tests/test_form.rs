@@ -0,0 +1,200 @@
+use pdf::document::Document;
+use pdf::form::FieldType;
+use pdf::object::array::PdfArray;
+use pdf::object::dictionary::PdfDict;
+use pdf::object::number::PdfNumber;
+use pdf::object::PdfObject;
+
+#[test]
+fn test_acro_form() {
+    let doc = Document::new_from_file("./tests/resources/form.pdf", None).unwrap();
+    let form = doc.acro_form().unwrap().unwrap();
+    assert_eq!(form.default_appearance(), Some("/Helv 0 Tf 0 g"));
+    assert!(form.default_resources().is_some());
+    assert!(!form.need_appearances());
+    assert_eq!(form.fields().len(), 5);
+    // the kid link back to the parent is skipped
+    assert_eq!(doc.diagnostics().warnings().len(), 1);
+
+    let names: Vec<&str> = form.terminal_fields().iter().map(|f| f.name()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "person.name",
+            "person.email",
+            "agree",
+            "color",
+            "country",
+            "sig"
+        ]
+    );
+
+    let person = form.field("person").unwrap();
+    assert!(!person.is_terminal());
+    assert_eq!(person.alternate_name(), Some("Person"));
+
+    // type and flags from the parent
+    let name = form.field("person.name").unwrap();
+    assert_eq!(name.partial_name(), Some("name"));
+    assert_eq!(name.field_type(), Some(FieldType::Text));
+    assert!(name.is_required());
+    assert_eq!(name.value_text().as_deref(), Some("Alice"));
+    assert_eq!(name.default_appearance(), Some("/Helv 12 Tf 0 g"));
+    assert_eq!(name.quadding(), 1);
+    assert_eq!(name.max_len(), Some(20));
+    assert_eq!(name.widgets().len(), 1);
+    assert_eq!(name.widgets()[0].page(), Some(0));
+
+    let email = form.field("person.email").unwrap();
+    assert!(email.value().is_none());
+    // MaxLen from the parent
+    assert_eq!(email.max_len(), Some(30));
+    assert_eq!(email.default_appearance(), Some("/Helv 0 Tf 0 g"));
+    assert_eq!(email.widgets()[0].page(), Some(1));
+
+    let agree = form.field("agree").unwrap();
+    assert!(agree.is_checkbox());
+    assert_eq!(agree.value_text().as_deref(), Some("Yes"));
+    assert_eq!(agree.on_states(), vec!["Yes"]);
+
+    let color = form.field("color").unwrap();
+    assert!(color.is_radio());
+    assert!(color.is_terminal());
+    assert_eq!(color.value_text().as_deref(), Some("Blue"));
+    // widgets with the same state are not adjacent
+    assert_eq!(color.widgets().len(), 3);
+    assert_eq!(color.on_states(), vec!["Red", "Blue"]);
+    assert_eq!(color.widgets()[1].appearance_state(), Some("Blue"));
+    assert_eq!(color.widgets()[0].page(), Some(0));
+
+    let country = form.field("country").unwrap();
+    assert_eq!(country.field_type(), Some(FieldType::Choice));
+    assert!(country.is_combo());
+    let options: Vec<(&str, &str)> = country
+        .options()
+        .iter()
+        .map(|o| (o.export(), o.display()))
+        .collect();
+    assert_eq!(
+        options,
+        vec![("fr", "France"), ("de", "Germany"), ("Other", "Other")]
+    );
+    assert_eq!(country.widgets()[0].page(), Some(1));
+
+    let sig = form.field("sig").unwrap();
+    assert_eq!(sig.field_type(), Some(FieldType::Signature));
+    assert!(sig.value().is_none());
+}
+
+#[test]
+fn test_malformed_fields() {
+    let doc = Document::new_from_file("./tests/resources/bad_form.pdf", None).unwrap();
+    let form = doc.acro_form().unwrap().unwrap();
+    let names: Vec<&str> = form.terminal_fields().iter().map(|f| f.name()).collect();
+    assert_eq!(names, vec!["name", "choice.good"]);
+    let skipped: Vec<_> = doc
+        .diagnostics()
+        .warnings()
+        .iter()
+        .map(|d| d.object())
+        .collect();
+    assert_eq!(skipped, vec![Some((61, 0)), Some((62, 0)), Some((65, 0))]);
+}
+
+#[test]
+fn test_no_acro_form() {
+    let doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    assert!(doc.acro_form().unwrap().is_none());
+}
+
+fn appearance_content(doc: &Document, widget: &PdfDict) -> String {
+    let ap = widget.get("AP").unwrap().clone().to_dict().unwrap();
+    let n = match ap.get("N").unwrap() {
+        PdfObject::Indirect((num, gen)) => doc.read_object(*num, *gen).unwrap(),
+        _ => panic!("normal appearance is not indirect"),
+    };
+    let data = n.as_stream().unwrap().decode_data(None).unwrap();
+    String::from_utf8(data).unwrap()
+}
+
+#[test]
+fn test_set_field_value() {
+    let mut doc = Document::new_from_file("./tests/resources/form.pdf", None).unwrap();
+    doc.set_field_value("person.name", "Bob").unwrap();
+    doc.set_field_value("person.email", "bob@example.com")
+        .unwrap();
+    doc.set_field_value("agree", "Off").unwrap();
+    doc.set_field_value("color", "Red").unwrap();
+    doc.set_field_value("country", "de").unwrap();
+
+    let mut out = Vec::new();
+    doc.save_incremental(&mut out).unwrap();
+    let doc = Document::from_bytes(out, None).unwrap();
+    assert_eq!(doc.revisions().len(), 2);
+    let form = doc.acro_form().unwrap().unwrap();
+    assert!(!form.need_appearances());
+
+    let name = form.field("person.name").unwrap();
+    assert_eq!(name.value_text().as_deref(), Some("Bob"));
+    let content = appearance_content(&doc, name.dict());
+    assert!(content.starts_with("/Tx BMC"));
+    assert!(content.contains("/Helv 12 Tf 0 g"));
+    assert!(content.contains("(Bob) Tj"));
+
+    // auto size fits the text in the field
+    let email = form.field("person.email").unwrap();
+    assert_eq!(email.value_text().as_deref(), Some("bob@example.com"));
+    let content = appearance_content(&doc, email.dict());
+    assert!(content.contains("(bob@example.com) Tj"));
+    assert!(!content.contains(" 0 Tf"));
+
+    let agree = form.field("agree").unwrap();
+    assert_eq!(agree.value_text().as_deref(), Some("Off"));
+    assert_eq!(agree.widgets()[0].appearance_state(), Some("Off"));
+
+    let color = form.field("color").unwrap();
+    assert_eq!(color.value_text().as_deref(), Some("Red"));
+    let states: Vec<Option<&str>> = color
+        .widgets()
+        .iter()
+        .map(|w| w.appearance_state())
+        .collect();
+    assert_eq!(states, vec![Some("Red"), Some("Off"), Some("Red")]);
+
+    // the display text of the option is shown
+    let country = form.field("country").unwrap();
+    assert_eq!(country.value_text().as_deref(), Some("de"));
+    assert!(appearance_content(&doc, country.dict()).contains("(Germany) Tj"));
+    assert_eq!(
+        country.dict().get("I"),
+        Some(&PdfObject::Array(PdfArray::new(vec![PdfObject::Number(
+            PdfNumber::Integer(1)
+        )])))
+    );
+}
+
+#[test]
+fn test_set_field_value_error() {
+    let mut doc = Document::new_from_file("./tests/resources/form.pdf", None).unwrap();
+    // longer than MaxLen
+    assert!(doc
+        .set_field_value("person.name", "a name longer than twenty")
+        .is_err());
+    assert!(doc.set_field_value("agree", "Maybe").is_err());
+    assert!(doc.set_field_value("country", "es").is_err());
+    assert!(doc.set_field_value("sig", "x").is_err());
+    assert!(doc.set_field_value("person", "x").is_err());
+    assert!(doc.set_field_value("missing", "x").is_err());
+}
+
+#[test]
+fn test_set_field_value_need_appearances() {
+    let mut doc = Document::new_from_file("./tests/resources/form.pdf", None).unwrap();
+    // Helvetica with WinAnsiEncoding can't draw the text
+    doc.set_field_value("person.name", "\u{4e2d}\u{6587}")
+        .unwrap();
+    let form = doc.acro_form().unwrap().unwrap();
+    assert!(form.need_appearances());
+    let name = form.field("person.name").unwrap();
+    assert_eq!(name.value_text().as_deref(), Some("\u{4e2d}\u{6587}"));
+}
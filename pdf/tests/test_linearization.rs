@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+
+use pdf::device::trace::Trace;
+use pdf::document::Document;
+use pdf::error::{PdfError, Result};
+use pdf::object::PdfObject;
+use pdf::source::{ChunkedSource, MemorySource, RangeFetch};
+
+// bytes of a file in downloading, only the first `available` bytes can be fetched
+#[derive(Clone)]
+struct Download {
+    data: Arc<Vec<u8>>,
+    available: Arc<Mutex<usize>>,
+}
+
+impl RangeFetch for Download {
+    fn fetch(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
+        if offset + len > *self.available.lock().unwrap() {
+            return Err(PdfError::MissingRange(offset, len));
+        }
+        Ok(self.data[offset..offset + len].to_vec())
+    }
+}
+
+#[test]
+fn test_linearized_hints() {
+    let doc = Document::new_from_file("./tests/resources/linearized.pdf", None).unwrap();
+    assert!(doc.is_linearized());
+    assert!(!doc.is_first_page_only());
+    let lin = doc.linearization().unwrap();
+    assert_eq!(lin.first_page_xref_offset(), 131);
+    assert_eq!(lin.first_page_object(), 5);
+    assert_eq!(lin.first_page_end(), 881);
+    assert_eq!(doc.total_page().unwrap(), 2);
+    let hints = doc.page_offset_hints().unwrap();
+    assert_eq!(hints.pages().len(), 2);
+    let first = hints.page(0).unwrap();
+    assert_eq!(first.objects_num(), 3);
+    assert_eq!(first.offset(), 633);
+    assert_eq!(first.length(), 881 - 633);
+    assert_eq!(first.content_offset(), 720);
+    let second = hints.page(1).unwrap();
+    assert_eq!(second.objects_num(), 2);
+    assert_eq!(second.offset(), 881);
+    assert_eq!(second.content_offset(), 968);
+
+    let doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    assert!(!doc.is_linearized());
+    assert!(doc.page_offset_hints().is_err());
+}
+
+#[test]
+fn test_updated_after_linearized() {
+    let mut doc = Document::new_from_file("./tests/resources/linearized.pdf", None).unwrap();
+    doc.add_object(PdfObject::Null);
+    let mut out = Vec::new();
+    doc.save_incremental(&mut out).unwrap();
+    // an incremental update is a normal file state, not a problem even in strict mode
+    let doc = Document::from_source_strict(MemorySource::new(out), None).unwrap();
+    assert!(!doc.is_linearized());
+    assert!(doc.diagnostics().warnings().is_empty());
+    assert_eq!(doc.total_page().unwrap(), 2);
+}
+
+#[test]
+fn test_open_first_page() {
+    let data = std::fs::read("./tests/resources/linearized.pdf").unwrap();
+    let len = data.len();
+    let download = Download {
+        data: Arc::new(data),
+        available: Arc::new(Mutex::new(0)),
+    };
+    let mut fetched = Vec::new();
+    let doc = loop {
+        let source = ChunkedSource::with_chunk_size(download.clone(), len, 1024);
+        match Document::open_first_page(source, None) {
+            Ok(doc) => break doc,
+            Err(PdfError::MissingRange(offset, length)) => {
+                assert!(!fetched.contains(&offset));
+                fetched.push(offset);
+                *download.available.lock().unwrap() = offset + length;
+            }
+            Err(e) => panic!("open first page failed:{:?}", e),
+        }
+    };
+    assert!(*download.available.lock().unwrap() < len / 2);
+    assert!(doc.is_first_page_only());
+    assert_eq!(doc.total_page().unwrap(), 2);
+    let page = doc.get_page(&0).unwrap();
+    let mut device = Trace::new();
+    page.display(0, &mut device).unwrap();
+    assert!(doc.get_page(&1).is_none());
+
+    let mut doc = doc;
+    *download.available.lock().unwrap() = len;
+    doc.load_all(None).unwrap();
+    assert!(!doc.is_first_page_only());
+    assert!(doc.get_page(&1).is_some());
+}
@@ -0,0 +1,113 @@
+use pdf::action::Action;
+use pdf::destination::{Destination, DestinationView};
+use pdf::document::Document;
+use pdf::geom::coordinate::Point;
+
+fn goto(action: Option<&Action>) -> (Option<&Destination>, Option<&str>) {
+    match action {
+        Some(Action::GoTo { destination, name }) => (destination.as_ref(), name.as_deref()),
+        a => panic!("GoTo action got {:?}", a),
+    }
+}
+
+#[test]
+fn test_links() {
+    let doc = Document::new_from_file("./tests/resources/links.pdf", None).unwrap();
+    let links = doc.links(0).unwrap();
+    // the text annotation is not a link
+    assert_eq!(links.len(), 10);
+
+    let uri = &links[0];
+    assert_eq!(uri.id(), Some((10, 0)));
+    assert_eq!(uri.rect().lx(), 10.0);
+    assert_eq!(uri.rect().uy(), 720.0);
+    assert_eq!(uri.quad_points().len(), 1);
+    assert_eq!(uri.quad_points()[0][2], Point::new(10.0, 700.0));
+    assert_eq!(
+        uri.action(),
+        Some(&Action::Uri {
+            uri: "https://example.com/a?b=1".to_string(),
+            is_map: false
+        })
+    );
+
+    let (dest, name) = goto(links[1].action());
+    assert_eq!(name, None);
+    let dest = dest.unwrap();
+    assert_eq!(dest.page(), Some(1));
+    assert_eq!(
+        dest.view(),
+        &DestinationView::Xyz {
+            left: Some(72.0),
+            top: Some(700.0),
+            zoom: None
+        }
+    );
+
+    // /Dest of the link in catalog /Dests
+    let (dest, name) = goto(links[2].action());
+    assert_eq!(name, Some("chap"));
+    assert_eq!(dest.unwrap().page(), Some(2));
+    assert_eq!(dest.unwrap().view(), &DestinationView::Fit);
+
+    // named destination in the name tree
+    let (dest, name) = goto(links[3].action());
+    assert_eq!(name, Some("intro"));
+    assert_eq!(dest.unwrap().page(), Some(0));
+
+    let (dest, name) = goto(links[4].action());
+    assert_eq!(name, Some("missing"));
+    assert!(dest.is_none());
+
+    match links[5].action() {
+        Some(Action::GoToR {
+            file,
+            destination,
+            name,
+            new_window,
+        }) => {
+            assert_eq!(file.as_deref(), Some("other.pdf"));
+            let destination = destination.as_ref().unwrap();
+            assert_eq!(destination.page(), Some(2));
+            assert_eq!(
+                destination.view(),
+                &DestinationView::FitH { top: Some(500.0) }
+            );
+            assert!(name.is_none());
+            assert_eq!(new_window, &Some(true));
+        }
+        a => panic!("GoToR action got {:?}", a),
+    }
+    assert_eq!(
+        links[6].action(),
+        Some(&Action::GoToR {
+            file: Some("doc.pdf".to_string()),
+            destination: None,
+            name: Some("remote".to_string()),
+            new_window: None
+        })
+    );
+    assert_eq!(
+        links[7].action(),
+        Some(&Action::Launch {
+            file: Some("notepad.exe".to_string()),
+            parameters: Some("readme.txt".to_string()),
+            new_window: None
+        })
+    );
+    assert_eq!(
+        links[8].action(),
+        Some(&Action::Named {
+            name: "NextPage".to_string()
+        })
+    );
+    assert_eq!(
+        links[9].action(),
+        Some(&Action::Other {
+            subtype: "JavaScript".to_string()
+        })
+    );
+
+    assert!(doc.links(1).unwrap().is_empty());
+    assert!(doc.links(3).is_err());
+}
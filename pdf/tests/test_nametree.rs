@@ -0,0 +1,59 @@
+use pdf::destination::DestinationView;
+use pdf::document::Document;
+use pdf::object::string::PdfLiteral;
+use pdf::object::PdfObject;
+
+#[test]
+fn test_name_tree() {
+    let doc = Document::new_from_file("./tests/resources/name_tree.pdf", None).unwrap();
+    let dests = doc.name_tree("Dests").unwrap().unwrap();
+    let keys: Vec<Vec<u8>> = dests.iter().map(|e| e.unwrap().0).collect();
+    assert_eq!(
+        keys,
+        vec![
+            b"a".to_vec(),
+            b"b".to_vec(),
+            b"c".to_vec(),
+            b"d".to_vec(),
+            b"f".to_vec()
+        ]
+    );
+    // the kid link back to the root is skipped
+    assert_eq!(doc.diagnostics().warnings().len(), 1);
+
+    assert_eq!(
+        dests.get(&b"b".to_vec()).unwrap(),
+        Some(PdfObject::Indirect((35, 0)))
+    );
+    assert_eq!(dests.get(&b"e".to_vec()).unwrap(), None);
+    assert_eq!(dests.get(&b"z".to_vec()).unwrap(), None);
+    assert!(doc.name_tree("EmbeddedFiles").unwrap().is_none());
+}
+
+#[test]
+fn test_named_destination() {
+    let doc = Document::new_from_file("./tests/resources/name_tree.pdf", None).unwrap();
+    let name = |s: &str| PdfObject::LiteralString(PdfLiteral::new(s.as_bytes().to_vec()));
+
+    let dest = doc.destination(&name("b")).unwrap().unwrap();
+    assert_eq!(dest.page(), Some(1));
+    assert_eq!(
+        dest.view(),
+        &DestinationView::Xyz {
+            left: Some(10.0),
+            top: Some(20.0),
+            zoom: Some(2.0)
+        }
+    );
+    let dest = doc.destination(&name("f")).unwrap().unwrap();
+    assert_eq!(dest.page(), Some(5));
+    assert_eq!(dest.view(), &DestinationView::Fit);
+    assert!(doc.destination(&name("e")).unwrap().is_none());
+
+    // the name is an indirect string
+    let dest = doc
+        .destination(&PdfObject::Indirect((36, 0)))
+        .unwrap()
+        .unwrap();
+    assert_eq!(dest.page(), Some(2));
+}
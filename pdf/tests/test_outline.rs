@@ -0,0 +1,59 @@
+use pdf::destination::DestinationView;
+use pdf::diagnostics::DiagnosticCategory;
+use pdf::document::Document;
+
+#[test]
+fn test_outlines() {
+    let doc = Document::new_from_file("./tests/resources/outline.pdf", None).unwrap();
+    let outlines = doc.outlines().unwrap();
+    // the last item link back to the first one
+    assert_eq!(outlines.len(), 2);
+    let warnings = doc.diagnostics().warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].category(), DiagnosticCategory::Structure);
+    assert_eq!(warnings[0].object(), Some((11, 0)));
+
+    let chapter = &outlines[0];
+    assert_eq!(chapter.title(), "Chapter 1");
+    assert!(chapter.is_open());
+    assert!(chapter.is_bold());
+    assert!(!chapter.is_italic());
+    assert_eq!(chapter.color(), Some([1.0, 0.0, 0.0]));
+    let dest = chapter.destination().unwrap();
+    assert_eq!(dest.page(), Some(0));
+    assert_eq!(
+        dest.view(),
+        &DestinationView::Xyz {
+            left: Some(0.0),
+            top: Some(792.0),
+            zoom: None
+        }
+    );
+
+    // GoTo action with a named destination
+    let section = &chapter.children()[0];
+    assert_eq!(section.title(), "Section 1.1");
+    assert!(section.action().is_some());
+    let dest = section.destination().unwrap();
+    assert_eq!(dest.page(), Some(1));
+    assert_eq!(dest.view(), &DestinationView::FitH { top: Some(500.0) });
+
+    let chapter = &outlines[1];
+    assert_eq!(chapter.title(), "第二章");
+    assert!(!chapter.is_open());
+    assert!(chapter.is_italic());
+    assert_eq!(chapter.color(), None);
+    let dest = chapter.destination().unwrap();
+    assert_eq!(dest.page(), Some(2));
+    assert_eq!(dest.view(), &DestinationView::Fit);
+    let appendix = &chapter.children()[0];
+    assert_eq!(appendix.title(), "Appendix");
+    assert!(appendix.destination().is_none());
+    assert!(appendix.action().unwrap().get("URI").is_some());
+}
+
+#[test]
+fn test_no_outlines() {
+    let doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    assert!(doc.outlines().unwrap().is_empty());
+}
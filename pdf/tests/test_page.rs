@@ -0,0 +1,133 @@
+use pdf::device::trace::Trace;
+use pdf::device::Device;
+use pdf::diagnostics::DiagnosticCategory;
+use pdf::document::Document;
+use pdf::error::Result;
+use pdf::geom::rect::Rect;
+use pdf::page::graphics_state::GraphicsState;
+use pdf::page::PageBox;
+
+#[test]
+fn test_inherited_attributes() {
+    let doc = Document::new_from_file("./tests/resources/inherited.pdf", None).unwrap();
+    assert_eq!(doc.total_page().unwrap(), 3);
+
+    // everything from ancestors, the nearest one win
+    let page = doc.get_page(&0).unwrap();
+    let mediabox = page.mediabox().unwrap();
+    assert_eq!((mediabox.width(), mediabox.height()), (300.0, 400.0));
+    let cropbox = page.cropbox().unwrap().unwrap();
+    assert_eq!((cropbox.lx(), cropbox.uy()), (10.0, 390.0));
+    assert_eq!(page.rotated().unwrap(), 270);
+
+    let page = doc.get_page(&1).unwrap();
+    let mediabox = page.mediabox().unwrap();
+    assert_eq!((mediabox.width(), mediabox.height()), (200.0, 200.0));
+    // Rotate written as a real number
+    assert_eq!(page.rotated().unwrap(), 180);
+
+    let page = doc.get_page(&2).unwrap();
+    assert!(page.cropbox().unwrap().is_none());
+    assert_eq!(page.rotated().unwrap(), 90);
+
+    for i in 0..3 {
+        let page = doc.get_page(&i).unwrap();
+        let mut device = Trace::new();
+        page.display(i, &mut device).unwrap();
+    }
+    assert!(doc.diagnostics().warnings().is_empty());
+}
+
+// size and ctm translation of the page from start_page
+#[derive(Default)]
+struct PageSize {
+    size: (f32, f32),
+    origin: (f32, f32),
+}
+
+impl Device for PageSize {
+    fn start_page(
+        &mut self,
+        state: &GraphicsState,
+        _page_num: u32,
+        width: f32,
+        height: f32,
+    ) -> Result<()> {
+        self.size = (width, height);
+        self.origin = (state.ctm.e, state.ctm.f);
+        Ok(())
+    }
+}
+
+fn rect(r: Rect) -> (f32, f32, f32, f32) {
+    (r.lx(), r.ly(), r.ux(), r.uy())
+}
+
+#[test]
+fn test_page_boxes() {
+    let doc = Document::new_from_file("./tests/resources/page_boxes.pdf", None).unwrap();
+    let mut page = doc.get_page(&0).unwrap();
+    assert_eq!(
+        rect(page.page_box(PageBox::MediaBox).unwrap()),
+        (0.0, 0.0, 600.0, 800.0)
+    );
+    assert_eq!(
+        rect(page.page_box(PageBox::CropBox).unwrap()),
+        (0.0, 0.0, 500.0, 700.0)
+    );
+    assert_eq!(
+        rect(page.page_box(PageBox::BleedBox).unwrap()),
+        (10.0, 10.0, 490.0, 690.0)
+    );
+    // TrimBox default to CropBox
+    assert_eq!(
+        rect(page.page_box(PageBox::TrimBox).unwrap()),
+        (0.0, 0.0, 500.0, 700.0)
+    );
+    assert_eq!(
+        rect(page.page_box(PageBox::ArtBox).unwrap()),
+        (100.0, 100.0, 600.0, 800.0)
+    );
+    assert_eq!(page.user_unit().unwrap(), 2.0);
+
+    let mut device = PageSize::default();
+    page.display(0, &mut device).unwrap();
+    assert_eq!(device.size, (1000.0, 1400.0));
+    assert_eq!(device.origin, (0.0, 1400.0));
+
+    // the upper left corner (10, 690) of BleedBox is the device origin
+    page.set_display_box(PageBox::BleedBox);
+    page.display(0, &mut device).unwrap();
+    assert_eq!(device.size, (960.0, 1360.0));
+    assert_eq!(device.origin, (-20.0, 1380.0));
+}
+
+#[test]
+fn test_page_tree_cycle() {
+    let doc = Document::new_from_file("./tests/resources/page_tree_cycle.pdf", None).unwrap();
+    assert_eq!(doc.total_page().unwrap(), 2);
+    assert!(doc.get_page(&0).is_some());
+    assert!(doc.get_page(&1).is_some());
+    assert!(doc.get_page(&2).is_none());
+    // the link back to the root and the second link to page 3
+    let warnings = doc.diagnostics().warnings();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].object(), Some((2, 0)));
+    assert_eq!(warnings[1].object(), Some((3, 0)));
+}
+
+#[test]
+fn test_content_error_diagnostic() {
+    let doc = Document::new_from_file("./tests/resources/content_error.pdf", None).unwrap();
+    let mut device = PageSize::default();
+    doc.get_page(&0).unwrap().display(0, &mut device).unwrap();
+    assert!(doc.diagnostics().warnings().is_empty());
+
+    doc.get_page(&1).unwrap().display(1, &mut device).unwrap();
+    let warnings = doc.diagnostics().warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].category(), DiagnosticCategory::Content);
+    assert_eq!(warnings[0].page(), Some(1));
+    assert_eq!(warnings[0].operator(), Some("w"));
+    assert_eq!(warnings[0].error_kind(), Some("Interpreter"));
+}
@@ -0,0 +1,28 @@
+use pdf::document::Document;
+use pdf::pagelabel::PageLabelStyle;
+
+#[test]
+fn test_page_labels() {
+    let doc = Document::new_from_file("./tests/resources/name_tree.pdf", None).unwrap();
+    let labels: Vec<String> = (0..6).map(|i| doc.page_label(i).unwrap()).collect();
+    assert_eq!(labels, vec!["i", "ii", "1", "2", "A-A", "Index"]);
+
+    let ranges = doc.page_labels().unwrap().unwrap();
+    assert_eq!(ranges.ranges().len(), 4);
+    assert_eq!(ranges.ranges()[0].style(), Some(PageLabelStyle::LowerRoman));
+    assert_eq!(ranges.ranges()[2].prefix(), "A-");
+    assert_eq!(ranges.ranges()[3].style(), None);
+
+    assert_eq!(doc.page_index_of_label("ii").unwrap(), Some(1));
+    assert_eq!(doc.page_index_of_label("A-A").unwrap(), Some(4));
+    assert_eq!(doc.page_index_of_label("3").unwrap(), None);
+}
+
+#[test]
+fn test_no_page_labels() {
+    let doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    assert!(doc.page_labels().unwrap().is_none());
+    assert_eq!(doc.page_label(0).unwrap(), "1");
+    assert_eq!(doc.page_index_of_label("1").unwrap(), Some(0));
+    assert_eq!(doc.page_index_of_label("2").unwrap(), None);
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use pdf::device::trace::Trace;
+use pdf::document::Document;
+use pdf::object::dictionary::PdfDict;
+use pdf::object::name::PdfName;
+use pdf::object::PdfObject;
+
+fn name(s: &str) -> PdfObject {
+    PdfObject::Name(PdfName::new(s.to_string()))
+}
+
+fn incremental_update(path: &str) {
+    let data = std::fs::read(path).unwrap();
+    let mut doc = Document::from_bytes(data.clone(), None).unwrap();
+    let revisions = doc.revisions().len();
+    let xref_type = doc.revisions()[revisions - 1]
+        .trailer()
+        .get("Type")
+        .cloned();
+
+    let font = PdfObject::Dict(PdfDict::new(HashMap::from([
+        ("Type".to_string(), name("Font")),
+        ("Subtype".to_string(), name("Type1")),
+        ("BaseFont".to_string(), name("Courier")),
+    ])));
+    let id = doc.add_object(font);
+    let mut catalog = doc.read_object(1, 0).unwrap().to_dict().unwrap();
+    catalog.insert("PageMode", name("UseOutlines"));
+    doc.set_object((1, 0), PdfObject::Dict(catalog)).unwrap();
+    assert_eq!(
+        doc.read_object(id.0, id.1)
+            .unwrap()
+            .get_from_dict("BaseFont"),
+        Some(&name("Courier"))
+    );
+
+    let mut out = Vec::new();
+    doc.save_incremental(&mut out).unwrap();
+    assert!(out.starts_with(data.as_slice()));
+
+    let saved = Document::from_bytes(out, None).unwrap();
+    assert!(!saved.is_repaired());
+    assert_eq!(saved.revisions().len(), revisions + 1);
+    assert_eq!(
+        saved.revisions()[revisions].trailer().get("Type"),
+        xref_type.as_ref()
+    );
+    let catalog = saved.read_object(1, 0).unwrap();
+    assert_eq!(
+        catalog.get_from_dict("PageMode"),
+        Some(&name("UseOutlines"))
+    );
+    let font = saved.read_object(id.0, id.1).unwrap();
+    assert_eq!(font.get_from_dict("BaseFont"), Some(&name("Courier")));
+    let page = saved.get_page(&0).unwrap();
+    let mut device = Trace::new();
+    page.display(0, &mut device).unwrap();
+}
+
+#[test]
+fn test_save_incremental_xref_table() {
+    incremental_update("./tests/resources/hello_world.pdf");
+
+    let mut doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    doc.delete_object((5, 0)).unwrap();
+    assert!(doc.delete_object((5, 0)).is_err());
+    let mut out = Vec::new();
+    doc.save_incremental(&mut out).unwrap();
+    // entry 0 is the head of the free list and links to the deleted object 5
+    let text = String::from_utf8_lossy(&out).into_owned();
+    let section = &text[text.rfind("\nxref\n").unwrap() + 1..];
+    assert!(section.starts_with("xref\n0 1\n0000000005 65535 f\r\n5 1\n0000000000 00001 f\r\n"));
+    let saved = Document::from_bytes(out, None).unwrap();
+    assert!(saved.read_object(5, 0).unwrap().is_null());
+    assert!(!saved.read_object(4, 0).unwrap().is_null());
+}
+
+#[test]
+fn test_save_incremental_xref_stream() {
+    incremental_update("./tests/resources/xref_stream.pdf");
+}
+
+fn trailer_id(doc: &Document) -> Vec<PdfObject> {
+    let trailer = doc.revisions().last().unwrap().trailer();
+    match trailer.get("ID") {
+        Some(PdfObject::Array(id)) => id.iter().cloned().collect(),
+        _ => panic!("no ID in trailer"),
+    }
+}
+
+#[test]
+fn test_save_file_id() {
+    let doc = Document::new_from_file("./tests/resources/xref_stream.pdf", None).unwrap();
+    let id = trailer_id(&doc);
+    let mut out = Vec::new();
+    doc.save_incremental(&mut out).unwrap();
+    let saved = trailer_id(&Document::from_bytes(out, None).unwrap());
+    assert_eq!(saved[0], id[0]);
+    assert_ne!(saved[1], id[1]);
+
+    let mut out = Vec::new();
+    doc.save_full(&mut out, false).unwrap();
+    let saved = trailer_id(&Document::from_bytes(out, None).unwrap());
+    assert_eq!(saved[0], id[0]);
+    assert_ne!(saved[1], id[1]);
+
+    // document without ID gets a new one
+    let doc = Document::new_from_file("./tests/resources/hello_world.pdf", None).unwrap();
+    let mut out = Vec::new();
+    doc.save_full(&mut out, false).unwrap();
+    let text = String::from_utf8_lossy(&out).into_owned();
+    let section = &text[text.rfind("\nxref\n").unwrap() + 1..];
+    assert_eq!(section.lines().nth(2), Some("0000000000 65535 f"));
+    let saved = trailer_id(&Document::from_bytes(out, None).unwrap());
+    assert_eq!(saved.len(), 2);
+}
+
+fn full_save(path: &str, object_streams: bool) {
+    let mut doc = Document::new_from_file(path, None).unwrap();
+    let pages = doc.total_page().unwrap();
+    // not referenced by any object, dropped by save_full
+    doc.add_object(name("Unused"));
+    let mut out = Vec::new();
+    doc.save_full(&mut out, object_streams).unwrap();
+    let has_object_stream = out.windows(13).any(|w| w == b"/Type /ObjStm");
+    assert_eq!(has_object_stream, object_streams);
+
+    let saved = Document::from_bytes(out, None).unwrap();
+    assert!(!saved.is_repaired());
+    assert!(saved.diagnostics().warnings().is_empty());
+    assert_eq!(saved.total_page().unwrap(), pages);
+    let catalog = saved.read_object(1, 0).unwrap();
+    assert_eq!(catalog.get_from_dict("Type"), Some(&name("Catalog")));
+    if !object_streams {
+        assert!(saved.objects_num() <= doc.objects_num());
+    }
+    for num in 1..saved.objects_num() as u32 {
+        assert_ne!(saved.read_object(num, 0).unwrap(), name("Unused"));
+    }
+    for i in 0..pages {
+        let page = saved.get_page(&i).unwrap();
+        let mut device = Trace::new();
+        page.display(i, &mut device).unwrap();
+    }
+}
+
+#[test]
+fn test_save_full() {
+    full_save("./tests/resources/hello_world.pdf", false);
+    full_save("./tests/resources/hello_world.pdf", true);
+    full_save("./tests/resources/xref_stream.pdf", false);
+    full_save("./tests/resources/xref_stream.pdf", true);
+    full_save("./tests/resources/incremental.pdf", true);
+}
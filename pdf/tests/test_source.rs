@@ -0,0 +1,20 @@
+use std::fs::File;
+
+use pdf::document::Document;
+use pdf::source::ChunkedSource;
+
+#[test]
+fn test_document_from_bytes() {
+    let data = std::fs::read("./tests/resources/xref_stream.pdf").unwrap();
+    let doc = Document::from_bytes(data, None).unwrap();
+    assert_eq!(doc.objects_num(), 22);
+}
+
+#[test]
+fn test_document_from_read_seek() {
+    let f = File::open("./tests/resources/xref_stream.pdf").unwrap();
+    let source = ChunkedSource::from_reader(f).unwrap();
+    let doc = Document::from_source(source, None).unwrap();
+    assert_eq!(doc.objects_num(), 22);
+    assert_eq!(doc.total_page().unwrap(), 1);
+}
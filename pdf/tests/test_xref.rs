@@ -18,3 +18,38 @@ fn test_xref_stream() {
     let doc = document::Document::new_from_file("./tests/resources/xref_stream.pdf", None).unwrap();
     assert_eq!(doc.objects_num(), 22);
 }
+
+#[test]
+fn test_xref_rebuild() {
+    let doc = document::Document::new_from_file("./tests/resources/broken_xref.pdf", None).unwrap();
+    assert!(doc.is_repaired());
+    assert_eq!(doc.objects_num(), 6);
+    assert_eq!(doc.total_page().unwrap(), 1);
+    let font = doc.read_object(5, 0).unwrap();
+    assert!(font.get_from_dict("BaseFont").is_some());
+}
+
+#[test]
+fn test_xref_incremental_update() {
+    let mut doc =
+        document::Document::new_from_file("./tests/resources/incremental.pdf", None).unwrap();
+    assert_eq!(doc.revisions().len(), 2);
+    assert_eq!(doc.revision(), 1);
+    let font = doc.read_object(4, 0).unwrap();
+    let name = font.get_from_dict("BaseFont").unwrap().as_name().unwrap();
+    assert_eq!(name.name(), "Courier");
+
+    doc.set_revision(0).unwrap();
+    let font = doc.read_object(4, 0).unwrap();
+    let name = font.get_from_dict("BaseFont").unwrap().as_name().unwrap();
+    assert_eq!(name.name(), "Times-Roman");
+}
+
+#[test]
+fn test_xref_hybrid_reference() {
+    let doc = document::Document::new_from_file("./tests/resources/hybrid_xref.pdf", None).unwrap();
+    assert!(!doc.is_repaired());
+    let font = doc.read_object(5, 0).unwrap();
+    let name = font.get_from_dict("BaseFont").unwrap().as_name().unwrap();
+    assert_eq!(name.name(), "Helvetica");
+}